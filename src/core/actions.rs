@@ -0,0 +1,476 @@
+use std::{fmt, fs, path::Path, sync::Mutex};
+
+use minifb::{Key, MouseButton};
+use once_cell::sync::Lazy;
+
+use crate::core::input;
+
+// Named actions on top of core::input. Bindings use a line based text format:
+//
+//   # comment
+//   button jump = Space | MouseRight
+//   button save = Ctrl+S
+//   axis move_forward = S/W
+//   axis look_yaw = MouseX*-0.003 | Right/Left*1.5
+//
+// A chord is inputs joined by `+` that all have to be held, Ctrl/Shift/Alt match either side.
+// Axis sources are `negative/positive` chord pairs, a single chord (1 while held) or
+// MouseX, MouseY, ScrollX, ScrollY, each with an optional `*scale`. Sources are summed.
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InputSource {
+    Key(Key),
+    Mouse(MouseButton),
+    Modifier(Modifier),
+}
+
+impl InputSource {
+    fn is_pressed(self) -> bool {
+        match self {
+            InputSource::Key(key) => input::is_pressed(key),
+            InputSource::Mouse(button) => input::is_mouse_pressed(button),
+            InputSource::Modifier(m) => modifier_keys(m).iter().any(|k| input::is_pressed(*k)),
+        }
+    }
+
+    fn is_down(self) -> bool {
+        match self {
+            InputSource::Key(key) => input::is_down(key),
+            InputSource::Mouse(button) => input::is_mouse_down(button),
+            // Down when the first of both keys goes down
+            InputSource::Modifier(m) => {
+                let keys = modifier_keys(m);
+                keys.iter().any(|k| input::is_down(*k)) && !keys.iter().any(|k| input::is_pressed(*k) && !input::is_down(*k))
+            }
+        }
+    }
+
+    fn was_pressed(self) -> bool {
+        match self {
+            InputSource::Key(key) => (input::is_pressed(key) && !input::is_down(key)) || input::is_up(key),
+            InputSource::Mouse(button) => (input::is_mouse_pressed(button) && !input::is_mouse_down(button)) || input::is_mouse_up(button),
+            InputSource::Modifier(m) => modifier_keys(m).iter().any(|k| InputSource::Key(*k).was_pressed()),
+        }
+    }
+}
+
+fn modifier_keys(modifier: Modifier) -> [Key; 2] {
+    match modifier {
+        Modifier::Ctrl => [Key::LeftCtrl, Key::RightCtrl],
+        Modifier::Shift => [Key::LeftShift, Key::RightShift],
+        Modifier::Alt => [Key::LeftAlt, Key::RightAlt],
+    }
+}
+
+// Inputs that all have to be held, like Ctrl+S
+#[derive(Clone, PartialEq, Debug)]
+pub struct Chord {
+    pub inputs: Vec<InputSource>,
+}
+
+impl Chord {
+    pub fn new(inputs: Vec<InputSource>) -> Self {
+        Self { inputs }
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        !self.inputs.is_empty() && self.inputs.iter().all(|i| i.is_pressed())
+    }
+
+    // Completed this frame: everything held and at least one input just went down
+    pub fn is_down(&self) -> bool {
+        self.is_pressed() && self.inputs.iter().any(|i| i.is_down())
+    }
+
+    pub fn is_up(&self) -> bool {
+        !self.inputs.is_empty() && self.inputs.iter().all(|i| i.was_pressed()) && !self.is_pressed()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MouseAxis {
+    X,
+    Y,
+    ScrollX,
+    ScrollY,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum AxisSource {
+    Chords { negative: Chord, positive: Chord },
+    Chord(Chord),
+    Mouse(MouseAxis),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AxisBinding {
+    pub source: AxisSource,
+    pub scale: f32,
+}
+
+impl AxisBinding {
+    pub fn new(source: AxisSource, scale: f32) -> Self {
+        Self { source, scale }
+    }
+
+    fn value(&self) -> f32 {
+        let value = match &self.source {
+            AxisSource::Chords { negative, positive } => positive.is_pressed() as i32 as f32 - negative.is_pressed() as i32 as f32,
+            AxisSource::Chord(chord) => chord.is_pressed() as i32 as f32,
+            AxisSource::Mouse(MouseAxis::X) => input::mouse_delta().x,
+            AxisSource::Mouse(MouseAxis::Y) => input::mouse_delta().y,
+            AxisSource::Mouse(MouseAxis::ScrollX) => input::scroll_delta().x,
+            AxisSource::Mouse(MouseAxis::ScrollY) => input::scroll_delta().y,
+        };
+        value * self.scale
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Binding {
+    Button(Vec<Chord>),
+    Axis(Vec<AxisBinding>),
+}
+
+// Actions keep their insertion order, so a saved config reads like the loaded one
+#[derive(Default, Clone, Debug)]
+pub struct ActionMap {
+    actions: Vec<(String, Binding)>,
+}
+
+impl ActionMap {
+    pub fn get(&self, name: &str) -> Option<&Binding> {
+        self.actions.iter().find(|(n, _)| n == name).map(|(_, b)| b)
+    }
+
+    pub fn set(&mut self, name: &str, binding: Binding) {
+        match self.actions.iter_mut().find(|(n, _)| n == name) {
+            Some((_, b)) => *b = binding,
+            None => self.actions.push((name.to_string(), binding)),
+        }
+    }
+
+    // Later lines override earlier bindings of the same action
+    pub fn parse(text: &str) -> Result<ActionMap, String> {
+        let mut map = ActionMap::default();
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let error = |message: String| format!("line {}: {message}", line_number + 1);
+            let (head, sources) = line.split_once('=').ok_or_else(|| error("expected `=`".to_string()))?;
+            let (kind, name) = head.trim().split_once(char::is_whitespace).ok_or_else(|| error("expected `button <name>` or `axis <name>`".to_string()))?;
+            let sources = sources.split('|').map(str::trim).filter(|s| !s.is_empty());
+
+            let binding = match kind {
+                "button" => Binding::Button(sources.map(parse_chord).collect::<Result<_, _>>().map_err(error)?),
+                "axis" => Binding::Axis(sources.map(parse_axis).collect::<Result<_, _>>().map_err(error)?),
+                other => return Err(error(format!("unknown binding kind `{other}`"))),
+            };
+            map.set(name.trim(), binding);
+        }
+
+        Ok(map)
+    }
+}
+
+impl fmt::Display for ActionMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, binding) in &self.actions {
+            match binding {
+                Binding::Button(chords) => {
+                    let chords: Vec<String> = chords.iter().map(chord_to_string).collect();
+                    writeln!(f, "button {name} = {}", chords.join(" | "))?;
+                }
+                Binding::Axis(axes) => {
+                    let axes: Vec<String> = axes.iter().map(axis_to_string).collect();
+                    writeln!(f, "axis {name} = {}", axes.join(" | "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_input(name: &str) -> Result<InputSource, String> {
+    let source = match name.to_ascii_lowercase().as_str() {
+        "ctrl" => InputSource::Modifier(Modifier::Ctrl),
+        "shift" => InputSource::Modifier(Modifier::Shift),
+        "alt" => InputSource::Modifier(Modifier::Alt),
+        "mouseleft" => InputSource::Mouse(MouseButton::Left),
+        "mousemiddle" => InputSource::Mouse(MouseButton::Middle),
+        "mouseright" => InputSource::Mouse(MouseButton::Right),
+        _ => InputSource::Key(input::key_from_name(name).ok_or_else(|| format!("unknown input `{name}`"))?),
+    };
+    Ok(source)
+}
+
+fn parse_chord(text: &str) -> Result<Chord, String> {
+    Ok(Chord::new(text.split('+').map(|s| parse_input(s.trim())).collect::<Result<_, _>>()?))
+}
+
+fn parse_axis(text: &str) -> Result<AxisBinding, String> {
+    let (source, scale) = match text.rsplit_once('*') {
+        Some((source, scale)) => (source.trim(), scale.trim().parse::<f32>().map_err(|_| format!("invalid scale `{scale}`"))?),
+        None => (text, 1.0),
+    };
+
+    let source = match source.to_ascii_lowercase().as_str() {
+        "mousex" => AxisSource::Mouse(MouseAxis::X),
+        "mousey" => AxisSource::Mouse(MouseAxis::Y),
+        "scrollx" => AxisSource::Mouse(MouseAxis::ScrollX),
+        "scrolly" => AxisSource::Mouse(MouseAxis::ScrollY),
+        _ => match source.split_once('/') {
+            Some((negative, positive)) => AxisSource::Chords { negative: parse_chord(negative.trim())?, positive: parse_chord(positive.trim())? },
+            None => AxisSource::Chord(parse_chord(source)?),
+        },
+    };
+    Ok(AxisBinding::new(source, scale))
+}
+
+fn input_to_string(input: &InputSource) -> String {
+    match input {
+        InputSource::Key(key) => format!("{key:?}"),
+        InputSource::Mouse(button) => format!("Mouse{button:?}"),
+        InputSource::Modifier(m) => format!("{m:?}"),
+    }
+}
+
+fn chord_to_string(chord: &Chord) -> String {
+    chord.inputs.iter().map(input_to_string).collect::<Vec<_>>().join("+")
+}
+
+fn axis_to_string(axis: &AxisBinding) -> String {
+    let source = match &axis.source {
+        AxisSource::Chords { negative, positive } => format!("{}/{}", chord_to_string(negative), chord_to_string(positive)),
+        AxisSource::Chord(chord) => chord_to_string(chord),
+        AxisSource::Mouse(MouseAxis::X) => "MouseX".to_string(),
+        AxisSource::Mouse(MouseAxis::Y) => "MouseY".to_string(),
+        AxisSource::Mouse(MouseAxis::ScrollX) => "ScrollX".to_string(),
+        AxisSource::Mouse(MouseAxis::ScrollY) => "ScrollY".to_string(),
+    };
+    if axis.scale == 1.0 { source } else { format!("{source}*{}", axis.scale) }
+}
+
+static ACTIONS: Lazy<Mutex<ActionMap>> = Lazy::new(|| { Mutex::new(ActionMap::default()) });
+
+// Adds or replaces the bindings from a config file
+pub fn load(path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).expect("Failed to read bindings file");
+    let loaded = ActionMap::parse(&text).unwrap_or_else(|e| panic!("Failed to parse bindings file {}: {e}", path.display()));
+
+    let mut actions = ACTIONS.lock().unwrap();
+    for (name, binding) in loaded.actions {
+        actions.set(&name, binding);
+    }
+}
+
+pub fn save(path: impl AsRef<Path>) {
+    fs::write(path, ACTIONS.lock().unwrap().to_string()).expect("Failed to write bindings file");
+}
+
+#[allow(dead_code)]
+pub fn bind(name: &str, binding: Binding) {
+    ACTIONS.lock().unwrap().set(name, binding);
+}
+
+pub fn binding(name: &str) -> Option<Binding> {
+    ACTIONS.lock().unwrap().get(name).cloned()
+}
+
+// For rebinding at runtime: the held modifiers plus the key or button that went down this frame
+#[allow(dead_code)]
+pub fn pressed_chord() -> Option<Chord> {
+    let mut inputs: Vec<InputSource> = [Modifier::Ctrl, Modifier::Shift, Modifier::Alt]
+        .into_iter()
+        .map(InputSource::Modifier)
+        .filter(|m| m.is_pressed())
+        .collect();
+
+    let modifier_key = |k: &Key| [Modifier::Ctrl, Modifier::Shift, Modifier::Alt].iter().any(|m| modifier_keys(*m).contains(k));
+    let trigger = input::ALL_KEYS.iter().copied().filter(|k| !modifier_key(k)).map(InputSource::Key)
+        .chain([MouseButton::Left, MouseButton::Middle, MouseButton::Right].map(InputSource::Mouse))
+        .find(|i| i.is_down())?;
+
+    inputs.push(trigger);
+    Some(Chord::new(inputs))
+}
+
+// Button actions
+#[allow(dead_code)]
+pub fn is_pressed(name: &str) -> bool {
+    match binding(name) {
+        Some(Binding::Button(chords)) => chords.iter().any(Chord::is_pressed),
+        _ => false,
+    }
+}
+
+pub fn is_down(name: &str) -> bool {
+    match binding(name) {
+        Some(Binding::Button(chords)) => chords.iter().any(Chord::is_down),
+        _ => false,
+    }
+}
+
+pub fn is_up(name: &str) -> bool {
+    match binding(name) {
+        Some(Binding::Button(chords)) => chords.iter().any(Chord::is_up) && !chords.iter().any(Chord::is_pressed),
+        _ => false,
+    }
+}
+
+// Axis actions. Button actions read as 0 or 1.
+#[allow(dead_code)]
+pub fn axis(name: &str) -> f32 {
+    match binding(name) {
+        Some(Binding::Axis(axes)) => axes.iter().map(AxisBinding::value).sum(),
+        Some(Binding::Button(chords)) => chords.iter().any(Chord::is_pressed) as i32 as f32,
+        None => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::input::RawInput;
+    use crate::math::numerics::float2::Float2;
+
+    fn frame(keys: &[Key], mouse_buttons: [bool; 3]) {
+        input::apply(RawInput { keys: keys.iter().copied().collect(), mouse_buttons, ..Default::default() });
+    }
+
+    fn keys(keys: &[Key]) {
+        frame(keys, [false; 3]);
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        let error = |text: &str| ActionMap::parse(text).unwrap_err();
+
+        assert_eq!(error("# bindings\n\nbutton jump Space"), "line 3: expected `=`");
+        assert_eq!(error("button jump = Space\nbutton = Space"), "line 2: expected `button <name>` or `axis <name>`");
+        assert_eq!(error("trigger jump = Space"), "line 1: unknown binding kind `trigger`");
+        assert_eq!(error("button jump = Space\nbutton save = Ctrl+Sss"), "line 2: unknown input `Sss`");
+        assert_eq!(error("axis move = A/D\naxis look = MouseX*fast"), "line 2: invalid scale `fast`");
+        assert_eq!(error("axis move = A/Nope"), "line 1: unknown input `Nope`");
+    }
+
+    #[test]
+    fn parse_reads_comments_and_overrides() {
+        let map = ActionMap::parse("button jump = Space # jump\n  # only a comment\nbutton jump = J | MouseRight\n").unwrap();
+        assert_eq!(map.actions.len(), 1);
+        assert_eq!(map.get("jump"), Some(&Binding::Button(vec![Chord::new(vec![InputSource::Key(Key::J)]), Chord::new(vec![InputSource::Mouse(MouseButton::Right)])])));
+
+        // Names and modifiers are case-insensitive
+        let map = ActionMap::parse("button save = ctrl+s").unwrap();
+        assert_eq!(map.get("save"), Some(&Binding::Button(vec![Chord::new(vec![InputSource::Modifier(Modifier::Ctrl), InputSource::Key(Key::S)])])));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let text = "\
+button jump = Space | MouseRight
+button save = Ctrl+S | Shift+Alt+MouseLeft
+axis move_forward = S/W
+axis look_yaw = MouseX*-0.003 | Right/Left*1.5
+axis zoom = ScrollY*0.25 | ScrollX | MouseY*2
+axis sprint = Shift+W | Ctrl+Q/Ctrl+E*-1
+";
+        let map = ActionMap::parse(text).unwrap();
+        assert_eq!(map.to_string(), text);
+        assert_eq!(ActionMap::parse(&map.to_string()).unwrap().actions, map.actions);
+    }
+
+    #[test]
+    fn chords_and_modifiers_fire_once() {
+        let _lock = input::lock_for_test();
+        input::reset();
+        let save = Chord::new(vec![InputSource::Modifier(Modifier::Ctrl), InputSource::Key(Key::S)]);
+
+        // Completes on whichever input goes down last
+        keys(&[Key::LeftCtrl]);
+        assert!(!save.is_down() && !save.is_pressed());
+        keys(&[Key::LeftCtrl, Key::S]);
+        assert!(save.is_down() && save.is_pressed());
+        keys(&[Key::LeftCtrl, Key::S]);
+        assert!(!save.is_down() && save.is_pressed() && !save.is_up());
+        keys(&[Key::LeftCtrl]);
+        assert!(save.is_up() && !save.is_pressed());
+        keys(&[]);
+        assert!(!save.is_up());
+
+        keys(&[Key::S]);
+        assert!(!save.is_down());
+        keys(&[Key::S, Key::RightCtrl]);
+        assert!(save.is_down());
+
+        // The other side joining an already held modifier isn't a new press
+        keys(&[Key::S, Key::RightCtrl, Key::LeftCtrl]);
+        assert!(!save.is_down() && save.is_pressed());
+        keys(&[Key::S, Key::LeftCtrl]);
+        assert!(save.is_pressed() && !save.is_up());
+        keys(&[Key::S]);
+        assert!(save.is_up());
+        keys(&[]);
+        assert!(!save.is_up());
+
+        // Both sides going down together counts once
+        keys(&[Key::S]);
+        keys(&[Key::S, Key::LeftCtrl, Key::RightCtrl]);
+        assert!(save.is_down());
+
+        // Mouse buttons in chords
+        input::reset();
+        let select = Chord::new(vec![InputSource::Modifier(Modifier::Shift), InputSource::Mouse(MouseButton::Left)]);
+        frame(&[Key::RightShift], [false; 3]);
+        frame(&[Key::RightShift], [true, false, false]);
+        assert!(select.is_down());
+        frame(&[Key::RightShift], [false; 3]);
+        assert!(select.is_up());
+
+        input::reset();
+    }
+
+    #[test]
+    fn actions_read_their_bindings() {
+        let _lock = input::lock_for_test();
+        input::reset();
+        let map = ActionMap::parse("button test_fire = Space | MouseLeft\naxis test_move = A/D | MouseX*0.5").unwrap();
+        for name in ["test_fire", "test_move"] {
+            bind(name, map.get(name).unwrap().clone());
+        }
+
+        keys(&[Key::Space]);
+        assert!(is_down("test_fire") && is_pressed("test_fire"));
+        keys(&[Key::Space]);
+        assert!(!is_down("test_fire"));
+        // Each chord is its own press
+        frame(&[Key::Space], [true, false, false]);
+        assert!(is_down("test_fire"));
+        // Up only once no chord is held anymore
+        frame(&[], [true, false, false]);
+        assert!(!is_up("test_fire"));
+        keys(&[]);
+        assert!(is_up("test_fire"));
+
+        input::apply(RawInput { keys: [Key::D].into(), mouse_position: Some(Float2::new(10.0, 0.0)), ..Default::default() });
+        assert_eq!(axis("test_move"), 1.0);
+        input::apply(RawInput { keys: [Key::A].into(), mouse_position: Some(Float2::new(14.0, 3.0)), ..Default::default() });
+        assert_eq!(axis("test_move"), -1.0 + 2.0);
+        assert_eq!(axis("test_fire"), 0.0);
+        assert_eq!(axis("test_missing"), 0.0);
+
+        input::reset();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,635 @@
+use crate::{math::numerics::{float3::Float3, float4::Float4}, types::{animated_texture::AnimatedTexture, texture::Texture}};
+
+// Reader for the Aseprite binary format (.aseprite / .ase)
+// Spec: https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md
+
+const HEADER_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+
+const CHUNK_OLD_PALETTE_256: u16 = 0x0004;
+const CHUNK_OLD_PALETTE_64: u16 = 0x0011;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_PALETTE: u16 = 0x2019;
+
+const LAYER_FLAG_VISIBLE: u16 = 1;
+const LAYER_TYPE_GROUP: u16 = 1;
+const HEADER_FLAG_LAYER_OPACITY: u32 = 1;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+    Addition,
+    Subtract,
+    Divide,
+}
+
+impl BlendMode {
+    fn from_u16(value: u16) -> Self {
+        match value {
+            1 => BlendMode::Multiply,
+            2 => BlendMode::Screen,
+            3 => BlendMode::Overlay,
+            4 => BlendMode::Darken,
+            5 => BlendMode::Lighten,
+            6 => BlendMode::ColorDodge,
+            7 => BlendMode::ColorBurn,
+            8 => BlendMode::HardLight,
+            9 => BlendMode::SoftLight,
+            10 => BlendMode::Difference,
+            11 => BlendMode::Exclusion,
+            12 => BlendMode::Hue,
+            13 => BlendMode::Saturation,
+            14 => BlendMode::Color,
+            15 => BlendMode::Luminosity,
+            16 => BlendMode::Addition,
+            17 => BlendMode::Subtract,
+            18 => BlendMode::Divide,
+            _ => BlendMode::Normal,
+        }
+    }
+}
+
+pub struct Layer {
+    pub visible: bool,
+    pub blend_mode: BlendMode,
+    pub opacity: f32,
+    is_group: bool,
+    child_level: u16,
+}
+
+pub struct Cel {
+    pub layer_index: usize,
+    pub x: i32,
+    pub y: i32,
+    pub opacity: f32,
+    pub z_index: i32,
+    pub width: u32,
+    pub height: u32,
+    // Row-major, top row first, non-premultiplied
+    pub pixels: Vec<Float4>,
+}
+
+pub struct Frame {
+    pub duration: f32, // seconds
+    pub cels: Vec<Cel>,
+}
+
+pub struct AsepriteFile {
+    pub width: u32,
+    pub height: u32,
+    pub layers: Vec<Layer>,
+    pub frames: Vec<Frame>,
+}
+
+impl AsepriteFile {
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let mut r = Reader::new(bytes);
+
+        r.skip(4); // file size
+        if r.u16()? != HEADER_MAGIC {
+            return Err("invalid aseprite header".to_string());
+        }
+        let frame_count = r.u16()?;
+        let width = r.u16()? as u32;
+        let height = r.u16()? as u32;
+        let color_depth = r.u16()?;
+        let flags = r.u32()?;
+        r.skip(2 + 4 + 4); // speed, reserved
+        let transparent_index = r.u8()?;
+        r.skip(128 - 29);
+
+        if width == 0 || height == 0 || frame_count == 0 {
+            return Err(format!("empty aseprite file ({width}x{height}, {frame_count} frames)"));
+        }
+        if !matches!(color_depth, 8 | 16 | 32) {
+            return Err(format!("unsupported aseprite color depth: {color_depth}"));
+        }
+
+        let mut layers = Vec::<Layer>::new();
+        let mut palette = vec![Float4::ZERO; 256];
+        let mut has_new_palette = false;
+        let mut frames = Vec::<Frame>::new();
+
+        for _ in 0..frame_count {
+            let frame_start = r.pos;
+            let frame_size = r.u32()? as usize;
+            if r.u16()? != FRAME_MAGIC {
+                return Err(format!("invalid aseprite frame {}", frames.len()));
+            }
+            let old_chunk_count = r.u16()? as u32;
+            let duration = r.u16()? as f32 / 1000.0;
+            r.skip(2);
+            let new_chunk_count = r.u32()?;
+            let chunk_count = if new_chunk_count == 0 { old_chunk_count } else { new_chunk_count };
+
+            let mut cels = Vec::<Cel>::new();
+
+            for _ in 0..chunk_count {
+                let chunk_start = r.pos;
+                let chunk_size = r.u32()? as usize;
+                let chunk_type = r.u16()?;
+                let chunk_end = chunk_start + chunk_size;
+                if chunk_end > bytes.len() || chunk_size < 6 {
+                    return Err(format!("aseprite chunk at {chunk_start} has an invalid size"));
+                }
+
+                match chunk_type {
+                    CHUNK_LAYER => {
+                        let layer_flags = r.u16()?;
+                        let layer_type = r.u16()?;
+                        let child_level = r.u16()?;
+                        r.skip(4); // default width / height
+                        let blend_mode = BlendMode::from_u16(r.u16()?);
+                        let opacity = r.u8()?;
+                        r.skip(3); // the name follows, skipped with the rest of the chunk
+
+                        let use_opacity = flags & HEADER_FLAG_LAYER_OPACITY != 0;
+                        layers.push(Layer {
+                            visible: layer_flags & LAYER_FLAG_VISIBLE != 0,
+                            blend_mode,
+                            opacity: if use_opacity { opacity as f32 / 255.0 } else { 1.0 },
+                            is_group: layer_type == LAYER_TYPE_GROUP,
+                            child_level,
+                        });
+                    }
+                    CHUNK_CEL => {
+                        let layer_index = r.u16()? as usize;
+                        let x = r.i16()? as i32;
+                        let y = r.i16()? as i32;
+                        let opacity = r.u8()? as f32 / 255.0;
+                        let cel_type = r.u16()?;
+                        let z_index = r.i16()? as i32;
+                        r.skip(5);
+
+                        match cel_type {
+                            // Raw / compressed image, tilemap cels are not supported
+                            0 | 2 => {
+                                let w = r.u16()? as u32;
+                                let h = r.u16()? as u32;
+                                let data = r.bytes(chunk_end.saturating_sub(r.pos))?;
+                                let raw = if cel_type == 0 {
+                                    data.to_vec()
+                                } else {
+                                    miniz_oxide::inflate::decompress_to_vec_zlib(data)
+                                        .map_err(|e| format!("failed to decompress aseprite cel: {e:?}"))?
+                                };
+                                let pixels = decode_pixels(&raw, w * h, color_depth, &palette, transparent_index)?;
+                                cels.push(Cel { layer_index, x, y, opacity, z_index, width: w, height: h, pixels });
+                            }
+                            1 => {
+                                let linked_frame = r.u16()? as usize;
+                                let linked = frames.get(linked_frame)
+                                    .and_then(|f| f.cels.iter().find(|c| c.layer_index == layer_index));
+                                if let Some(linked) = linked {
+                                    cels.push(Cel {
+                                        layer_index,
+                                        x,
+                                        y,
+                                        opacity,
+                                        z_index,
+                                        width: linked.width,
+                                        height: linked.height,
+                                        pixels: linked.pixels.clone(),
+                                    });
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    CHUNK_PALETTE => {
+                        let new_size = r.u32()? as usize;
+                        let first = r.u32()? as usize;
+                        let last = r.u32()? as usize;
+                        r.skip(8);
+                        if first > last || last > 0xFFFF {
+                            return Err(format!("invalid aseprite palette range {first}..={last}"));
+                        }
+                        has_new_palette = true;
+                        if palette.len() < new_size.max(last + 1) {
+                            palette.resize(new_size.max(last + 1), Float4::ZERO);
+                        }
+                        for entry in &mut palette[first..=last] {
+                            let entry_flags = r.u16()?;
+                            let c = rgba_to_float4(r.u8()?, r.u8()?, r.u8()?, r.u8()?);
+                            if entry_flags & 1 != 0 {
+                                r.string()?;
+                            }
+                            *entry = c;
+                        }
+                    }
+                    // Old palettes are only written for backwards compatibility
+                    CHUNK_OLD_PALETTE_256 | CHUNK_OLD_PALETTE_64 if !has_new_palette => {
+                        let packets = r.u16()?;
+                        let mut index = 0usize;
+                        for _ in 0..packets {
+                            index += r.u8()? as usize;
+                            let count = match r.u8()? { 0 => 256, n => n as usize };
+                            for _ in 0..count {
+                                let (mut cr, mut cg, mut cb) = (r.u8()?, r.u8()?, r.u8()?);
+                                if chunk_type == CHUNK_OLD_PALETTE_64 {
+                                    cr = (cr as u32 * 255 / 63) as u8;
+                                    cg = (cg as u32 * 255 / 63) as u8;
+                                    cb = (cb as u32 * 255 / 63) as u8;
+                                }
+                                if index < palette.len() {
+                                    palette[index] = rgba_to_float4(cr, cg, cb, 255);
+                                }
+                                index += 1;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+
+                r.pos = chunk_end;
+            }
+
+            r.pos = frame_start + frame_size;
+            if r.pos > bytes.len() {
+                return Err(format!("aseprite frame {} runs past the end of the file", frames.len()));
+            }
+            frames.push(Frame { duration, cels });
+        }
+
+        Ok(Self { width, height, layers, frames })
+    }
+
+    // A layer is only drawn if it and every group above it are visible
+    fn effective_visibility(&self) -> Vec<bool> {
+        let mut result = Vec::with_capacity(self.layers.len());
+        let mut group_stack: Vec<bool> = Vec::new();
+
+        for layer in &self.layers {
+            group_stack.truncate(layer.child_level as usize);
+            let parent_visible = group_stack.iter().all(|v| *v);
+            let visible = parent_visible && layer.visible;
+            result.push(visible && !layer.is_group);
+
+            if layer.is_group {
+                group_stack.push(visible);
+            }
+        }
+        result
+    }
+
+    // Composite all visible layers of one frame into a texture
+    pub fn flatten_frame(&self, frame_index: usize) -> Texture {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let mut canvas = vec![Float4::ZERO; width * height];
+
+        let visible = self.effective_visibility();
+        let frame = &self.frames[frame_index];
+
+        let mut cels: Vec<&Cel> = frame.cels.iter()
+            .filter(|c| visible.get(c.layer_index).copied().unwrap_or(false))
+            .collect();
+        cels.sort_by_key(|c| (c.layer_index as i32 + c.z_index, c.z_index));
+
+        for cel in cels {
+            let layer = &self.layers[cel.layer_index];
+            let opacity = cel.opacity * layer.opacity;
+
+            for cy in 0..cel.height as i32 {
+                let y = cel.y + cy;
+                if y < 0 || y >= height as i32 {
+                    continue;
+                }
+                for cx in 0..cel.width as i32 {
+                    let x = cel.x + cx;
+                    if x < 0 || x >= width as i32 {
+                        continue;
+                    }
+                    let src = cel.pixels[(cy * cel.width as i32 + cx) as usize];
+                    let dst = &mut canvas[y as usize * width + x as usize];
+                    *dst = composite(*dst, src, opacity, layer.blend_mode);
+                }
+            }
+        }
+
+        // Flip y to match typical texture coordinate convention, cels are 8-bit so nothing is lost
+        let unorm8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let texels = canvas.chunks(width).rev().flatten()
+            .map(|c| [unorm8(c.x), unorm8(c.y), unorm8(c.z), unorm8(c.w)])
+            .collect();
+        Texture::from_rgba8(self.width, self.height, texels).compact()
+    }
+
+    pub fn to_animated_texture(&self) -> AnimatedTexture {
+        let frames = (0..self.frames.len()).map(|i| self.flatten_frame(i)).collect();
+        let durations = self.frames.iter().map(|f| f.duration).collect();
+        AnimatedTexture::new(frames, durations)
+    }
+}
+
+fn decode_pixels(raw: &[u8], count: u32, color_depth: u16, palette: &[Float4], transparent_index: u8) -> Result<Vec<Float4>, String> {
+    let count = count as usize;
+    let bytes_per_pixel = color_depth as usize / 8;
+    if raw.len() < count * bytes_per_pixel {
+        return Err(format!("aseprite cel has {} bytes for {count} pixels", raw.len()));
+    }
+
+    Ok(match color_depth {
+        32 => raw[..count * 4]
+            .chunks_exact(4)
+            .map(|p| rgba_to_float4(p[0], p[1], p[2], p[3]))
+            .collect(),
+        16 => raw[..count * 2]
+            .chunks_exact(2)
+            .map(|p| rgba_to_float4(p[0], p[0], p[0], p[1]))
+            .collect(),
+        _ => raw[..count]
+            .iter()
+            .map(|i| {
+                if *i == transparent_index {
+                    Float4::ZERO
+                } else {
+                    palette.get(*i as usize).copied().unwrap_or(Float4::ZERO)
+                }
+            })
+            .collect(),
+    })
+}
+
+fn rgba_to_float4(r: u8, g: u8, b: u8, a: u8) -> Float4 {
+    Float4::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0)
+}
+
+// Separable/non-separable blending as in the W3C compositing spec, followed by source-over
+fn composite(dst: Float4, src: Float4, opacity: f32, mode: BlendMode) -> Float4 {
+    let src_alpha = src.w * opacity;
+    if src_alpha <= 0.0 {
+        return dst;
+    }
+
+    let cb = Float3::new(dst.x, dst.y, dst.z);
+    let cs = Float3::new(src.x, src.y, src.z);
+
+    let blended = if dst.w > 0.0 { blend(cb, cs, mode) } else { cs };
+    let mixed = cs * (1.0 - dst.w) + blended * dst.w;
+
+    let out_alpha = src_alpha + dst.w * (1.0 - src_alpha);
+    let out = (mixed * src_alpha + cb * dst.w * (1.0 - src_alpha)) / out_alpha;
+
+    Float4::new(out.x, out.y, out.z, out_alpha)
+}
+
+fn blend(cb: Float3, cs: Float3, mode: BlendMode) -> Float3 {
+    let per_channel = |f: fn(f32, f32) -> f32| Float3::new(f(cb.x, cs.x), f(cb.y, cs.y), f(cb.z, cs.z));
+
+    match mode {
+        BlendMode::Normal => cs,
+        BlendMode::Multiply => per_channel(|b, s| b * s),
+        BlendMode::Screen => per_channel(screen),
+        BlendMode::Overlay => per_channel(|b, s| hard_light(s, b)),
+        BlendMode::Darken => per_channel(f32::min),
+        BlendMode::Lighten => per_channel(f32::max),
+        BlendMode::ColorDodge => per_channel(|b, s| {
+            if b == 0.0 { 0.0 } else if s >= 1.0 { 1.0 } else { (b / (1.0 - s)).min(1.0) }
+        }),
+        BlendMode::ColorBurn => per_channel(|b, s| {
+            if b >= 1.0 { 1.0 } else if s <= 0.0 { 0.0 } else { 1.0 - ((1.0 - b) / s).min(1.0) }
+        }),
+        BlendMode::HardLight => per_channel(hard_light),
+        BlendMode::SoftLight => per_channel(|b, s| {
+            if s <= 0.5 {
+                b - (1.0 - 2.0 * s) * b * (1.0 - b)
+            } else {
+                let d = if b <= 0.25 { ((16.0 * b - 12.0) * b + 4.0) * b } else { b.sqrt() };
+                b + (2.0 * s - 1.0) * (d - b)
+            }
+        }),
+        BlendMode::Difference => per_channel(|b, s| (b - s).abs()),
+        BlendMode::Exclusion => per_channel(|b, s| b + s - 2.0 * b * s),
+        BlendMode::Hue => set_lum(set_sat(cs, sat(cb)), lum(cb)),
+        BlendMode::Saturation => set_lum(set_sat(cb, sat(cs)), lum(cb)),
+        BlendMode::Color => set_lum(cs, lum(cb)),
+        BlendMode::Luminosity => set_lum(cb, lum(cs)),
+        BlendMode::Addition => per_channel(|b, s| (b + s).min(1.0)),
+        BlendMode::Subtract => per_channel(|b, s| (b - s).max(0.0)),
+        BlendMode::Divide => per_channel(|b, s| {
+            if b == 0.0 { 0.0 } else if b >= s { 1.0 } else { b / s }
+        }),
+    }
+}
+
+fn screen(b: f32, s: f32) -> f32 {
+    b + s - b * s
+}
+
+fn hard_light(b: f32, s: f32) -> f32 {
+    if s <= 0.5 { b * 2.0 * s } else { screen(b, 2.0 * s - 1.0) }
+}
+
+fn lum(c: Float3) -> f32 {
+    0.3 * c.x + 0.59 * c.y + 0.11 * c.z
+}
+
+fn clip_color(c: Float3) -> Float3 {
+    let l = lum(c);
+    let n = c.x.min(c.y).min(c.z);
+    let x = c.x.max(c.y).max(c.z);
+    let mut c = c;
+    if n < 0.0 {
+        c = Float3::new(l, l, l) + (c - l) * (l / (l - n));
+    }
+    if x > 1.0 {
+        c = Float3::new(l, l, l) + (c - l) * ((1.0 - l) / (x - l));
+    }
+    c
+}
+
+fn set_lum(c: Float3, l: f32) -> Float3 {
+    clip_color(c + (l - lum(c)))
+}
+
+fn sat(c: Float3) -> f32 {
+    c.x.max(c.y).max(c.z) - c.x.min(c.y).min(c.z)
+}
+
+fn set_sat(c: Float3, s: f32) -> Float3 {
+    let max = c.x.max(c.y).max(c.z);
+    let min = c.x.min(c.y).min(c.z);
+    if max <= min {
+        return Float3::ZERO;
+    }
+    let scale = |v: f32| (v - min) * s / (max - min);
+    Float3::new(scale(c.x), scale(c.y), scale(c.z))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn skip(&mut self, count: usize) {
+        self.pos += count;
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let slice = self.data.get(self.pos..self.pos + count)
+            .ok_or_else(|| format!("aseprite file ends at {} while reading {count} bytes at {}", self.data.len(), self.pos))?;
+        self.pos += count;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(i16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::core::{assets, resource_helper};
+
+    fn assert_close(a: Float4, b: Float4) {
+        assert!((a - b).length() < 1e-5, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn bundled_files_match_their_pngs() {
+        let pairs = [
+            ("DirtBlock.aseprite", "Dirtblock.png"),
+            ("Smallworldtexture.aseprite", "Smallworldtexture.png"),
+            ("color.aseprite", "color.png"),
+            ("floortexture.aseprite", "floortexture.png"),
+        ];
+        for (aseprite, png) in pairs {
+            let a = resource_helper::load_texture(assets::resolve(aseprite).to_str().unwrap());
+            let b = resource_helper::load_texture(assets::resolve(png).to_str().unwrap());
+            assert_eq!((a.width(), a.height()), (b.width(), b.height()), "{aseprite}");
+            assert_eq!((a.format(), a.layout()), (b.format(), b.layout()), "{aseprite}");
+
+            for y in 0..a.height() {
+                for x in 0..a.width() {
+                    assert_eq!(a.get_pixel_srgb(x, y), b.get_pixel_srgb(x, y), "{aseprite} at ({x}, {y})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_files_are_errors() {
+        let bytes = fs::read(assets::resolve("color.aseprite")).unwrap();
+        assert!(AsepriteFile::parse(&bytes).is_ok());
+
+        // Cut off anywhere, including inside chunks the reader skips
+        for len in (0..bytes.len()).step_by(7).chain([bytes.len() - 1]) {
+            assert!(AsepriteFile::parse(&bytes[..len]).is_err(), "truncated to {len}");
+        }
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[4] = 0;
+        assert_eq!(AsepriteFile::parse(&bad_magic).err().unwrap(), "invalid aseprite header");
+
+        let mut bad_depth = bytes.clone();
+        bad_depth[12] = 24;
+        assert_eq!(AsepriteFile::parse(&bad_depth).err().unwrap(), "unsupported aseprite color depth: 24");
+
+        // Surfaced by the texture loader instead of panicking
+        assert!(resource_helper::load_texture_from_memory(&bytes[..bytes.len() / 2], Some("aseprite")).is_err());
+    }
+
+    #[test]
+    fn composite_applies_opacity() {
+        let red = Float4::new(1.0, 0.0, 0.0, 1.0);
+        let blue = Float4::new(0.0, 0.0, 1.0, 1.0);
+
+        assert_close(composite(red, blue, 1.0, BlendMode::Normal), blue);
+        assert_close(composite(red, blue, 0.5, BlendMode::Normal), Float4::new(0.5, 0.0, 0.5, 1.0));
+        assert_close(composite(red, blue, 0.0, BlendMode::Normal), red);
+
+        // Cel and layer opacity multiply with the pixel's own alpha
+        let faint_blue = Float4::new(0.0, 0.0, 1.0, 0.5);
+        assert_close(composite(red, faint_blue, 0.5, BlendMode::Normal), Float4::new(0.75, 0.0, 0.25, 1.0));
+
+        // Over nothing the source keeps its color whatever the mode, with the combined alpha
+        for mode in [BlendMode::Normal, BlendMode::Multiply, BlendMode::Difference, BlendMode::Luminosity] {
+            assert_close(composite(Float4::ZERO, faint_blue, 0.5, mode), Float4::new(0.0, 0.0, 1.0, 0.25));
+        }
+
+        // Over a half transparent backdrop the blend only shows where the backdrop is
+        let half_red = Float4::new(1.0, 0.0, 0.0, 0.5);
+        assert_close(composite(half_red, Float4::new(0.5, 0.5, 0.5, 1.0), 1.0, BlendMode::Multiply), Float4::new(0.5, 0.25, 0.25, 1.0));
+        assert_close(composite(half_red, blue, 0.5, BlendMode::Normal), Float4::new(1.0 / 3.0, 0.0, 2.0 / 3.0, 0.75));
+    }
+
+    #[test]
+    fn composite_blend_modes() {
+        let backdrop = Float4::new(0.5, 1.0, 0.2, 1.0);
+        let source = Float4::new(0.5, 0.5, 1.0, 1.0);
+        let cases = [
+            (BlendMode::Multiply, Float3::new(0.25, 0.5, 0.2)),
+            (BlendMode::Screen, Float3::new(0.75, 1.0, 1.0)),
+            (BlendMode::Overlay, Float3::new(0.5, 1.0, 0.4)),
+            (BlendMode::Darken, Float3::new(0.5, 0.5, 0.2)),
+            (BlendMode::Lighten, Float3::new(0.5, 1.0, 1.0)),
+            (BlendMode::ColorDodge, Float3::new(1.0, 1.0, 1.0)),
+            (BlendMode::ColorBurn, Float3::new(0.0, 1.0, 0.2)),
+            (BlendMode::HardLight, Float3::new(0.5, 1.0, 1.0)),
+            (BlendMode::Difference, Float3::new(0.0, 0.5, 0.8)),
+            (BlendMode::Exclusion, Float3::new(0.5, 0.5, 0.8)),
+            (BlendMode::Addition, Float3::new(1.0, 1.0, 1.0)),
+            (BlendMode::Subtract, Float3::new(0.0, 0.5, 0.0)),
+            (BlendMode::Divide, Float3::new(1.0, 1.0, 0.2)),
+        ];
+        for (mode, expected) in cases {
+            let c = composite(backdrop, source, 1.0, mode);
+            assert!((Float3::new(c.x, c.y, c.z) - expected).length() < 1e-5 && c.w == 1.0, "{mode:?}: {c:?}");
+        }
+
+        // Half opacity lands halfway between the backdrop and the blend
+        let c = composite(backdrop, source, 0.5, BlendMode::Multiply);
+        assert_close(c, Float4::new(0.375, 0.75, 0.2, 1.0));
+
+        // The non-separable modes trade hue, saturation and luminosity between the two
+        let to_rgb = |c: Float4| Float3::new(c.x, c.y, c.z);
+        let (b, s) = (to_rgb(backdrop), to_rgb(source));
+        assert!((lum(to_rgb(composite(backdrop, source, 1.0, BlendMode::Luminosity))) - lum(s)).abs() < 1e-5);
+        assert!((lum(to_rgb(composite(backdrop, source, 1.0, BlendMode::Color))) - lum(b)).abs() < 1e-5);
+        assert!((lum(to_rgb(composite(backdrop, source, 1.0, BlendMode::Hue))) - lum(b)).abs() < 1e-5);
+        let saturation = to_rgb(composite(backdrop, source, 1.0, BlendMode::Saturation));
+        assert!((lum(saturation) - lum(b)).abs() < 1e-5 && (sat(saturation) - sat(s)).abs() < 1e-5, "{saturation:?}");
+    }
+}
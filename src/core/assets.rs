@@ -0,0 +1,426 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use once_cell::sync::Lazy;
+
+use crate::core::resource_helper;
+use crate::types::{animated_texture::AnimatedTexture, handle::Handle, mesh::Mesh, texture::Texture};
+
+struct Entry<T> {
+    handle: Handle<T>,
+    modified: Option<SystemTime>,
+}
+
+struct Cache<T> {
+    entries: HashMap<PathBuf, Entry<T>>,
+    loader: fn(&str) -> T,
+}
+
+impl<T> Cache<T> {
+    fn new(loader: fn(&str) -> T) -> Self {
+        Self {
+            entries: HashMap::new(),
+            loader,
+        }
+    }
+
+    fn get(&self, path: &Path) -> Option<Handle<T>> {
+        self.entries.get(path).map(|entry| entry.handle.clone())
+    }
+
+    // Keeps the first asset if another thread loaded the same file in the meantime
+    fn insert(&mut self, path: PathBuf, asset: T, modified: Option<SystemTime>) -> Handle<T> {
+        self.entries.entry(path).or_insert_with(|| Entry { handle: Handle::new(asset), modified }).handle.clone()
+    }
+
+    fn path_of(&self, handle: &Handle<T>) -> Option<&PathBuf> {
+        self.entries.iter().find(|(_, e)| e.handle.ptr_eq(handle)).map(|(path, _)| path)
+    }
+
+    // Files modified since they were loaded, with their new modification time
+    fn changed(&self) -> Vec<(PathBuf, Handle<T>, Option<SystemTime>)> {
+        self.entries.iter()
+            .filter_map(|(path, entry)| {
+                let modified = modified_time(path);
+                (modified.is_some() && modified != entry.modified).then(|| (path.clone(), entry.handle.clone(), modified))
+            })
+            .collect()
+    }
+
+    fn set_modified(&mut self, path: &Path, modified: Option<SystemTime>) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            entry.modified = modified;
+        }
+    }
+
+    // Drops assets only the cache still holds a handle to, returns how many
+    fn release_unused(&mut self) -> usize {
+        let count = self.entries.len();
+        self.entries.retain(|_, entry| entry.handle.ref_count() > 1);
+        count - self.entries.len()
+    }
+}
+
+type CacheOf<T> = fn(&mut AssetManager) -> &mut Cache<T>;
+
+// What one poll of `update` reloaded, by asset name. Failed files keep the old asset and are retried.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReloadReport {
+    pub reloaded: Vec<String>,
+    pub failed: Vec<(String, String)>, // name and the loader's panic message
+}
+
+impl ReloadReport {
+    pub fn is_empty(&self) -> bool {
+        self.reloaded.is_empty() && self.failed.is_empty()
+    }
+}
+
+struct AssetManager {
+    roots: Vec<PathBuf>,
+    meshes: Cache<Mesh>,
+    textures: Cache<Texture>,
+    animated_textures: Cache<AnimatedTexture>,
+
+    hot_reload: bool,
+    poll_interval: Duration,
+    last_poll: Instant,
+    last_reload: ReloadReport, // last poll that reloaded or failed something
+}
+
+impl AssetManager {
+    fn new() -> Self {
+        Self {
+            roots: default_roots(),
+            meshes: Cache::new(resource_helper::load_mesh),
+            textures: Cache::new(resource_helper::load_texture),
+            animated_textures: Cache::new(resource_helper::load_animated_texture),
+
+            hot_reload: true,
+            poll_interval: Duration::from_millis(500),
+            last_poll: Instant::now(),
+            last_reload: ReloadReport::default(),
+        }
+    }
+
+    fn resolve(&self, name: &str) -> Result<PathBuf, String> {
+        let path = Path::new(name);
+        let found = if path.is_absolute() {
+            path.exists().then(|| path.to_path_buf())
+        } else {
+            self.roots.iter().map(|root| root.join(path)).find(|p| p.exists())
+        };
+
+        match found {
+            Some(p) => Ok(p.canonicalize().unwrap_or(p)),
+            None => Err(format!("Asset not found: {name} (roots: {:?})", self.roots)),
+        }
+    }
+
+    fn release_unused(&mut self) -> usize {
+        self.meshes.release_unused() + self.textures.release_unused() + self.animated_textures.release_unused()
+    }
+
+    // Inverse of `resolve`: the path relative to the first root containing it
+    fn name_of(&self, path: &Path) -> String {
+        self.roots.iter()
+            .filter_map(|root| path.strip_prefix(root.canonicalize().ok()?).ok())
+            .next()
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+#[cfg(feature = "release_paths")]
+fn default_roots() -> Vec<PathBuf> {
+    vec![PathBuf::from("assets")]
+}
+
+#[cfg(not(feature = "release_paths"))]
+fn default_roots() -> Vec<PathBuf> {
+    vec![
+        PathBuf::from("src/assets"),
+        PathBuf::from("../rasterizer/src/assets"),
+        PathBuf::from("assets"),
+    ]
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|m| m.modified()).ok()
+}
+
+// Loaders and the not-found panic run without the lock held, so a broken file can't poison it
+fn resolve_in(assets: &Mutex<AssetManager>, name: &str) -> PathBuf {
+    let resolved = assets.lock().unwrap().resolve(name);
+    resolved.unwrap_or_else(|e| panic!("{e}"))
+}
+
+fn load<T>(assets: &Mutex<AssetManager>, name: &str, cache: CacheOf<T>) -> Handle<T> {
+    let path = resolve_in(assets, name);
+    let loader = {
+        let mut assets = assets.lock().unwrap();
+        let cache = cache(&mut assets);
+        if let Some(handle) = cache.get(&path) {
+            return handle;
+        }
+        cache.loader
+    };
+
+    let asset = loader(path.to_str().expect("Asset path is not valid UTF-8"));
+    let modified = modified_time(&path);
+    cache(&mut assets.lock().unwrap()).insert(path, asset, modified)
+}
+
+fn reload_changed<T>(assets: &Mutex<AssetManager>, cache: CacheOf<T>, report: &mut ReloadReport) {
+    let (changed, loader) = {
+        let mut assets = assets.lock().unwrap();
+        let cache = cache(&mut assets);
+        (cache.changed(), cache.loader)
+    };
+
+    for (path, handle, modified) in changed {
+        // A half-written file makes the loader panic, keep the old asset and retry next poll
+        let path_str = path.to_str().expect("Asset path is not valid UTF-8");
+        let result = panic::catch_unwind(AssertUnwindSafe(|| loader(path_str)));
+
+        let mut assets = assets.lock().unwrap();
+        let name = assets.name_of(&path);
+        match result {
+            Ok(asset) => {
+                handle.replace(asset);
+                cache(&mut assets).set_modified(&path, modified);
+                report.reloaded.push(name);
+            }
+            Err(payload) => report.failed.push((name, panic_message(payload.as_ref()))),
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "loader panicked".to_string(),
+    }
+}
+
+fn poll(assets: &Mutex<AssetManager>) -> ReloadReport {
+    let mut report = ReloadReport::default();
+    {
+        let mut assets = assets.lock().unwrap();
+        if assets.last_poll.elapsed() < assets.poll_interval {
+            return report;
+        }
+        assets.last_poll = Instant::now();
+        assets.release_unused();
+        if !assets.hot_reload {
+            return report;
+        }
+    }
+
+    reload_changed(assets, |a| &mut a.meshes, &mut report);
+    reload_changed(assets, |a| &mut a.textures, &mut report);
+    reload_changed(assets, |a| &mut a.animated_textures, &mut report);
+    if !report.is_empty() {
+        assets.lock().unwrap().last_reload = report.clone();
+    }
+    report
+}
+
+static ASSETS: Lazy<Mutex<AssetManager>> = Lazy::new(|| { Mutex::new(AssetManager::new()) });
+
+// Roots are searched in order, the first one containing the file wins
+#[allow(dead_code)]
+pub fn set_roots(roots: &[&str]) {
+    ASSETS.lock().unwrap().roots = roots.iter().map(PathBuf::from).collect();
+}
+
+pub fn resolve(name: &str) -> PathBuf {
+    resolve_in(&ASSETS, name)
+}
+
+pub fn load_mesh(name: &str) -> Handle<Mesh> {
+    load(&ASSETS, name, |a| &mut a.meshes)
+}
+
+pub fn load_texture(name: &str) -> Handle<Texture> {
+    load(&ASSETS, name, |a| &mut a.textures)
+}
+
+pub fn load_animated_texture(name: &str) -> Handle<AnimatedTexture> {
+    load(&ASSETS, name, |a| &mut a.animated_textures)
+}
+
+// Names a loaded asset can be loaded again by, None for assets not loaded through this module
+pub fn mesh_name(handle: &Handle<Mesh>) -> Option<String> {
+    let assets = ASSETS.lock().unwrap();
+    assets.meshes.path_of(handle).map(|path| assets.name_of(path))
+}
+
+pub fn texture_name(handle: &Handle<Texture>) -> Option<String> {
+    let assets = ASSETS.lock().unwrap();
+    assets.textures.path_of(handle).map(|path| assets.name_of(path))
+}
+
+pub fn animated_texture_name(handle: &Handle<AnimatedTexture>) -> Option<String> {
+    let assets = ASSETS.lock().unwrap();
+    assets.animated_textures.path_of(handle).map(|path| assets.name_of(path))
+}
+
+#[allow(dead_code)]
+pub fn set_hot_reload(enabled: bool) {
+    ASSETS.lock().unwrap().hot_reload = enabled;
+}
+
+// Last poll that reloaded or failed to reload something, for the UI
+pub fn last_reload() -> ReloadReport {
+    ASSETS.lock().unwrap().last_reload.clone()
+}
+
+// Called once per frame by the engine, releases unused assets and checks the files of the loaded
+// ones for changes
+pub fn update() -> ReloadReport {
+    poll(&ASSETS)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::types::mesh_builder;
+
+    // Test meshes are text files holding the size of a cube
+    fn cube_from_file(path: &str) -> Mesh {
+        let text = fs::read_to_string(path).expect("Failed to read test asset");
+        mesh_builder::cube(text.trim().parse().expect("Failed to parse test asset"))
+    }
+
+    fn size_of(mesh: &Mesh) -> f32 {
+        mesh.vertices.iter().map(|v| v.x).fold(f32::MIN, f32::max) * 2.0
+    }
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("rasterizer_assets_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("Failed to create test directory");
+        root
+    }
+
+    fn manager(roots: &[&Path]) -> Mutex<AssetManager> {
+        let mut assets = AssetManager::new();
+        assets.roots = roots.iter().map(|r| r.to_path_buf()).collect();
+        assets.meshes = Cache::new(cube_from_file);
+        assets.poll_interval = Duration::ZERO;
+        Mutex::new(assets)
+    }
+
+    // Rewrites a file with a modification time that can't collide with the previous one
+    fn rewrite(path: &Path, text: &str, seconds_later: u64) {
+        fs::write(path, text).unwrap();
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(seconds_later)).unwrap();
+    }
+
+    fn load_cube(assets: &Mutex<AssetManager>, name: &str) -> Handle<Mesh> {
+        load(assets, name, |a| &mut a.meshes)
+    }
+
+    #[test]
+    fn loads_each_file_once_until_released() {
+        let root = temp_root("dedup");
+        fs::write(root.join("cube.txt"), "1").unwrap();
+        let assets = manager(&[&root]);
+
+        let a = load_cube(&assets, "cube.txt");
+        let b = load_cube(&assets, "./cube.txt");
+        let c = load_cube(&assets, root.join("cube.txt").to_str().unwrap());
+        assert!(a.ptr_eq(&b) && a.ptr_eq(&c));
+        assert_eq!(a.ref_count(), 4);
+
+        // Still held, nothing to release
+        assert_eq!(assets.lock().unwrap().release_unused(), 0);
+        drop((a, b, c));
+        assert_eq!(assets.lock().unwrap().release_unused(), 1);
+        assert!(assets.lock().unwrap().meshes.entries.is_empty());
+
+        // The next load reads the file again
+        rewrite(&root.join("cube.txt"), "3", 0);
+        assert_eq!(size_of(&load_cube(&assets, "cube.txt")), 3.0);
+    }
+
+    #[test]
+    fn resolves_against_roots_in_order() {
+        let (first, second) = (temp_root("roots_first"), temp_root("roots_second"));
+        fs::write(first.join("both.txt"), "1").unwrap();
+        fs::write(second.join("both.txt"), "2").unwrap();
+        fs::create_dir_all(second.join("sub")).unwrap();
+        fs::write(second.join("sub/only.txt"), "3").unwrap();
+        let assets = manager(&[&first, &second]);
+
+        let manager = assets.lock().unwrap();
+        assert_eq!(manager.resolve("both.txt"), Ok(first.join("both.txt").canonicalize().unwrap()));
+        let only = manager.resolve("sub/only.txt").unwrap();
+        assert_eq!(only, second.join("sub/only.txt").canonicalize().unwrap());
+        assert_eq!(manager.name_of(&only), "sub/only.txt");
+        assert!(manager.resolve("missing.txt").unwrap_err().starts_with("Asset not found: missing.txt"));
+        drop(manager);
+
+        assert_eq!(size_of(&load_cube(&assets, "both.txt")), 1.0);
+    }
+
+    #[test]
+    fn failed_loads_leave_the_manager_usable() {
+        let root = temp_root("poison");
+        fs::write(root.join("broken.txt"), "cube").unwrap();
+        fs::write(root.join("cube.txt"), "2").unwrap();
+        let assets = manager(&[&root]);
+
+        assert!(panic::catch_unwind(|| load_cube(&assets, "broken.txt")).is_err());
+        assert!(panic::catch_unwind(|| load_cube(&assets, "missing.txt")).is_err());
+        assert!(!assets.is_poisoned());
+        assert_eq!(size_of(&load_cube(&assets, "cube.txt")), 2.0);
+    }
+
+    #[test]
+    fn hot_reload_replaces_assets_and_keeps_them_on_failure() {
+        let root = temp_root("reload");
+        let path = root.join("cube.txt");
+        fs::write(&path, "1").unwrap();
+        let assets = manager(&[&root]);
+
+        let mut handle = load_cube(&assets, "cube.txt");
+        assert_eq!(poll(&assets), ReloadReport::default());
+
+        rewrite(&path, "2", 10);
+        assert_eq!(poll(&assets), ReloadReport { reloaded: vec!["cube.txt".to_string()], failed: vec![] });
+        assert!(handle.refresh());
+        assert_eq!(size_of(&handle), 2.0);
+        assert_eq!(poll(&assets), ReloadReport::default());
+
+        // A broken file is reported on every poll until it's fixed, the old asset stays
+        rewrite(&path, "two", 20);
+        for _ in 0..2 {
+            let report = poll(&assets);
+            assert_eq!(report.failed, [("cube.txt".to_string(), "Failed to parse test asset: ParseFloatError { kind: Invalid }".to_string())]);
+        }
+        assert!(!handle.refresh());
+        assert_eq!(size_of(&handle), 2.0);
+        assert_eq!(assets.lock().unwrap().last_reload.failed.len(), 1);
+
+        rewrite(&path, "4", 30);
+        assert_eq!(poll(&assets).reloaded, ["cube.txt"]);
+        assert!(handle.refresh());
+        assert_eq!(size_of(&handle), 4.0);
+
+        // Without hot reload nothing is checked
+        assets.lock().unwrap().hot_reload = false;
+        rewrite(&path, "5", 40);
+        assert!(poll(&assets).is_empty());
+        assert!(!handle.refresh());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,481 @@
+use minifb::{Key, MouseButton};
+
+use crate::{core::input, math::{mathf, numerics::float3::Float3}, rasterizer::camera::Camera};
+
+// Just short of straight up/down, where yaw stops being defined
+const MAX_PITCH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+
+#[inline(always)]
+fn key_axis(positive: Key, negative: Key) -> f32 {
+    input::is_pressed(positive) as i32 as f32 - input::is_pressed(negative) as i32 as f32
+}
+
+// Moves `current` towards `target`, reaching ~63% after 1 / rate seconds regardless of frame rate
+#[inline(always)]
+fn approach(current: Float3, target: Float3, rate: f32, delta_time: f32) -> Float3 {
+    if rate <= 0.0 {
+        return target;
+    }
+    mathf::lerp_float3(current, target, 1.0 - (-rate * delta_time).exp())
+}
+
+pub struct LookBindings {
+    pub button: Option<MouseButton>, // mouse look only while held, None looks all the time
+    pub left: Key,
+    pub right: Key,
+    pub up: Key,
+    pub down: Key,
+}
+
+impl Default for LookBindings {
+    fn default() -> Self {
+        Self {
+            button: Some(MouseButton::Right),
+            left: Key::Left,
+            right: Key::Right,
+            up: Key::Up,
+            down: Key::Down,
+        }
+    }
+}
+
+// Yaw and pitch kept outside of the transform, so pitch can be clamped
+struct Look {
+    yaw: f32,
+    pitch: f32,
+}
+
+impl Look {
+    fn from_camera(cam: &Camera) -> Self {
+        Self { yaw: cam.transform.rotation.y, pitch: cam.transform.rotation.x }
+    }
+
+    // `sensitivity` is radians per pixel of mouse movement, `key_speed` radians per second
+    fn update(&mut self, bindings: &LookBindings, sensitivity: f32, key_speed: f32, delta_time: f32) {
+        if bindings.button.is_none_or(input::is_mouse_pressed) {
+            let delta = input::mouse_delta();
+            self.yaw -= delta.x * sensitivity;
+            self.pitch -= delta.y * sensitivity;
+        }
+
+        self.yaw += key_axis(bindings.left, bindings.right) * key_speed * delta_time;
+        self.pitch += key_axis(bindings.up, bindings.down) * key_speed * delta_time;
+        self.pitch = self.pitch.clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    fn apply(&self, cam: &mut Camera) {
+        cam.transform.set_rotation(Float3::new(self.pitch, self.yaw, 0.0));
+    }
+}
+
+pub struct FlyBindings {
+    pub forward: Key,
+    pub back: Key,
+    pub left: Key,
+    pub right: Key,
+    pub up: Key,
+    pub down: Key,
+    pub fast: Key,
+    pub faster: Key, // raise / lower the base speed
+    pub slower: Key,
+    pub look: LookBindings,
+}
+
+impl Default for FlyBindings {
+    fn default() -> Self {
+        Self {
+            forward: Key::W,
+            back: Key::S,
+            left: Key::A,
+            right: Key::D,
+            up: Key::Space,
+            down: Key::LeftShift,
+            fast: Key::LeftCtrl,
+            faster: Key::R,
+            slower: Key::F,
+            look: LookBindings::default(),
+        }
+    }
+}
+
+// Free-flying camera, moves along where it looks
+pub struct FlyCamera {
+    pub bindings: FlyBindings,
+    pub speed: f32, // units per second
+    pub fast_multiplier: f32,
+    pub acceleration: f32, // how quickly the velocity follows the input, 0 is instant
+    pub mouse_sensitivity: f32,
+    pub key_look_speed: f32,
+
+    look: Look,
+    velocity: Float3,
+}
+
+impl FlyCamera {
+    pub fn new(cam: &Camera) -> Self {
+        Self {
+            bindings: FlyBindings::default(),
+            speed: 0.8,
+            fast_multiplier: 3.0,
+            acceleration: 10.0,
+            mouse_sensitivity: 0.003,
+            key_look_speed: 1.5,
+            look: Look::from_camera(cam),
+            velocity: Float3::ZERO,
+        }
+    }
+
+    pub fn update(&mut self, cam: &mut Camera, delta_time: f32) {
+        let b = &self.bindings;
+
+        self.look.update(&b.look, self.mouse_sensitivity, self.key_look_speed, delta_time);
+        self.look.apply(cam);
+
+        // Doubles or halves the speed per second held
+        self.speed *= 2f32.powf(key_axis(b.faster, b.slower) * delta_time);
+
+        let t = &cam.transform;
+        let direction = (t.forward() * key_axis(b.forward, b.back)
+            + t.right() * key_axis(b.right, b.left)
+            + Float3::UNIT_Y * key_axis(b.up, b.down)).normalize();
+        let speed = if input::is_pressed(b.fast) { self.speed * self.fast_multiplier } else { self.speed };
+
+        self.velocity = approach(self.velocity, direction * speed, self.acceleration, delta_time);
+        cam.transform.translate(self.velocity * delta_time);
+    }
+}
+
+pub struct OrbitBindings {
+    pub rotate_button: MouseButton,
+    pub pan_button: MouseButton,
+    pub zoom_in: Key,
+    pub zoom_out: Key,
+    pub rotate_left: Key,
+    pub rotate_right: Key,
+    pub rotate_up: Key,
+    pub rotate_down: Key,
+}
+
+impl Default for OrbitBindings {
+    fn default() -> Self {
+        Self {
+            rotate_button: MouseButton::Left,
+            pan_button: MouseButton::Right,
+            zoom_in: Key::W,
+            zoom_out: Key::S,
+            rotate_left: Key::Left,
+            rotate_right: Key::Right,
+            rotate_up: Key::Up,
+            rotate_down: Key::Down,
+        }
+    }
+}
+
+// Circles around a target point. Drag to rotate, drag with the pan button to move the target, scroll to zoom.
+pub struct OrbitCamera {
+    pub bindings: OrbitBindings,
+    pub target: Float3,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub rotate_sensitivity: f32, // radians per pixel
+    pub key_rotate_speed: f32,   // radians per second
+    pub zoom_step: f32,          // distance factor per scroll step
+    pub key_zoom_speed: f32,     // distance factor per second
+    pub pan_sensitivity: f32,    // fraction of the distance per pixel
+
+    look: Look,
+}
+
+impl OrbitCamera {
+    pub fn new(target: Float3, distance: f32) -> Self {
+        Self {
+            bindings: OrbitBindings::default(),
+            target,
+            distance,
+            min_distance: 0.05,
+            max_distance: 100.0,
+            rotate_sensitivity: 0.005,
+            key_rotate_speed: 1.5,
+            zoom_step: 0.9,
+            key_zoom_speed: 0.25,
+            pan_sensitivity: 0.002,
+            look: Look { yaw: 0.0, pitch: -0.3 },
+        }
+    }
+
+    pub fn update(&mut self, cam: &mut Camera, delta_time: f32) {
+        let b = &self.bindings;
+        let look_bindings = LookBindings {
+            button: Some(b.rotate_button),
+            left: b.rotate_left,
+            right: b.rotate_right,
+            up: b.rotate_up,
+            down: b.rotate_down,
+        };
+        self.look.update(&look_bindings, self.rotate_sensitivity, self.key_rotate_speed, delta_time);
+        self.look.apply(cam);
+
+        self.distance *= self.zoom_step.powf(input::scroll_delta().y);
+        self.distance *= self.key_zoom_speed.powf(key_axis(b.zoom_in, b.zoom_out) * delta_time);
+        self.distance = self.distance.clamp(self.min_distance, self.max_distance);
+
+        // Dragging moves the scene with the cursor
+        if input::is_mouse_pressed(b.pan_button) {
+            let delta = input::mouse_delta();
+            let scale = self.pan_sensitivity * self.distance;
+            self.target = self.target - cam.transform.right() * (delta.x * scale) + cam.transform.up() * (delta.y * scale);
+        }
+
+        cam.transform.set_position(self.target - cam.transform.forward() * self.distance);
+    }
+}
+
+pub struct FirstPersonBindings {
+    pub forward: Key,
+    pub back: Key,
+    pub left: Key,
+    pub right: Key,
+    pub jump: Key,
+    pub sprint: Key,
+    pub look: LookBindings,
+}
+
+impl Default for FirstPersonBindings {
+    fn default() -> Self {
+        Self {
+            forward: Key::W,
+            back: Key::S,
+            left: Key::A,
+            right: Key::D,
+            jump: Key::Space,
+            sprint: Key::LeftShift,
+            look: LookBindings::default(),
+        }
+    }
+}
+
+// Walks on the ground given by `ground_height(x, z)`, with gravity and jumping
+pub struct FirstPersonCamera {
+    pub bindings: FirstPersonBindings,
+    pub walk_speed: f32,
+    pub sprint_multiplier: f32,
+    pub acceleration: f32,
+    pub mouse_sensitivity: f32,
+    pub key_look_speed: f32,
+    pub eye_height: f32,
+    pub step_height: f32, // drops smaller than this are walked down instead of falling
+    pub gravity: f32,
+    pub jump_speed: f32,
+    pub ground_height: Box<dyn Fn(f32, f32) -> f32>,
+
+    look: Look,
+    velocity: Float3,
+    grounded: bool,
+}
+
+impl FirstPersonCamera {
+    pub fn new(cam: &Camera) -> Self {
+        Self {
+            bindings: FirstPersonBindings::default(),
+            walk_speed: 0.8,
+            sprint_multiplier: 2.0,
+            acceleration: 12.0,
+            mouse_sensitivity: 0.003,
+            key_look_speed: 1.5,
+            eye_height: 0.2,
+            step_height: 0.05,
+            gravity: 9.81,
+            jump_speed: 1.5,
+            ground_height: Box::new(|_, _| 0.0),
+            look: Look::from_camera(cam),
+            velocity: Float3::ZERO,
+            grounded: false,
+        }
+    }
+
+    pub fn update(&mut self, cam: &mut Camera, delta_time: f32) {
+        let b = &self.bindings;
+
+        self.look.update(&b.look, self.mouse_sensitivity, self.key_look_speed, delta_time);
+        self.look.apply(cam);
+
+        // Walking ignores pitch
+        let forward = Float3::new(self.look.yaw.sin(), 0.0, self.look.yaw.cos());
+        let right = cam.transform.right();
+        let right = Float3::new(right.x, 0.0, right.z).normalize();
+        let direction = (forward * key_axis(b.forward, b.back) + right * key_axis(b.right, b.left)).normalize();
+        let speed = if input::is_pressed(b.sprint) { self.walk_speed * self.sprint_multiplier } else { self.walk_speed };
+
+        let horizontal = Float3::new(self.velocity.x, 0.0, self.velocity.z);
+        let horizontal = approach(horizontal, direction * speed, self.acceleration, delta_time);
+
+        let mut vertical = self.velocity.y - self.gravity * delta_time;
+        if self.grounded && input::is_down(b.jump) {
+            vertical = self.jump_speed;
+        }
+        self.velocity = Float3::new(horizontal.x, vertical, horizontal.z);
+
+        let mut position = cam.transform.position + self.velocity * delta_time;
+        let ground = (self.ground_height)(position.x, position.z) + self.eye_height;
+        let snap = self.grounded && self.velocity.y <= 0.0 && position.y - ground < self.step_height;
+        if position.y <= ground || snap {
+            position.y = ground;
+            self.velocity.y = 0.0;
+            self.grounded = true;
+        }
+        else {
+            self.grounded = false;
+        }
+
+        cam.transform.set_position(position);
+    }
+}
+
+pub enum CameraController {
+    Fly(FlyCamera),
+    Orbit(OrbitCamera),
+    FirstPerson(FirstPersonCamera),
+}
+
+impl CameraController {
+    pub fn update(&mut self, cam: &mut Camera, delta_time: f32) {
+        match self {
+            CameraController::Fly(c) => c.update(cam, delta_time),
+            CameraController::Orbit(c) => c.update(cam, delta_time),
+            CameraController::FirstPerson(c) => c.update(cam, delta_time),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::input::RawInput;
+    use crate::math::numerics::float2::Float2;
+
+    fn hold(keys: &[Key]) {
+        input::apply(RawInput { keys: keys.iter().copied().collect(), ..Default::default() });
+    }
+
+    fn assert_close(a: Float3, b: Float3, tolerance: f32) {
+        assert!((a - b).length() <= tolerance, "{a:?} != {b:?}");
+    }
+
+    // Runs `steps` updates of `dt` while the keys stay held
+    fn fly(keys: &[Key], acceleration: f32, steps: usize, dt: f32) -> (Camera, FlyCamera) {
+        let mut cam = Camera::new(70.0);
+        let mut fly = FlyCamera::new(&cam);
+        fly.acceleration = acceleration;
+        hold(keys);
+        for _ in 0..steps {
+            fly.update(&mut cam, dt);
+        }
+        (cam, fly)
+    }
+
+    #[test]
+    fn fly_camera_is_frame_rate_independent() {
+        let _lock = input::lock_for_test();
+        input::reset();
+        // Looking and speed changes don't depend on the step size
+        let look = [Key::Left, Key::Up, Key::R];
+        let (one_cam, one) = fly(&look, 10.0, 1, 0.1);
+        let (ten_cam, ten) = fly(&look, 10.0, 10, 0.01);
+        assert_close(one_cam.transform.rotation, ten_cam.transform.rotation, 1e-5);
+        assert!((one.speed - ten.speed).abs() < 1e-5);
+
+        // Neither does the velocity easing
+        let keys = [Key::W, Key::D, Key::Space];
+        let (_, one) = fly(&keys, 10.0, 1, 0.1);
+        let (_, ten) = fly(&keys, 10.0, 10, 0.01);
+        assert_close(one.velocity, ten.velocity, 1e-5);
+
+        // Without easing the velocity is constant, so the distance travelled matches too
+        let (one_cam, _) = fly(&keys, 0.0, 1, 0.1);
+        let (ten_cam, _) = fly(&keys, 0.0, 10, 0.01);
+        assert_close(one_cam.transform.position, ten_cam.transform.position, 2e-3);
+
+        input::reset();
+    }
+
+    #[test]
+    fn pitch_is_clamped() {
+        let _lock = input::lock_for_test();
+        input::reset();
+
+        // Keys alone can't look past straight up
+        let (cam, _) = fly(&[Key::Up], 10.0, 100, 0.1);
+        assert_eq!(cam.transform.rotation.x, MAX_PITCH);
+        let (cam, _) = fly(&[Key::Down], 10.0, 100, 0.1);
+        assert_eq!(cam.transform.rotation.x, -MAX_PITCH);
+
+        // Neither can a large mouse movement, and the clamped pitch is where it turns back from
+        let mut cam = Camera::new(70.0);
+        let mut fly = FlyCamera::new(&cam);
+        let mouse = |y: f32| input::apply(RawInput { mouse_position: Some(Float2::new(0.0, y)), mouse_buttons: [false, false, true], ..Default::default() });
+        mouse(0.0);
+        mouse(-10000.0);
+        fly.update(&mut cam, 0.01);
+        assert_eq!(cam.transform.rotation.x, MAX_PITCH);
+        mouse(-10000.0 + 100.0);
+        fly.update(&mut cam, 0.01);
+        assert!((cam.transform.rotation.x - (MAX_PITCH - 100.0 * fly.mouse_sensitivity)).abs() < 1e-5);
+
+        input::reset();
+    }
+
+    #[test]
+    fn first_person_camera_follows_the_ground() {
+        let _lock = input::lock_for_test();
+        input::reset();
+
+        // A small step down at z = 0.3, a cliff at z = 0.6
+        let ground = |_: f32, z: f32| if z < 0.3 { 0.0 } else if z < 0.6 { -0.03 } else { -1.0 };
+        let mut cam = Camera::new(70.0);
+        cam.transform.set_position(Float3::new(0.0, 0.5, 0.0));
+        let mut walker = FirstPersonCamera::new(&cam);
+        walker.ground_height = Box::new(ground);
+        let dt = 1.0 / 60.0;
+
+        // Falls until it lands at eye height
+        hold(&[]);
+        for _ in 0..60 {
+            walker.update(&mut cam, dt);
+        }
+        assert!(walker.grounded);
+        assert_eq!(cam.transform.position.y, walker.eye_height);
+
+        // Walks down the small step without leaving the ground
+        hold(&[Key::W]);
+        while cam.transform.position.z < 0.45 {
+            walker.update(&mut cam, dt);
+            assert!(walker.grounded);
+            assert_eq!(cam.transform.position.y, ground(0.0, cam.transform.position.z) + walker.eye_height);
+        }
+        assert!(cam.transform.position.x.abs() < 1e-5);
+
+        // Jumping leaves the ground
+        hold(&[Key::W, Key::Space]);
+        walker.update(&mut cam, dt);
+        assert!(!walker.grounded);
+        assert!(cam.transform.position.y > -0.03 + walker.eye_height);
+
+        // Lands again and walks off the cliff, which is too high to snap down
+        hold(&[Key::W]);
+        while cam.transform.position.z < 0.6 {
+            walker.update(&mut cam, dt);
+        }
+        walker.update(&mut cam, dt);
+        assert!(!walker.grounded);
+        assert!(cam.transform.position.y > -1.0 + walker.eye_height);
+
+        for _ in 0..120 {
+            walker.update(&mut cam, dt);
+        }
+        assert!(walker.grounded);
+        assert_eq!(cam.transform.position.y, -1.0 + walker.eye_height);
+
+        input::reset();
+    }
+}
\ No newline at end of file
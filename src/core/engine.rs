@@ -1,8 +1,73 @@
 use rayon::prelude::*;
-use std::time::Instant;
+use std::{path::PathBuf, thread, time::{Duration, Instant}};
 use minifb::{Window, WindowOptions};
 
-use crate::{core::input, rasterizer::render_target::RenderTarget, types::scene::Scene};
+use crate::{core::{assets, input, recording::Recording, scene_manager, time}, rasterizer::{debug_draw, render_target::RenderTarget}, types::scene::Scene};
+
+// Timing of the engine loop
+#[derive(Debug, Clone, Copy)]
+pub struct LoopSettings {
+    pub fixed_timestep: f32,     // seconds per `Scene::fixed_update`
+    pub max_delta: f32,          // longer frames (hitches, resizes) are clamped to this
+    pub target_fps: Option<f32>, // sleeps to hold this frame rate, None runs as fast as possible
+}
+
+impl Default for LoopSettings {
+    fn default() -> Self {
+        Self {
+            fixed_timestep: 1.0 / 60.0,
+            max_delta: 0.25,
+            target_fps: None,
+        }
+    }
+}
+
+// Collects frame time and runs the fixed steps it covers
+struct FrameStepper {
+    settings: LoopSettings,
+    accumulator: f32,
+}
+
+impl FrameStepper {
+    fn new(settings: LoopSettings) -> Self {
+        Self { settings, accumulator: 0.0 }
+    }
+
+    fn step<S: Scene>(&mut self, scene: &mut S, delta_time: f32, render_target: &mut RenderTarget) {
+        let delta_time = delta_time.min(self.settings.max_delta);
+        let fixed_timestep = self.settings.fixed_timestep;
+        time::advance(delta_time);
+        debug_draw::clear();
+
+        self.accumulator += delta_time;
+        while self.accumulator >= fixed_timestep {
+            scene.fixed_update(fixed_timestep);
+            self.accumulator -= fixed_timestep;
+        }
+
+        scene.update(delta_time, render_target);
+        scene.render(self.accumulator / fixed_timestep, render_target);
+        render_target.resolve();
+    }
+}
+
+// Sleeps until `1 / fps` seconds after `frame_start`, the last millisecond is spun since sleep overshoots
+fn pace(frame_start: Instant, fps: f32) {
+    let frame_end = frame_start + Duration::from_secs_f32(1.0 / fps);
+    let sleep = frame_end.saturating_duration_since(Instant::now()).saturating_sub(Duration::from_millis(1));
+    if !sleep.is_zero() {
+        thread::sleep(sleep);
+    }
+    while Instant::now() < frame_end {
+        std::hint::spin_loop();
+    }
+}
+
+struct Replay {
+    recording: Recording,
+    timestep: Option<f32>, // None uses the recorded frame deltas
+    frame: usize,
+}
 
 pub struct Engine {
     width: u32,
@@ -10,6 +75,10 @@ pub struct Engine {
     window: Window,
     framebuffer: Vec<u32>,
     last_frame: Instant,
+    loop_settings: LoopSettings,
+
+    recording: Option<(PathBuf, Recording)>,
+    replay: Option<Replay>,
 }
 
 impl Engine {
@@ -18,7 +87,7 @@ impl Engine {
     pub const TITLE: &'static str = "Software Rasterizer";
 
     pub fn new() -> Self {
-        let window = Window::new(
+        let mut window = Window::new(
             Self::TITLE,
             Self::START_WIDTH as usize,
             Self::START_HEIGHT as usize,
@@ -30,6 +99,8 @@ impl Engine {
             },
         )
         .expect("Failed to create window");
+        input::attach(&mut window);
+        window.set_target_fps(0); // paced by LoopSettings::target_fps instead
 
         let framebuffer = vec![0u32; Self::START_WIDTH as usize * Self::START_HEIGHT as usize];
 
@@ -39,16 +110,107 @@ impl Engine {
             window,
             framebuffer,
             last_frame: Instant::now(),
+            loop_settings: LoopSettings::default(),
+            recording: None,
+            replay: None,
+        }
+    }
+
+    pub fn set_loop_settings(&mut self, settings: LoopSettings) {
+        self.loop_settings = settings;
+    }
+
+    #[allow(dead_code)]
+    pub fn loop_settings(&self) -> LoopSettings {
+        self.loop_settings
+    }
+
+    // Records every frame's input and delta, written to `path` when the window closes
+    pub fn record(&mut self, path: impl Into<PathBuf>) {
+        self.recording = Some((path.into(), Recording::new()));
+    }
+
+    // Plays a recording back instead of reading the window, the window closes when it ends
+    pub fn replay(&mut self, recording: Recording, timestep: Option<f32>) {
+        self.replay = Some(Replay { recording, timestep, frame: 0 });
+    }
+
+    // Runs a recording without a window and calls `on_frame` after every rendered frame.
+    // Runs of the same recording and timestep render identical frames.
+    pub fn replay_headless<S: Scene>(
+        scene: &mut S,
+        recording: &Recording,
+        timestep: Option<f32>,
+        settings: LoopSettings,
+        mut on_frame: impl FnMut(usize, &RenderTarget),
+    ) {
+        input::reset();
+        time::reset();
+        let mut stepper = FrameStepper::new(settings);
+
+        let (width, height) = recording.frames.first().map_or((Self::START_WIDTH, Self::START_HEIGHT), |f| (f.width, f.height));
+        let mut render_target = RenderTarget::new(width, height);
+        scene.start(&mut render_target);
+
+        for (i, frame) in recording.frames.iter().enumerate() {
+            if frame.width != render_target.width() || frame.height != render_target.height() {
+                render_target = RenderTarget::new(frame.width, frame.height);
+                scene.resize(frame.width, frame.height, &mut render_target);
+            }
+
+            input::apply(frame.input.clone());
+            stepper.step(scene, timestep.unwrap_or(frame.delta_time), &mut render_target);
+            on_frame(i, &render_target);
+            if scene_manager::quit_requested() {
+                break;
+            }
         }
+        scene.exit();
     }
 
-    pub fn run<S: Scene>(&mut self, scene: &mut S) {
-        let mut render_target = RenderTarget::new(Self::START_WIDTH, Self::START_HEIGHT);
+    // Returns where the recording was saved, if one was made
+    pub fn run<S: Scene>(&mut self, scene: &mut S) -> Option<PathBuf> {
+        time::reset();
+        if self.replay.is_some() {
+            input::reset();
+        }
+
+        let mut render_target = RenderTarget::new(self.width, self.height);
+        let mut stepper = FrameStepper::new(self.loop_settings);
 
         scene.start(&mut render_target);
-        while self.window.is_open() {
-            let new_width = self.window.get_size().0 as u32;
-            let new_height = self.window.get_size().1 as u32;
+        self.last_frame = Instant::now();
+        let mut focused = true;
+        while self.window.is_open() && !scene_manager::quit_requested() {
+            let (delta_time, new_width, new_height) = match &mut self.replay {
+                Some(replay) => {
+                    let Some(frame) = replay.recording.frames.get(replay.frame) else { break };
+                    replay.frame += 1;
+                    self.last_frame = Instant::now();
+
+                    input::apply(frame.input.clone());
+                    (replay.timestep.unwrap_or(frame.delta_time), frame.width, frame.height)
+                }
+                None => {
+                    if self.window.is_active() != focused {
+                        focused = !focused;
+                        scene.focus_changed(focused);
+                    }
+
+                    let (width, height) = self.window.get_size();
+                    let raw = input::update(&mut self.window);
+                    assets::update();
+
+                    let now = Instant::now();
+                    let delta_time = (now - self.last_frame).as_secs_f32();
+                    self.last_frame = now;
+
+                    if let Some((_, recording)) = &mut self.recording {
+                        recording.push(delta_time, width as u32, height as u32, raw);
+                    }
+                    (delta_time, width as u32, height as u32)
+                }
+            };
 
             if new_width != self.width || new_height != self.height {
                 self.width = new_width;
@@ -56,14 +218,8 @@ impl Engine {
                 self.resize(scene, &mut render_target);
             }
 
-            input::update(&self.window);
-
-            let now = Instant::now();
-            let delta_time = (now - self.last_frame).as_secs_f32();
-            self.last_frame = now;
-
-            scene.update(delta_time, &mut render_target);
-            self.render(&mut render_target);
+            stepper.step(scene, delta_time, &mut render_target);
+            self.render(&render_target);
 
             self.window
                 .update_with_buffer(
@@ -72,7 +228,17 @@ impl Engine {
                     self.height as usize,
                 )
                 .unwrap();
+
+            if let Some(fps) = self.loop_settings.target_fps {
+                pace(self.last_frame, fps);
+            }
         }
+
+        scene.exit();
+
+        let (path, recording) = self.recording.take()?;
+        recording.save(&path);
+        Some(path)
     }
 
     fn render (&mut self, render_target: &RenderTarget) {
@@ -103,4 +269,56 @@ impl Engine {
         *render_target = RenderTarget::new(self.width, self.height);
         scene.resize(self.width, self.height, render_target);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct CountingScene {
+        fixed_steps: u32,
+        updates: Vec<f32>,
+        alphas: Vec<f32>,
+    }
+
+    impl Scene for CountingScene {
+        fn resize(&mut self, _new_width: u32, _new_height: u32, _render_target: &mut RenderTarget) {}
+        fn update(&mut self, delta_time: f32, _render_target: &mut RenderTarget) {
+            self.updates.push(delta_time);
+        }
+        fn start(&mut self, _render_target: &mut RenderTarget) {}
+        fn fixed_update(&mut self, _fixed_delta_time: f32) {
+            self.fixed_steps += 1;
+        }
+        fn render(&mut self, alpha: f32, _render_target: &mut RenderTarget) {
+            self.alphas.push(alpha);
+        }
+    }
+
+    #[test]
+    fn fixed_steps_follow_accumulated_time() {
+        let settings = LoopSettings { fixed_timestep: 0.25, max_delta: 1.0, target_fps: None };
+        let mut stepper = FrameStepper::new(settings);
+        let mut scene = CountingScene::default();
+        let mut render_target = RenderTarget::new(1, 1);
+
+        for delta in [0.125, 0.125, 0.625] {
+            stepper.step(&mut scene, delta, &mut render_target);
+        }
+        assert_eq!(scene.fixed_steps, 3);
+        assert_eq!(scene.alphas, [0.5, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn long_frames_are_clamped() {
+        let settings = LoopSettings { fixed_timestep: 0.25, max_delta: 0.5, target_fps: None };
+        let mut stepper = FrameStepper::new(settings);
+        let mut scene = CountingScene::default();
+        let mut render_target = RenderTarget::new(1, 1);
+
+        stepper.step(&mut scene, 10.0, &mut render_target);
+        assert_eq!(scene.fixed_steps, 2);
+        assert_eq!(scene.updates, [0.5]);
+    }
+}
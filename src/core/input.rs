@@ -1,32 +1,151 @@
-use minifb::Key;
+use minifb::{InputCallback, Key, MouseButton, MouseMode, Window};
 use std::collections::HashSet;
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
 
+use crate::math::numerics::float2::Float2;
+
+// Raw input gathered for one frame, either from the window or from a recording
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RawInput {
+    pub keys: HashSet<Key>,
+    pub mouse_position: Option<Float2>, // None while the window reports no position
+    pub mouse_buttons: [bool; 3],
+    pub scroll: Float2,
+    pub text: Vec<char>,
+}
+
 #[derive(Debug, Default)]
 struct Input {
     keys_pressed: HashSet<Key>,
     keys_down: HashSet<Key>,
     keys_up: HashSet<Key>,
     prev_pressed: HashSet<Key>,
+
+    // Window coordinates, y down like minifb reports them
+    mouse_position: Option<Float2>,
+    mouse_delta: Float2,
+    mouse_pressed: [bool; 3],
+    mouse_down: [bool; 3],
+    mouse_up: [bool; 3],
+    scroll: Float2,
+    text: Vec<char>,
+
+    cursor_grabbed: bool,
+    cursor_hidden: bool,
+
+    // Filled by the window callback between updates
+    held_keys: HashSet<Key>,
+    tapped_keys: HashSet<Key>, // pressed and released before the next update
+    pending_text: Vec<char>,
 }
 
 static INPUT: Lazy<Mutex<Input>> = Lazy::new(|| { Mutex::new(Input::default()) });
 
-pub fn update(window: &minifb::Window) {
-    let mut input = INPUT.lock().unwrap();
+// Held by tests that drive the global input, so they don't see each other's frames
+#[cfg(test)]
+static TEST_LOCK: Lazy<Mutex<()>> = Lazy::new(|| { Mutex::new(()) });
+
+#[cfg(test)]
+pub fn lock_for_test() -> std::sync::MutexGuard<'static, ()> {
+    TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Receives key and character events while minifb processes window messages
+struct WindowInput;
+
+impl InputCallback for WindowInput {
+    fn add_char(&mut self, uni_char: u32) {
+        if let Some(c) = char::from_u32(uni_char).filter(|c| !c.is_control()) {
+            INPUT.lock().unwrap().pending_text.push(c);
+        }
+    }
+
+    fn set_key_state(&mut self, key: Key, state: bool) {
+        let mut input = INPUT.lock().unwrap();
+        if state {
+            input.held_keys.insert(key);
+        }
+        else if input.held_keys.remove(&key) && !input.keys_pressed.contains(&key) {
+            // Never seen by an update, keep it for one frame so the press isn't lost
+            input.tapped_keys.insert(key);
+        }
+    }
+}
 
-    input.prev_pressed = input.keys_pressed.clone();
-    input.keys_pressed.clear();
+// Routes the window's key and text events into this module, called once after creating the window
+pub fn attach(window: &mut Window) {
+    window.set_input_callback(Box::new(WindowInput));
+}
+
+// Reads this frame's input from the window, returns it so it can be recorded
+pub fn update(window: &mut Window) -> RawInput {
+    let raw = {
+        let mut input = INPUT.lock().unwrap();
+        if !window.is_active() {
+            input.held_keys.clear();
+        }
 
-    for key in ALL_KEYS {
-        if window.is_key_down(*key) {
-            input.keys_pressed.insert(*key);
+        let mut keys = &input.held_keys | &input.tapped_keys;
+        input.tapped_keys.clear();
+        keys.remove(&Key::Unknown);
+
+        RawInput {
+            keys,
+            mouse_position: window.get_mouse_pos(MouseMode::Pass).map(|(x, y)| Float2::new(x, y)),
+            mouse_buttons: [MouseButton::Left, MouseButton::Middle, MouseButton::Right].map(|b| window.get_mouse_down(b)),
+            scroll: window.get_scroll_wheel().map_or(Float2::ZERO, |(x, y)| Float2::new(x, y)),
+            text: std::mem::take(&mut input.pending_text),
         }
+    };
+
+    apply(raw.clone());
+
+    // minifb can't confine or warp the cursor, grabbing hides it and look code relies on the delta
+    let mut input = INPUT.lock().unwrap();
+    if input.cursor_hidden != input.cursor_grabbed {
+        input.cursor_hidden = input.cursor_grabbed;
+        window.set_cursor_visibility(!input.cursor_grabbed);
     }
+    raw
+}
+
+// Advances one frame from raw input, derives the down/up edges and the mouse delta
+pub fn apply(raw: RawInput) {
+    let mut input = INPUT.lock().unwrap();
 
+    input.prev_pressed = std::mem::replace(&mut input.keys_pressed, raw.keys);
     input.keys_down = &input.keys_pressed - &input.prev_pressed;
     input.keys_up   = &input.prev_pressed - &input.keys_pressed;
+
+    input.mouse_delta = match (input.mouse_position, raw.mouse_position) {
+        (Some(old), Some(new)) => new - old,
+        _ => Float2::ZERO,
+    };
+    input.mouse_position = raw.mouse_position.or(input.mouse_position);
+
+    for i in 0..3 {
+        let was_pressed = input.mouse_pressed[i];
+        input.mouse_pressed[i] = raw.mouse_buttons[i];
+        input.mouse_down[i] = raw.mouse_buttons[i] && !was_pressed;
+        input.mouse_up[i] = !raw.mouse_buttons[i] && was_pressed;
+    }
+
+    input.scroll = raw.scroll;
+    input.text = raw.text;
+}
+
+// Forgets all state, so a replay starts from the same input as the recording
+pub fn reset() {
+    *INPUT.lock().unwrap() = Input::default();
+}
+
+fn button_index(button: MouseButton) -> usize {
+    match button {
+        MouseButton::Left => 0,
+        MouseButton::Middle => 1,
+        MouseButton::Right => 2,
+    }
 }
 
 #[allow(dead_code)]
@@ -44,13 +163,193 @@ pub fn is_up(key: Key) -> bool {
     INPUT.lock().unwrap().keys_up.contains(&key)
 }
 
-static ALL_KEYS: &[Key] = &[
-    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L,
-    Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X,
-    Key::Y, Key::Z,
-    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8,
-    Key::Key9,
-    Key::Escape, Key::Space, Key::Enter, Key::Backspace, Key::Tab, Key::Left, Key::Right,
-    Key::Up, Key::Down, Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl,
-    Key::LeftAlt, Key::RightAlt,
-];
\ No newline at end of file
+#[allow(dead_code)]
+pub fn mouse_position() -> Float2 {
+    INPUT.lock().unwrap().mouse_position.unwrap_or_default()
+}
+
+// Movement since the last frame in pixels
+#[allow(dead_code)]
+pub fn mouse_delta() -> Float2 {
+    INPUT.lock().unwrap().mouse_delta
+}
+
+#[allow(dead_code)]
+pub fn is_mouse_pressed(button: MouseButton) -> bool {
+    INPUT.lock().unwrap().mouse_pressed[button_index(button)]
+}
+
+#[allow(dead_code)]
+pub fn is_mouse_down(button: MouseButton) -> bool {
+    INPUT.lock().unwrap().mouse_down[button_index(button)]
+}
+
+#[allow(dead_code)]
+pub fn is_mouse_up(button: MouseButton) -> bool {
+    INPUT.lock().unwrap().mouse_up[button_index(button)]
+}
+
+// Scroll wheel movement this frame, y is the usual vertical wheel
+#[allow(dead_code)]
+pub fn scroll_delta() -> Float2 {
+    INPUT.lock().unwrap().scroll
+}
+
+// Hides this frame's mouse buttons, movement and scroll from everything that reads them afterwards,
+// for UI that took the mouse. The position stays.
+#[allow(dead_code)]
+pub fn consume_mouse() {
+    let mut input = INPUT.lock().unwrap();
+    input.mouse_delta = Float2::ZERO;
+    input.mouse_pressed = [false; 3];
+    input.mouse_down = [false; 3];
+    input.mouse_up = [false; 3];
+    input.scroll = Float2::ZERO;
+}
+
+// Characters typed this frame, in order
+#[allow(dead_code)]
+pub fn text_input() -> Vec<char> {
+    INPUT.lock().unwrap().text.clone()
+}
+
+// Hides the cursor for mouse look, takes effect on the next update
+#[allow(dead_code)]
+pub fn set_cursor_grab(grabbed: bool) {
+    INPUT.lock().unwrap().cursor_grabbed = grabbed;
+}
+
+#[allow(dead_code)]
+pub fn is_cursor_grabbed() -> bool {
+    INPUT.lock().unwrap().cursor_grabbed
+}
+
+// Case-insensitive lookup by the variant name, like `LeftShift` or `Key1`
+pub fn key_from_name(name: &str) -> Option<Key> {
+    ALL_KEYS.iter().copied().find(|k| format!("{k:?}").eq_ignore_ascii_case(name))
+}
+
+// Every key minifb reports, used to look keys up by name
+pub static ALL_KEYS: &[Key] = &[
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::F13, Key::F14, Key::F15,
+    Key::Down, Key::Left, Key::Right, Key::Up,
+    Key::Apostrophe, Key::Backquote, Key::Backslash, Key::Comma, Key::Equal, Key::LeftBracket, Key::Minus,
+    Key::Period, Key::RightBracket, Key::Semicolon, Key::Slash,
+    Key::Backspace, Key::Delete, Key::End, Key::Enter, Key::Escape, Key::Home, Key::Insert, Key::Menu,
+    Key::PageDown, Key::PageUp, Key::Pause, Key::Space, Key::Tab, Key::NumLock, Key::CapsLock, Key::ScrollLock,
+    Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl,
+    Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4, Key::NumPad5, Key::NumPad6,
+    Key::NumPad7, Key::NumPad8, Key::NumPad9, Key::NumPadDot, Key::NumPadSlash, Key::NumPadAsterisk,
+    Key::NumPadMinus, Key::NumPadPlus, Key::NumPadEnter,
+    Key::LeftAlt, Key::RightAlt, Key::LeftSuper, Key::RightSuper,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(keys: &[Key], mouse_position: Option<Float2>, mouse_buttons: [bool; 3]) {
+        apply(RawInput { keys: keys.iter().copied().collect(), mouse_position, mouse_buttons, ..Default::default() });
+    }
+
+    #[test]
+    fn apply_derives_key_edges() {
+        let _lock = lock_for_test();
+        reset();
+
+        frame(&[Key::A], None, [false; 3]);
+        assert!(is_pressed(Key::A) && is_down(Key::A) && !is_up(Key::A));
+
+        frame(&[Key::A, Key::B], None, [false; 3]);
+        assert!(is_pressed(Key::A) && !is_down(Key::A));
+        assert!(is_pressed(Key::B) && is_down(Key::B));
+
+        frame(&[Key::B], None, [false; 3]);
+        assert!(!is_pressed(Key::A) && !is_down(Key::A) && is_up(Key::A));
+        assert!(is_pressed(Key::B) && !is_up(Key::B));
+
+        frame(&[], None, [false; 3]);
+        assert!(!is_up(Key::A) && is_up(Key::B));
+        frame(&[], None, [false; 3]);
+        assert!(!is_up(Key::B));
+
+        // A reset forgets what was held, so nothing goes up
+        frame(&[Key::C], None, [false; 3]);
+        reset();
+        frame(&[], None, [false; 3]);
+        assert!(!is_up(Key::C));
+    }
+
+    #[test]
+    fn apply_derives_mouse_edges_and_delta() {
+        let _lock = lock_for_test();
+        reset();
+
+        // No delta until there are two positions
+        frame(&[], Some(Float2::new(10.0, 20.0)), [true, false, false]);
+        assert_eq!(mouse_position(), Float2::new(10.0, 20.0));
+        assert_eq!(mouse_delta(), Float2::ZERO);
+        assert!(is_mouse_pressed(MouseButton::Left) && is_mouse_down(MouseButton::Left) && !is_mouse_up(MouseButton::Left));
+
+        frame(&[], Some(Float2::new(13.0, 16.0)), [true, false, true]);
+        assert_eq!(mouse_delta(), Float2::new(3.0, -4.0));
+        assert!(is_mouse_pressed(MouseButton::Left) && !is_mouse_down(MouseButton::Left));
+        assert!(is_mouse_down(MouseButton::Right) && !is_mouse_pressed(MouseButton::Middle));
+
+        // Leaving the window keeps the last position without moving
+        frame(&[], None, [false, false, true]);
+        assert_eq!(mouse_position(), Float2::new(13.0, 16.0));
+        assert_eq!(mouse_delta(), Float2::ZERO);
+        assert!(is_mouse_up(MouseButton::Left) && !is_mouse_up(MouseButton::Right));
+
+        frame(&[], Some(Float2::new(11.0, 16.0)), [false; 3]);
+        assert_eq!(mouse_delta(), Float2::new(-2.0, 0.0));
+        assert!(!is_mouse_up(MouseButton::Left) && is_mouse_up(MouseButton::Right));
+    }
+
+    #[test]
+    fn consume_mouse_keeps_position_and_keys() {
+        let _lock = lock_for_test();
+        reset();
+
+        frame(&[], Some(Float2::new(5.0, 5.0)), [false; 3]);
+        apply(RawInput {
+            keys: [Key::Space].into(),
+            mouse_position: Some(Float2::new(8.0, 9.0)),
+            mouse_buttons: [true, true, false],
+            scroll: Float2::new(0.0, 2.0),
+            text: vec!['x'],
+        });
+        consume_mouse();
+
+        assert_eq!(mouse_position(), Float2::new(8.0, 9.0));
+        assert_eq!(mouse_delta(), Float2::ZERO);
+        assert_eq!(scroll_delta(), Float2::ZERO);
+        assert!(!is_mouse_pressed(MouseButton::Left) && !is_mouse_down(MouseButton::Middle));
+        assert!(is_down(Key::Space));
+        assert_eq!(text_input(), ['x']);
+
+        // The consumed press is never released for anyone else
+        frame(&[Key::Space], Some(Float2::new(8.0, 9.0)), [false; 3]);
+        assert!(!is_mouse_up(MouseButton::Left) && !is_mouse_up(MouseButton::Middle));
+        assert!(is_pressed(Key::Space) && !is_down(Key::Space));
+    }
+
+    #[test]
+    fn key_names() {
+        assert_eq!(key_from_name("LeftShift"), Some(Key::LeftShift));
+        assert_eq!(key_from_name("leftshift"), Some(Key::LeftShift));
+        assert_eq!(key_from_name("KEY1"), Some(Key::Key1));
+        assert_eq!(key_from_name("NumPadEnter"), Some(Key::NumPadEnter));
+        assert_eq!(key_from_name("Shift"), None);
+        assert_eq!(key_from_name(""), None);
+
+        for key in ALL_KEYS {
+            assert_eq!(key_from_name(&format!("{key:?}")), Some(*key));
+        }
+    }
+}
\ No newline at end of file
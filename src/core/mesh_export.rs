@@ -0,0 +1,281 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use crate::{math::numerics::float3::Float3, types::mesh::Mesh};
+
+pub struct ObjMaterial {
+    pub name: String,
+    pub diffuse_color: Float3,
+    pub diffuse_texture: Option<String>,
+}
+
+impl ObjMaterial {
+    #[allow(dead_code)]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            diffuse_color: Float3::ONE,
+            diffuse_texture: None,
+        }
+    }
+}
+
+// Writes the obj and, if a material is given, a .mtl with the same name next to it
+#[allow(dead_code)]
+pub fn save_obj(path: &str, mesh: &Mesh, material: Option<&ObjMaterial>) {
+    let material = material.map(|m| {
+        let mtl_path = Path::new(path).with_extension("mtl");
+        fs::write(&mtl_path, write_mtl(m)).expect("Failed to write mtl file");
+        (mtl_path.file_name().unwrap().to_string_lossy().into_owned(), m)
+    });
+
+    let obj = write_obj(mesh, material.as_ref().map(|(file, m)| (file.as_str(), *m)));
+    fs::write(path, obj).expect("Failed to write obj file");
+}
+
+#[allow(dead_code)]
+pub fn save_ply(path: &str, mesh: &Mesh, binary: bool) {
+    let data = if binary { write_ply_binary(mesh) } else { write_ply_ascii(mesh).into_bytes() };
+    fs::write(path, data).expect("Failed to write ply file");
+}
+
+// Vertex colors use the common `v x y z r g b` extension
+pub fn write_obj(mesh: &Mesh, material: Option<(&str, &ObjMaterial)>) -> String {
+    let mut out = String::new();
+    let has_normals = !mesh.normals.is_empty();
+    let has_uvs = !mesh.uvs.is_empty();
+    let has_colors = !mesh.colors.is_empty();
+
+    if let Some((mtl_file, _)) = material {
+        writeln!(out, "mtllib {mtl_file}").unwrap();
+    }
+    writeln!(out, "o mesh").unwrap();
+
+    for (i, v) in mesh.vertices.iter().enumerate() {
+        if has_colors {
+            let c = mesh.colors[i];
+            writeln!(out, "v {} {} {} {} {} {}", v.x, v.y, v.z, c.x, c.y, c.z).unwrap();
+        } else {
+            writeln!(out, "v {} {} {}", v.x, v.y, v.z).unwrap();
+        }
+    }
+    for uv in &mesh.uvs {
+        writeln!(out, "vt {} {}", uv.x, uv.y).unwrap();
+    }
+    for n in &mesh.normals {
+        writeln!(out, "vn {} {} {}", n.x, n.y, n.z).unwrap();
+    }
+
+    if let Some((_, m)) = material {
+        writeln!(out, "usemtl {}", m.name).unwrap();
+    }
+
+    for tri in mesh.indices.chunks_exact(3) {
+        out.push('f');
+        for index in tri {
+            // obj indices are 1-based
+            let i = index + 1;
+            match (has_uvs, has_normals) {
+                (true, true) => write!(out, " {i}/{i}/{i}").unwrap(),
+                (false, true) => write!(out, " {i}//{i}").unwrap(),
+                (true, false) => write!(out, " {i}/{i}").unwrap(),
+                (false, false) => write!(out, " {i}").unwrap(),
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+pub fn write_mtl(material: &ObjMaterial) -> String {
+    let mut out = String::new();
+    let c = material.diffuse_color;
+    writeln!(out, "newmtl {}", material.name).unwrap();
+    writeln!(out, "Kd {} {} {}", c.x, c.y, c.z).unwrap();
+    if let Some(texture) = &material.diffuse_texture {
+        writeln!(out, "map_Kd {texture}").unwrap();
+    }
+    out
+}
+
+fn ply_header(mesh: &Mesh, format: &str) -> String {
+    let mut out = String::new();
+    writeln!(out, "ply").unwrap();
+    writeln!(out, "format {format} 1.0").unwrap();
+    writeln!(out, "element vertex {}", mesh.vertices.len()).unwrap();
+    writeln!(out, "property float x\nproperty float y\nproperty float z").unwrap();
+    if !mesh.normals.is_empty() {
+        writeln!(out, "property float nx\nproperty float ny\nproperty float nz").unwrap();
+    }
+    if !mesh.uvs.is_empty() {
+        writeln!(out, "property float s\nproperty float t").unwrap();
+    }
+    if !mesh.colors.is_empty() {
+        writeln!(out, "property uchar red\nproperty uchar green\nproperty uchar blue\nproperty uchar alpha").unwrap();
+    }
+    writeln!(out, "element face {}", mesh.indices.len() / 3).unwrap();
+    writeln!(out, "property list uchar uint vertex_indices").unwrap();
+    writeln!(out, "end_header").unwrap();
+    out
+}
+
+fn color_to_u8(c: f32) -> u8 {
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+pub fn write_ply_ascii(mesh: &Mesh) -> String {
+    let mut out = ply_header(mesh, "ascii");
+
+    for i in 0..mesh.vertices.len() {
+        let v = mesh.vertices[i];
+        write!(out, "{} {} {}", v.x, v.y, v.z).unwrap();
+        if let Some(n) = mesh.normals.get(i) {
+            write!(out, " {} {} {}", n.x, n.y, n.z).unwrap();
+        }
+        if let Some(uv) = mesh.uvs.get(i) {
+            write!(out, " {} {}", uv.x, uv.y).unwrap();
+        }
+        if let Some(c) = mesh.colors.get(i) {
+            write!(out, " {} {} {} {}", color_to_u8(c.x), color_to_u8(c.y), color_to_u8(c.z), color_to_u8(c.w)).unwrap();
+        }
+        out.push('\n');
+    }
+
+    for tri in mesh.indices.chunks_exact(3) {
+        writeln!(out, "3 {} {} {}", tri[0], tri[1], tri[2]).unwrap();
+    }
+
+    out
+}
+
+pub fn write_ply_binary(mesh: &Mesh) -> Vec<u8> {
+    let mut out = ply_header(mesh, "binary_little_endian").into_bytes();
+
+    for i in 0..mesh.vertices.len() {
+        let v = mesh.vertices[i];
+        for f in [v.x, v.y, v.z] {
+            out.extend_from_slice(&f.to_le_bytes());
+        }
+        if let Some(n) = mesh.normals.get(i) {
+            for f in [n.x, n.y, n.z] {
+                out.extend_from_slice(&f.to_le_bytes());
+            }
+        }
+        if let Some(uv) = mesh.uvs.get(i) {
+            for f in [uv.x, uv.y] {
+                out.extend_from_slice(&f.to_le_bytes());
+            }
+        }
+        if let Some(c) = mesh.colors.get(i) {
+            out.extend_from_slice(&[color_to_u8(c.x), color_to_u8(c.y), color_to_u8(c.z), color_to_u8(c.w)]);
+        }
+    }
+
+    for tri in mesh.indices.chunks_exact(3) {
+        out.push(3);
+        for index in tri {
+            out.extend_from_slice(&index.to_le_bytes());
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::resource_helper;
+    use crate::math::numerics::float4::Float4;
+
+    const ASSETS: [&str; 4] = ["Cube.obj", "Floor.obj", "SmallWorld.obj", "dragon.obj"];
+
+    fn temp_path(name: &str) -> String {
+        let dir = std::env::temp_dir().join("rasterizer_mesh_export");
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    fn assert_same_mesh(a: &Mesh, b: &Mesh, color_tolerance: f32) {
+        assert_eq!(a.indices, b.indices);
+        assert_eq!(a.vertices, b.vertices);
+        assert_eq!(a.normals, b.normals);
+        assert_eq!(a.uvs, b.uvs);
+        assert_eq!(a.colors.len(), b.colors.len());
+        for (ca, cb) in a.colors.iter().zip(&b.colors) {
+            let d = *ca - *cb;
+            assert!(d.x.abs().max(d.y.abs()).max(d.z.abs()) <= color_tolerance);
+        }
+    }
+
+    // OBJ faces are read back one vertex per corner, so compare what each triangle corner holds
+    fn assert_same_triangles(a: &Mesh, b: &Mesh) {
+        assert_eq!(a.indices.len(), b.indices.len());
+        for (ia, ib) in a.indices.iter().zip(&b.indices).map(|(ia, ib)| (*ia as usize, *ib as usize)) {
+            assert_eq!(a.vertices[ia], b.vertices[ib]);
+            assert_eq!(a.normals[ia], b.normals[ib]);
+            assert_eq!(a.uvs[ia], b.uvs[ib]);
+            assert_eq!(a.colors.get(ia), b.colors.get(ib));
+        }
+    }
+
+    #[test]
+    fn obj_round_trip() {
+        for asset in ASSETS {
+            let mesh = resource_helper::load_mesh(&format!("src/assets/{asset}"));
+            let path = temp_path(asset);
+            save_obj(&path, &mesh, Some(&ObjMaterial::new("material")));
+
+            assert_same_triangles(&mesh, &resource_helper::load_mesh(&path));
+            assert!(Path::new(&path).with_extension("mtl").exists());
+        }
+    }
+
+    #[test]
+    fn ply_round_trip() {
+        for asset in ASSETS {
+            let mesh = resource_helper::load_mesh(&format!("src/assets/{asset}"));
+            for binary in [false, true] {
+                let path = temp_path(&format!("{asset}.{binary}.ply"));
+                save_ply(&path, &mesh, binary);
+                assert_same_mesh(&mesh, &resource_helper::load_mesh(&path), 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn ply_headers_with_crlf_load() {
+        let mesh = resource_helper::load_mesh("src/assets/Cube.obj");
+
+        let ascii = write_ply_ascii(&mesh).replace('\n', "\r\n");
+        let path = temp_path("crlf.ascii.ply");
+        fs::write(&path, ascii).unwrap();
+        assert_same_mesh(&mesh, &resource_helper::load_mesh(&path), 0.0);
+
+        // Only the header is text in binary files
+        let binary = write_ply_binary(&mesh);
+        let header_len = binary.windows(11).position(|w| w == b"end_header\n").unwrap() + 11;
+        let mut crlf = String::from_utf8(binary[..header_len].to_vec()).unwrap().replace('\n', "\r\n").into_bytes();
+        crlf.extend_from_slice(&binary[header_len..]);
+        let path = temp_path("crlf.binary.ply");
+        fs::write(&path, crlf).unwrap();
+        assert_same_mesh(&mesh, &resource_helper::load_mesh(&path), 0.0);
+    }
+
+    #[test]
+    fn vertex_colors_round_trip() {
+        let mut mesh = resource_helper::load_mesh("src/assets/Cube.obj");
+        mesh.colors = (0..mesh.vertices.len())
+            .map(|i| Float4::new(i as f32 / mesh.vertices.len() as f32, 0.25, 1.0, 1.0))
+            .collect();
+
+        let obj_path = temp_path("colored.obj");
+        save_obj(&obj_path, &mesh, None);
+        assert_same_triangles(&mesh, &resource_helper::load_mesh(&obj_path));
+
+        for binary in [false, true] {
+            let ply_path = temp_path(&format!("colored.{binary}.ply"));
+            save_ply(&ply_path, &mesh, binary);
+            assert_same_mesh(&mesh, &resource_helper::load_mesh(&ply_path), 1.0 / 255.0);
+        }
+    }
+}
\ No newline at end of file
@@ -1,3 +1,12 @@
+pub mod actions;
+pub mod aseprite;
+pub mod camera_controller;
+pub mod assets;
 pub mod engine;
 pub mod input;
-pub mod resource_helper;
\ No newline at end of file
+pub mod mesh_export;
+pub mod recording;
+pub mod resource_helper;
+pub mod scene_file;
+pub mod scene_manager;
+pub mod time;
\ No newline at end of file
@@ -0,0 +1,243 @@
+use std::{collections::HashSet, fmt, fs, path::Path};
+
+use crate::{core::input::{self, RawInput}, math::numerics::float2::Float2};
+
+// Per-frame input captured by the engine, one line per frame:
+//
+//   recording 1
+//   frame 0.016666668 1408x792 keys=W,LeftShift mouse=512,300 buttons=100 scroll=0,0 text=104,105
+//
+// `mouse=-` is a frame without a cursor position, `buttons` are left, middle and right,
+// `text` is unicode code points. Floats are written so they read back bit-exact.
+
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedFrame {
+    pub delta_time: f32,
+    pub width: u32,
+    pub height: u32,
+    pub input: RawInput,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Recording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, delta_time: f32, width: u32, height: u32, input: RawInput) {
+        self.frames.push(RecordedFrame { delta_time, width, height, input });
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).expect("Failed to read recording");
+        Self::parse(&text).unwrap_or_else(|e| panic!("Failed to parse recording {}: {e}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) {
+        fs::write(path, self.to_string()).expect("Failed to write recording");
+    }
+
+    pub fn parse(text: &str) -> Result<Recording, String> {
+        let mut lines = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+
+        match lines.next().map(|(_, l)| l.split_whitespace().collect::<Vec<_>>()).as_deref() {
+            Some(["recording", version]) if version.parse() == Ok(VERSION) => {}
+            Some(["recording", version]) => return Err(format!("unsupported version {version}")),
+            _ => return Err("missing `recording` header".to_string()),
+        }
+
+        let mut recording = Recording::new();
+        for (line_number, line) in lines {
+            let frame = parse_frame(line).map_err(|e| format!("line {}: {e}", line_number + 1))?;
+            recording.frames.push(frame);
+        }
+        Ok(recording)
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "recording {VERSION}")?;
+
+        for frame in &self.frames {
+            let input = &frame.input;
+
+            // Sorted so the same input always writes the same line
+            let mut keys: Vec<String> = input.keys.iter().map(|k| format!("{k:?}")).collect();
+            keys.sort();
+            let mouse = input.mouse_position.map_or("-".to_string(), |p| format!("{},{}", p.x, p.y));
+            let buttons: String = input.mouse_buttons.iter().map(|&b| if b { '1' } else { '0' }).collect();
+            let text: Vec<String> = input.text.iter().map(|&c| (c as u32).to_string()).collect();
+
+            writeln!(
+                f, "frame {} {}x{} keys={} mouse={mouse} buttons={buttons} scroll={},{} text={}",
+                frame.delta_time, frame.width, frame.height, keys.join(","), input.scroll.x, input.scroll.y, text.join(",")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_float(text: &str) -> Result<f32, String> {
+    text.parse().map_err(|_| format!("invalid number `{text}`"))
+}
+
+fn parse_float2(text: &str) -> Result<Float2, String> {
+    let (x, y) = text.split_once(',').ok_or_else(|| format!("expected `x,y`, got `{text}`"))?;
+    Ok(Float2::new(parse_float(x)?, parse_float(y)?))
+}
+
+fn parse_frame(line: &str) -> Result<RecordedFrame, String> {
+    let mut parts = line.split_whitespace();
+    if parts.next() != Some("frame") {
+        return Err("expected `frame`".to_string());
+    }
+
+    let delta_time = parse_float(parts.next().ok_or("missing frame delta")?)?;
+    let size = parts.next().ok_or("missing frame size")?;
+    let (width, height) = size.split_once('x')
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .ok_or_else(|| format!("invalid size `{size}`"))?;
+
+    let mut input = RawInput::default();
+    for part in parts {
+        let (name, value) = part.split_once('=').ok_or_else(|| format!("expected `name=value`, got `{part}`"))?;
+        match name {
+            "keys" => {
+                input.keys = value.split(',').filter(|k| !k.is_empty())
+                    .map(|k| input::key_from_name(k).ok_or_else(|| format!("unknown key `{k}`")))
+                    .collect::<Result<HashSet<_>, _>>()?;
+            }
+            "mouse" => input.mouse_position = if value == "-" { None } else { Some(parse_float2(value)?) },
+            "buttons" => {
+                let buttons: Vec<char> = value.chars().collect();
+                if buttons.len() != 3 || buttons.iter().any(|c| *c != '0' && *c != '1') {
+                    return Err(format!("invalid buttons `{value}`"));
+                }
+                input.mouse_buttons = [buttons[0] == '1', buttons[1] == '1', buttons[2] == '1'];
+            }
+            "scroll" => input.scroll = parse_float2(value)?,
+            "text" => {
+                input.text = value.split(',').filter(|c| !c.is_empty())
+                    .map(|c| c.parse().ok().and_then(char::from_u32).ok_or_else(|| format!("invalid character `{c}`")))
+                    .collect::<Result<_, _>>()?;
+            }
+            other => return Err(format!("unknown field `{other}`")),
+        }
+    }
+
+    Ok(RecordedFrame { delta_time, width, height, input })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minifb::Key;
+    use crate::core::{camera_controller::{CameraController, FlyCamera}, engine::{Engine, LoopSettings}, resource_helper};
+    use crate::math::numerics::{float3::Float3, float4::Float4};
+    use crate::rasterizer::{camera::Camera, rasterizer, render_target::RenderTarget};
+    use crate::shaders::{shader_base::Shader, texture_shader::TextureShader};
+    use crate::ecs::{scripts::Scripts, systems::{self, Schedule}, world::World};
+    use crate::types::{handle::Handle, mesh_builder, mesh_renderer::MeshRenderer, scene::Scene, transform::Transform};
+
+    struct CubeScene {
+        world: World,
+        schedule: Schedule,
+    }
+
+    impl Scene for CubeScene {
+        fn start(&mut self, _render_target: &mut RenderTarget) {
+            let texture = Handle::new(resource_helper::load_texture("src/assets/color.png"));
+            let shader = Shader::TextureShader(TextureShader::new(texture, false));
+            self.world.spawn().with(Transform::default()).with(MeshRenderer::new(Handle::new(mesh_builder::cube(0.5)), shader));
+
+            let cam = Camera::new(90.0);
+            let mut transform = Transform::default();
+            transform.set_position(Float3::new(0.0, 0.0, -1.5));
+            let controller = CameraController::Fly(FlyCamera::new(&cam));
+            self.world.spawn().with(transform).with(cam).with(Scripts::new().with(controller));
+        }
+
+        fn update(&mut self, delta_time: f32, render_target: &mut RenderTarget) {
+            self.schedule.run(&mut self.world, delta_time);
+            render_target.clear(Float4::ZERO);
+            rasterizer::render(render_target, &self.world, &systems::active_camera(&self.world).unwrap());
+        }
+
+        fn resize(&mut self, _new_width: u32, _new_height: u32, _render_target: &mut RenderTarget) {}
+    }
+
+    fn cube_scene() -> CubeScene {
+        CubeScene { world: World::new(), schedule: Schedule::default() }
+    }
+
+    // Walks forward, strafes while turning with the mouse and types a little
+    fn sample_recording() -> Recording {
+        let mut recording = Recording::new();
+        for i in 0..30 {
+            let mut input = RawInput::default();
+            if i < 20 {
+                input.keys.insert(Key::W);
+            }
+            if i % 3 == 0 {
+                input.keys.insert(Key::D);
+            }
+            input.mouse_position = (i != 5).then(|| Float2::new(40.0 + i as f32 * 1.5, 30.0 - i as f32 * 0.25));
+            input.mouse_buttons = [false, false, i > 10];
+            input.scroll = Float2::new(0.0, if i == 12 { -1.0 } else { 0.0 });
+            if i == 2 {
+                input.text = vec!['w', 'é'];
+            }
+
+            let size = if i < 15 { (64, 48) } else { (48, 64) };
+            recording.push(1.0 / 60.0 + (i % 4) as f32 * 0.0013, size.0, size.1, input);
+        }
+        recording
+    }
+
+    fn replay_checksums(recording: &Recording, timestep: Option<f32>) -> Vec<u64> {
+        let mut checksums = Vec::new();
+        Engine::replay_headless(&mut cube_scene(), recording, timestep, LoopSettings::default(), |_, render_target| checksums.push(render_target.checksum()));
+        checksums
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let recording = sample_recording();
+        let text = recording.to_string();
+        assert_eq!(Recording::parse(&text).unwrap(), recording);
+        assert_eq!(Recording::parse(&text).unwrap().to_string(), text);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Recording::parse("").is_err());
+        assert!(Recording::parse("recording 99").is_err());
+        let error = Recording::parse("recording 1\nframe 0.1 10x10 keys=NotAKey").unwrap_err();
+        assert!(error.starts_with("line 2"));
+    }
+
+    #[test]
+    fn replay_is_deterministic() {
+        let _lock = input::lock_for_test();
+        let recording = Recording::parse(&sample_recording().to_string()).unwrap();
+
+        let recorded = replay_checksums(&recording, None);
+        assert_eq!(recorded.len(), recording.frames.len());
+        assert_eq!(recorded, replay_checksums(&recording, None));
+
+        // The camera moves, so frames differ
+        assert_ne!(recorded[0], recorded[19]);
+
+        let fixed = replay_checksums(&recording, Some(1.0 / 30.0));
+        assert_eq!(fixed, replay_checksums(&recording, Some(1.0 / 30.0)));
+        assert_ne!(fixed, recorded);
+    }
+}
\ No newline at end of file
@@ -1,62 +1,132 @@
 use std::{fs, path::Path};
 
-use crate::{math::numerics::{float2::Float2, float3::Float3, float4::Float4}, types::{mesh::Mesh, texture::Texture}};
+use image::{DynamicImage, ImageFormat, metadata::CicpTransferCharacteristics};
+
+use crate::{core::aseprite::AsepriteFile, math::numerics::{float2::Float2, float3::Float3, float4::Float4}, types::{animated_texture::AnimatedTexture, mesh::Mesh, texture::{ColorSpace, Texture}}};
 
 pub fn load_mesh(path: &str) -> Mesh {
-    let obj_string = fs::read_to_string(&path).expect("Failed to read obj file");
+    if extension(path).is_some_and(|e| e.eq_ignore_ascii_case("ply")) {
+        let bytes = fs::read(path).expect("Failed to read ply file");
+        return load_ply(&bytes);
+    }
+
+    let obj_string = fs::read_to_string(path).expect("Failed to read obj file");
     load_obj(&obj_string)
 }
 
 pub fn load_texture(path: &str) -> Texture {
-    load_texture_png(&path)
+    let bytes = fs::read(path).expect("Failed to read texture file");
+    load_texture_from_memory(&bytes, extension(path)).unwrap_or_else(|e| panic!("Failed to load texture {path}: {e}"))
+}
+
+// All frames of an .aseprite file, other formats give a single frame
+pub fn load_animated_texture(path: &str) -> AnimatedTexture {
+    let bytes = fs::read(path).expect("Failed to read texture file");
+    let animated = if is_aseprite(&bytes) {
+        AsepriteFile::parse(&bytes).map(|file| file.to_animated_texture())
+    } else {
+        load_texture_from_memory(&bytes, extension(path)).map(|texture| AnimatedTexture::new(vec![texture], vec![0.0]))
+    };
+    animated.unwrap_or_else(|e| panic!("Failed to load texture {path}: {e}"))
+}
+
+// Decode a texture from an encoded file in memory (embedded glTF / bbmodel images).
+// The format is detected from the data, `format_hint` is a file extension or mime type
+// and is needed for formats without a signature like TGA.
+pub fn load_texture_from_memory(bytes: &[u8], format_hint: Option<&str>) -> Result<Texture, String> {
+    if is_aseprite(bytes) {
+        return AsepriteFile::parse(bytes).map(|file| file.flatten_frame(0));
+    }
+
+    let format = image::guess_format(bytes).ok()
+        .or_else(|| format_hint.and_then(|hint| {
+            ImageFormat::from_extension(hint).or_else(|| ImageFormat::from_mime_type(hint))
+        }))
+        .ok_or("unknown texture format")?;
+
+    let img = image::load_from_memory_with_format(bytes, format).map_err(|e| e.to_string())?;
+    Ok(texture_from_image(&img, source_color_space(&img, format)))
+}
+
+fn extension(path: &str) -> Option<&str> {
+    Path::new(path).extension().and_then(|e| e.to_str())
+}
+
+fn is_aseprite(bytes: &[u8]) -> bool {
+    bytes.len() >= 6 && bytes[4] == 0xE0 && bytes[5] == 0xA5
 }
 
+// HDR and EXR always store linear radiance, everything else is sRGB unless tagged otherwise
+fn source_color_space(img: &DynamicImage, format: ImageFormat) -> ColorSpace {
+    match format {
+        ImageFormat::Hdr | ImageFormat::OpenExr => ColorSpace::Linear,
+        _ if img.color_space().transfer == CicpTransferCharacteristics::Linear => ColorSpace::Linear,
+        _ => ColorSpace::Srgb,
+    }
+}
+
+// Faces meeting at a sharper angle keep a hard edge when normals have to be generated
+const DEFAULT_CREASE_ANGLE: f32 = 60.0;
+
+// Meshes without normals get smooth normals
 fn load_obj(model_string: &str) -> Mesh {
     let lines = split_by_line(model_string, true);
 
     let mut vertex_positions = Vec::<Float3>::new();
+    let mut vertex_colors = Vec::<Float4>::new();
     let mut normals_src = Vec::<Float3>::new();
     let mut uvs_src = Vec::<Float2>::new();
 
     let mut positions = Vec::<Float3>::new();
     let mut normals = Vec::<Float3>::new();
     let mut uvs = Vec::<Float2>::new();
+    let mut colors = Vec::<Float4>::new();
     let mut indices = Vec::<u32>::new();
 
     for line in lines {
-        if line.starts_with("v ") {
-            let p: Vec<&str> = line[2..].split_whitespace().collect();
+        if let Some(rest) = line.strip_prefix("v ") {
+            let p: Vec<&str> = rest.split_whitespace().collect();
             vertex_positions.push(Float3 {
                 x: p[0].parse().unwrap(),
                 y: p[1].parse().unwrap(),
                 z: p[2].parse().unwrap(),
             });
+
+            // Non-standard but common extension: v x y z r g b [a]
+            if p.len() >= 6 {
+                vertex_colors.push(Float4 {
+                    x: p[3].parse().unwrap(),
+                    y: p[4].parse().unwrap(),
+                    z: p[5].parse().unwrap(),
+                    w: p.get(6).map(|a| a.parse().unwrap()).unwrap_or(1.0),
+                });
+            }
         }
-        else if line.starts_with("vn ") {
-            let p: Vec<&str> = line[3..].split_whitespace().collect();
+        else if let Some(rest) = line.strip_prefix("vn ") {
+            let p: Vec<&str> = rest.split_whitespace().collect();
             normals_src.push(Float3 {
                 x: p[0].parse().unwrap(),
                 y: p[1].parse().unwrap(),
                 z: p[2].parse().unwrap(),
             });
         }
-        else if line.starts_with("vt ") {
-            let p: Vec<&str> = line[3..].split_whitespace().collect();
+        else if let Some(rest) = line.strip_prefix("vt ") {
+            let p: Vec<&str> = rest.split_whitespace().collect();
             uvs_src.push(Float2 {
                 x: p[0].parse().unwrap(),
                 y: p[1].parse().unwrap(),
             });
         }
-        else if line.starts_with("f ") {
+        else if let Some(rest) = line.strip_prefix("f ") {
             let groups: Vec<&str> =
-                line[2..].split_whitespace().collect();
+                rest.split_whitespace().collect();
 
             let mut face_indices = Vec::<u32>::new();
 
             for g in groups {
                 let e: Vec<&str> = g.split('/').collect();
 
-                let v = e.get(0).and_then(|s| s.parse::<usize>().ok());
+                let v = e.first().and_then(|s| s.parse::<usize>().ok());
                 let t = e.get(1).and_then(|s| s.parse::<usize>().ok());
                 let n = e.get(2).and_then(|s| s.parse::<usize>().ok());
 
@@ -69,6 +139,9 @@ fn load_obj(model_string: &str) -> Mesh {
                 positions.push(pos);
                 normals.push(norm);
                 uvs.push(uv);
+                if !vertex_colors.is_empty() {
+                    colors.push(v.map(|i| vertex_colors[i - 1]).unwrap_or(Float4::ONE));
+                }
 
                 face_indices.push(index);
             }
@@ -82,34 +155,180 @@ fn load_obj(model_string: &str) -> Mesh {
         }
     }
 
-    Mesh::new(positions, indices, normals, uvs)
+    let mut mesh = Mesh::new(positions, indices, normals, uvs);
+    mesh.colors = colors;
+    if normals_src.is_empty() {
+        mesh.compute_smooth_normals(DEFAULT_CREASE_ANGLE);
+    }
+    mesh
 }
 
-fn load_texture_png<P: AsRef<Path>>(path: P) -> Texture {
-    let img = image::open(path).expect("Failed to load texture");
-    let rgba = img.to_rgba8();
-    let (width, height) = rgba.dimensions();
+// Reads ascii and binary little/big endian PLY. Missing uvs are filled with zeros
+// like in load_obj, so every vertex attribute has the same length. Missing normals are generated.
+fn load_ply(bytes: &[u8]) -> Mesh {
+    // Headers written on Windows end their lines with \r\n
+    let marker_end = bytes.windows(10).position(|w| w == b"end_header").expect("Invalid ply header") + 10;
+    let header_end = match &bytes[marker_end..] {
+        [b'\n', ..] => marker_end + 1,
+        [b'\r', b'\n', ..] => marker_end + 2,
+        _ => panic!("Invalid ply header"),
+    };
+    let header = std::str::from_utf8(&bytes[..header_end]).expect("Invalid ply header");
 
-    // y first, then x
-    let mut image_data = vec![vec![Float4::ZERO; width as usize]; height as usize];
+    let mut format = "";
+    let mut elements: Vec<(&str, usize, Vec<PlyProperty>)> = Vec::new();
 
-    for y in 0..height {
-        for x in 0..width {
-            let [r, g, b, a] = rgba.get_pixel(x, y).0;
+    for line in split_by_line(header, true) {
+        let p: Vec<&str> = line.split_whitespace().collect();
+        match p.first() {
+            Some(&"format") => format = p[1],
+            Some(&"element") => elements.push((p[1], p[2].parse().unwrap(), Vec::new())),
+            Some(&"property") if p[1] == "list" => elements.last_mut().unwrap().2.push((p[4], p[3], Some(p[2]))),
+            Some(&"property") => elements.last_mut().unwrap().2.push((p[2], p[1], None)),
+            _ => {}
+        }
+    }
 
-            // Flip y to match typical texture coordinate convention
-            let flipped_y = height - 1 - y;
-
-            image_data[flipped_y as usize][x as usize] = Float4 {
-                x: r as f32 / 255.0,
-                y: g as f32 / 255.0,
-                z: b as f32 / 255.0,
-                w: a as f32 / 255.0,
-            };
+    let mut reader = PlyReader::new(&bytes[header_end..], format);
+
+    let has_normals = elements.iter().any(|(name, _, properties)| *name == "vertex" && properties.iter().any(|p| p.0 == "nx"));
+
+    let mut mesh = Mesh::empty();
+    for (name, count, properties) in &elements {
+        let has_colors = properties.iter().any(|p| p.0 == "red");
+
+        for _ in 0..*count {
+            let mut position = Float3::ZERO;
+            let mut normal = Float3::ZERO;
+            let mut uv = Float2::ZERO;
+            let mut color = Float4::ONE;
+            let mut face = Vec::<u32>::new();
+
+            for (prop, ty, list_type) in properties {
+                if let Some(list_type) = list_type {
+                    let len = reader.read(list_type) as usize;
+                    for _ in 0..len {
+                        face.push(reader.read(ty) as u32);
+                    }
+                    continue;
+                }
+
+                let value = reader.read(ty);
+                // Integer colors are 0..255, float colors 0..1
+                let channel = if matches!(*ty, "uchar" | "uint8") { value as f32 / 255.0 } else { value as f32 };
+                match *prop {
+                    "x" => position.x = value as f32,
+                    "y" => position.y = value as f32,
+                    "z" => position.z = value as f32,
+                    "nx" => normal.x = value as f32,
+                    "ny" => normal.y = value as f32,
+                    "nz" => normal.z = value as f32,
+                    "s" | "u" | "texture_u" => uv.x = value as f32,
+                    "t" | "v" | "texture_v" => uv.y = value as f32,
+                    "red" => color.x = channel,
+                    "green" => color.y = channel,
+                    "blue" => color.z = channel,
+                    "alpha" => color.w = channel,
+                    _ => {}
+                }
+            }
+
+            if *name == "vertex" {
+                mesh.vertices.push(position);
+                mesh.normals.push(normal);
+                mesh.uvs.push(uv);
+                if has_colors {
+                    mesh.colors.push(color);
+                }
+            }
+            else if *name == "face" {
+                // Fan triangulation
+                for i in 2..face.len() {
+                    mesh.indices.push(face[0]);
+                    mesh.indices.push(face[i - 1]);
+                    mesh.indices.push(face[i]);
+                }
+            }
+        }
+    }
+
+    if !has_normals {
+        mesh.compute_smooth_normals(DEFAULT_CREASE_ANGLE);
+    }
+    mesh
+}
+
+// (name, type, count type for lists)
+type PlyProperty<'a> = (&'a str, &'a str, Option<&'a str>);
+
+struct PlyReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    ascii_tokens: Option<std::str::SplitAsciiWhitespace<'a>>,
+    big_endian: bool,
+}
+
+impl<'a> PlyReader<'a> {
+    fn new(data: &'a [u8], format: &str) -> Self {
+        let ascii_tokens = (format == "ascii")
+            .then(|| std::str::from_utf8(data).expect("Invalid ascii ply").split_ascii_whitespace());
+        Self {
+            data,
+            pos: 0,
+            ascii_tokens,
+            big_endian: format == "binary_big_endian",
         }
     }
 
-    Texture::new(image_data)
+    fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        let mut b: [u8; N] = self.data[self.pos..self.pos + N].try_into().unwrap();
+        self.pos += N;
+        if self.big_endian {
+            b.reverse();
+        }
+        b
+    }
+
+    fn read(&mut self, ty: &str) -> f64 {
+        if let Some(tokens) = &mut self.ascii_tokens {
+            return tokens.next().expect("Unexpected end of ply data").parse().unwrap();
+        }
+
+        match ty {
+            "char" | "int8" => i8::from_le_bytes(self.bytes()) as f64,
+            "uchar" | "uint8" => u8::from_le_bytes(self.bytes()) as f64,
+            "short" | "int16" => i16::from_le_bytes(self.bytes()) as f64,
+            "ushort" | "uint16" => u16::from_le_bytes(self.bytes()) as f64,
+            "int" | "int32" => i32::from_le_bytes(self.bytes()) as f64,
+            "uint" | "uint32" => u32::from_le_bytes(self.bytes()) as f64,
+            "float" | "float32" => f32::from_le_bytes(self.bytes()) as f64,
+            "double" | "float64" => f64::from_le_bytes(self.bytes()),
+            _ => panic!("Unsupported ply property type: {ty}"),
+        }
+    }
+}
+
+// 8-bit sources stay 8-bit, 16-bit and HDR sources go through 32-bit float to keep their precision and range
+fn texture_from_image(img: &DynamicImage, color_space: ColorSpace) -> Texture {
+    let (width, height) = (img.width(), img.height());
+
+    let texture = match img {
+        DynamicImage::ImageLuma8(_) | DynamicImage::ImageLumaA8(_) | DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => {
+            let rgba = img.to_rgba8();
+            // Flip y to match typical texture coordinate convention
+            let texels = rgba.rows().rev().flat_map(|row| row.map(|p| p.0)).collect();
+            Texture::from_rgba8(width, height, texels)
+        }
+        _ => {
+            let rgba = img.to_rgba32f();
+            let texels = rgba.rows().rev()
+                .flat_map(|row| row.map(|p| Float4::new(p.0[0], p.0[1], p.0[2], p.0[3])))
+                .collect();
+            Texture::from_texels(width, height, texels)
+        }
+    };
+
+    texture.with_color_space(color_space).compact()
 }
 
 pub fn split_by_line(text: &str, remove_empty: bool) -> Vec<&str> {
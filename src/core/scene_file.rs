@@ -0,0 +1,567 @@
+use std::{collections::HashMap, fmt::Write as _, fs, path::Path};
+
+use crate::core::{assets, camera_controller::{CameraController, FirstPersonCamera, FlyCamera, OrbitCamera}};
+use crate::ecs::{components::{Light, Name}, scripts::{Bob, Scripts, Spin}, world::{Entity, World}};
+use crate::math::numerics::{float3::Float3, float4::Float4};
+use crate::post::{bloom::Bloom, color_grading::ColorGrading, depth_of_field::DepthOfField, fog::Fog, fxaa::Fxaa, stack::{PostPass, PostStack}, vignette::Vignette};
+use crate::rasterizer::{camera::{Camera, Projection, Viewport}, ssao::Ssao};
+use crate::shaders::{animated_texture_shader::AnimatedTextureShader, lit_texture_shader::LitTextureShader, shader_base::Shader, texture_shader::TextureShader, transparent_texture_shader::TransparentTextureShader};
+use crate::types::{lod::LodGroup, mesh_renderer::{MeshRenderer, Wireframe}, texture::TextureFilter, transform::Transform};
+
+// Scenes as text, one entity per block and one component per line:
+//
+//   entity Dragon
+//       transform position=0,0.05,0 rotation=0,0,0 scale=0.2,0.2,0.2
+//       renderer mesh=dragon.obj shader=lit texture=color.png wireframe=true lod=4,0.5,0.4 crossfade=0.3
+//       spin speed=0,0.2,0
+//
+//   entity Camera
+//       transform position=0,0.2,-1
+//       camera fov=100
+//       controller fly
+//       ssao radius=0.05
+//       post fog density=0.3
+//       post fxaa
+//
+// Components:
+//   transform  position, rotation (pitch,yaw,roll in radians), scale, forward (instead of rotation)
+//   renderer   mesh, shader=texture|lit|transparent|animated, texture, filter=nearest|bilinear, wireframe, alpha (transparent),
+//              lod=levels,ratio,first_screen_size, crossfade, hysteresis,
+//              edge_color=r,g,b,a, edge_width (pixels), edge_overlay (edges over the filled mesh)
+//   camera     fov | orthographic=size | frustum=left,right,bottom,top, aspect, viewport=x,y,width,height
+//   light      directional
+//   controller fly [speed] | orbit [target, distance] | first_person
+//   spin       speed (radians per second)
+//   bob        amplitude, frequency
+//   ssao       radius, intensity, bias, samples, blur (ambient occlusion for the camera's lit shaders)
+//   post       one post-processing pass per line, run in file order, each takes enabled=false:
+//              fxaa [span_max, edge_threshold, edge_threshold_min] | bloom [threshold, intensity, radius] |
+//              vignette [intensity, radius, softness] | color_grading lut [strength] |
+//              depth_of_field [focus, range, radius] | fog [color=r,g,b,a, density, start]
+//
+// Asset paths are resolved by core::assets. `#` starts a comment, indentation is only for reading.
+
+pub fn load(path: impl AsRef<Path>) -> World {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).expect("Failed to read scene file");
+    parse(&text).unwrap_or_else(|e| panic!("Failed to parse scene file {}: {e}", path.display()))
+}
+
+pub fn save(world: &World, path: impl AsRef<Path>) {
+    fs::write(path, to_string(world)).expect("Failed to write scene file");
+}
+
+pub fn parse(text: &str) -> Result<World, String> {
+    let mut world = World::new();
+    let mut entity: Option<Entity> = None;
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let error = |message: String| format!("line {}: {message}", line_number + 1);
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+        if keyword == "entity" {
+            let mut builder = world.spawn();
+            if !rest.trim().is_empty() {
+                builder = builder.with(Name::new(rest.trim()));
+            }
+            entity = Some(builder.id());
+            continue;
+        }
+
+        let entity = entity.ok_or_else(|| error(format!("`{keyword}` outside of an entity")))?;
+        let mut fields = Fields::parse(rest).map_err(error)?;
+        parse_component(&mut world, entity, keyword, &mut fields).map_err(error)?;
+        fields.finish().map_err(error)?;
+    }
+
+    Ok(world)
+}
+
+fn parse_component(world: &mut World, entity: Entity, keyword: &str, fields: &mut Fields) -> Result<(), String> {
+    match keyword {
+        "transform" => {
+            let mut transform = Transform::default();
+            transform.set_position(fields.float3("position")?.unwrap_or(Float3::ZERO));
+            transform.set_rotation(fields.float3("rotation")?.unwrap_or(Float3::ZERO));
+            transform.set_scale(fields.float3("scale")?.unwrap_or(Float3::ONE));
+            if let Some(forward) = fields.float3("forward")? {
+                transform.set_forward(forward);
+            }
+            world.insert(entity, transform);
+        }
+        "renderer" => {
+            let mesh = assets::load_mesh(&fields.required("mesh")?);
+            let texture = fields.required("texture")?;
+            let wireframe = fields.bool("wireframe")?.unwrap_or(false);
+
+            let mut shader = match fields.flag_or("shader", "texture")?.as_str() {
+                "texture" => Shader::TextureShader(TextureShader::new(assets::load_texture(&texture), wireframe)),
+                "lit" => Shader::LitTextureShader(LitTextureShader::new(Float3::UNIT_Y, assets::load_texture(&texture), wireframe)),
+                "transparent" => {
+                    let alpha = fields.float("alpha")?.unwrap_or(1.0);
+                    Shader::TransparentTextureShader(TransparentTextureShader::new(assets::load_texture(&texture), alpha))
+                }
+                "animated" => Shader::AnimatedTextureShader(AnimatedTextureShader::new(assets::load_animated_texture(&texture), wireframe)),
+                other => return Err(format!("unknown shader `{other}`")),
+            };
+            match fields.flag_or("filter", "nearest")?.as_str() {
+                "nearest" => {}
+                "bilinear" => shader.set_filter(TextureFilter::Bilinear),
+                other => return Err(format!("unknown filter `{other}`")),
+            }
+
+            let mut renderer = MeshRenderer::new(mesh.clone(), shader);
+            if let Some(lod) = fields.floats("lod", 3)? {
+                let mut group = LodGroup::generate(mesh, lod[0] as usize, lod[1], lod[2]);
+                group.crossfade_duration = fields.float("crossfade")?.unwrap_or(0.0);
+                group.hysteresis = fields.float("hysteresis")?.unwrap_or(group.hysteresis);
+                renderer = renderer.with_lod(group);
+            }
+            if let Some(c) = fields.floats("edge_color", 4)? {
+                renderer.wireframe.color = Float4::new(c[0], c[1], c[2], c[3]);
+            }
+            renderer.wireframe.width = fields.float("edge_width")?.unwrap_or(renderer.wireframe.width);
+            renderer.wireframe.overlay = fields.bool("edge_overlay")?.unwrap_or(false);
+            world.insert(entity, renderer);
+        }
+        "camera" => {
+            let projection = if let Some(size) = fields.float("orthographic")? {
+                Projection::Orthographic { size }
+            } else if let Some(f) = fields.floats("frustum", 4)? {
+                Projection::Frustum { left: f[0], right: f[1], bottom: f[2], top: f[3] }
+            } else {
+                Projection::Perspective { fov_degrees: fields.float("fov")?.unwrap_or(60.0) }
+            };
+
+            let mut camera = Camera::with_projection(projection);
+            camera.aspect = fields.float("aspect")?;
+            if let Some(v) = fields.floats("viewport", 4)? {
+                camera.viewport = Viewport::new(v[0], v[1], v[2], v[3]);
+            }
+            if let Some(transform) = world.get::<Transform>(entity) {
+                camera.transform = transform.clone();
+            }
+            world.insert(entity, camera);
+        }
+        "light" => {
+            match fields.flag_or("", "directional")?.as_str() {
+                "directional" => world.insert(entity, Light::Directional),
+                other => return Err(format!("unknown light `{other}`")),
+            };
+        }
+        "controller" => {
+            let camera = world.get::<Camera>(entity).map(|c| c.clone()).ok_or("controller needs a camera before it")?;
+            let controller = match fields.flag_or("", "fly")?.as_str() {
+                "fly" => {
+                    let mut fly = FlyCamera::new(&camera);
+                    fly.speed = fields.float("speed")?.unwrap_or(fly.speed);
+                    CameraController::Fly(fly)
+                }
+                "orbit" => CameraController::Orbit(OrbitCamera::new(fields.float3("target")?.unwrap_or(Float3::ZERO), fields.float("distance")?.unwrap_or(1.0))),
+                "first_person" => CameraController::FirstPerson(FirstPersonCamera::new(&camera)),
+                other => return Err(format!("unknown controller `{other}`")),
+            };
+            add_script(world, entity, Scripts::new().with(controller));
+        }
+        "spin" => add_script(world, entity, Scripts::new().with(Spin::new(fields.float3("speed")?.unwrap_or(Float3::ZERO)))),
+        "bob" => {
+            let bob = Bob::new(fields.float("amplitude")?.unwrap_or(0.1), fields.float("frequency")?.unwrap_or(1.0));
+            add_script(world, entity, Scripts::new().with(bob));
+        }
+        "ssao" => {
+            let d = Ssao::default();
+            world.insert(entity, Ssao {
+                radius: fields.float("radius")?.unwrap_or(d.radius),
+                intensity: fields.float("intensity")?.unwrap_or(d.intensity),
+                bias: fields.float("bias")?.unwrap_or(d.bias),
+                samples: fields.float("samples")?.map_or(d.samples, |s| s.max(0.0) as u32),
+                blur: fields.bool("blur")?.unwrap_or(d.blur),
+            });
+        }
+        "post" => {
+            let enabled = fields.bool("enabled")?.unwrap_or(true);
+            add_post_pass(world, entity, parse_post_pass(fields)?, enabled);
+        }
+        other => return Err(format!("unknown component `{other}`")),
+    }
+    Ok(())
+}
+
+fn parse_post_pass(fields: &mut Fields) -> Result<PostPass, String> {
+    let pass = match fields.required("")?.as_str() {
+        "fxaa" => {
+            let d = Fxaa::default();
+            PostPass::Fxaa(Fxaa {
+                span_max: fields.float("span_max")?.unwrap_or(d.span_max),
+                edge_threshold: fields.float("edge_threshold")?.unwrap_or(d.edge_threshold),
+                edge_threshold_min: fields.float("edge_threshold_min")?.unwrap_or(d.edge_threshold_min),
+            })
+        }
+        "bloom" => {
+            let d = Bloom::default();
+            PostPass::Bloom(Bloom {
+                threshold: fields.float("threshold")?.unwrap_or(d.threshold),
+                intensity: fields.float("intensity")?.unwrap_or(d.intensity),
+                radius: fields.float("radius")?.unwrap_or(d.radius),
+            })
+        }
+        "vignette" => {
+            let d = Vignette::default();
+            PostPass::Vignette(Vignette {
+                intensity: fields.float("intensity")?.unwrap_or(d.intensity),
+                radius: fields.float("radius")?.unwrap_or(d.radius),
+                softness: fields.float("softness")?.unwrap_or(d.softness),
+            })
+        }
+        "color_grading" => {
+            let mut grading = ColorGrading::load(&fields.required("lut")?)?;
+            grading.strength = fields.float("strength")?.unwrap_or(grading.strength);
+            PostPass::ColorGrading(grading)
+        }
+        "depth_of_field" => {
+            let d = DepthOfField::default();
+            PostPass::DepthOfField(DepthOfField {
+                focus_distance: fields.float("focus")?.unwrap_or(d.focus_distance),
+                focus_range: fields.float("range")?.unwrap_or(d.focus_range),
+                max_radius: fields.float("radius")?.unwrap_or(d.max_radius),
+            })
+        }
+        "fog" => {
+            let d = Fog::default();
+            let color = fields.floats("color", 4)?.map_or(d.color, |c| Float4::new(c[0], c[1], c[2], c[3]));
+            PostPass::Fog(Fog { color, density: fields.float("density")?.unwrap_or(d.density), start: fields.float("start")?.unwrap_or(d.start) })
+        }
+        other => return Err(format!("unknown post pass `{other}`")),
+    };
+    Ok(pass)
+}
+
+// Scripts from several lines end up in one component, in file order
+fn add_script(world: &mut World, entity: Entity, scripts: Scripts) {
+    if let Some(mut existing) = world.get_mut::<Scripts>(entity) {
+        existing.0.extend(scripts.0);
+        return;
+    }
+    world.insert(entity, scripts);
+}
+
+// Passes from several lines end up in one stack, in file order
+fn add_post_pass(world: &mut World, entity: Entity, pass: PostPass, enabled: bool) {
+    if let Some(mut stack) = world.get_mut::<PostStack>(entity) {
+        stack.push(pass, enabled);
+        return;
+    }
+    let mut stack = PostStack::new();
+    stack.push(pass, enabled);
+    world.insert(entity, stack);
+}
+
+// `name=value` pairs of one line, plus bare words like `directional` stored under ""
+struct Fields {
+    values: HashMap<String, String>,
+}
+
+impl Fields {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        for part in text.split_whitespace() {
+            let (name, value) = part.split_once('=').unwrap_or(("", part));
+            if values.insert(name.to_string(), value.to_string()).is_some() {
+                return Err(format!("`{part}` given twice"));
+            }
+        }
+        Ok(Self { values })
+    }
+
+    // Fails on fields nobody asked for, so typos don't go unnoticed
+    fn finish(self) -> Result<(), String> {
+        match self.values.into_iter().next() {
+            Some((name, value)) if name.is_empty() => Err(format!("unexpected `{value}`")),
+            Some((name, _)) => Err(format!("unknown field `{name}`")),
+            None => Ok(()),
+        }
+    }
+
+    fn take(&mut self, name: &str) -> Option<String> {
+        self.values.remove(name)
+    }
+
+    fn required(&mut self, name: &str) -> Result<String, String> {
+        self.take(name).ok_or_else(|| format!("missing `{name}`"))
+    }
+
+    fn flag_or(&mut self, name: &str, default: &str) -> Result<String, String> {
+        Ok(self.take(name).unwrap_or_else(|| default.to_string()))
+    }
+
+    fn float(&mut self, name: &str) -> Result<Option<f32>, String> {
+        self.take(name).map(|v| v.parse().map_err(|_| format!("invalid number `{v}` for `{name}`"))).transpose()
+    }
+
+    fn floats(&mut self, name: &str, count: usize) -> Result<Option<Vec<f32>>, String> {
+        let Some(value) = self.take(name) else { return Ok(None) };
+        let floats: Vec<f32> = value.split(',').map(|v| v.parse()).collect::<Result<_, _>>()
+            .map_err(|_| format!("invalid numbers `{value}` for `{name}`"))?;
+        if floats.len() != count {
+            return Err(format!("`{name}` needs {count} numbers"));
+        }
+        Ok(Some(floats))
+    }
+
+    fn float3(&mut self, name: &str) -> Result<Option<Float3>, String> {
+        Ok(self.floats(name, 3)?.map(|v| Float3::new(v[0], v[1], v[2])))
+    }
+
+    fn bool(&mut self, name: &str) -> Result<Option<bool>, String> {
+        self.take(name).map(|v| v.parse().map_err(|_| format!("invalid bool `{v}` for `{name}`"))).transpose()
+    }
+}
+
+fn float3_to_string(v: Float3) -> String {
+    format!("{},{},{}", v.x, v.y, v.z)
+}
+
+// Writes every entity in spawn order. Components that can't be described, like meshes built in
+// code or scripts without a `describe`, are left out with a comment.
+pub fn to_string(world: &World) -> String {
+    let mut out = String::new();
+    let transforms = world.read::<Transform>();
+    let renderers = world.read::<MeshRenderer>();
+    let cameras = world.read::<Camera>();
+    let lights = world.read::<Light>();
+    let scripts = world.read::<Scripts>();
+    let post_stacks = world.read::<PostStack>();
+    let ssaos = world.read::<Ssao>();
+
+    for (i, entity) in world.entities().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        match world.get::<Name>(entity) {
+            Some(name) => writeln!(out, "entity {}", name.0).unwrap(),
+            None => writeln!(out, "entity").unwrap(),
+        }
+
+        if let Some(t) = transforms.get(entity) {
+            writeln!(out, "    transform position={} rotation={} scale={}", float3_to_string(t.position), float3_to_string(t.rotation), float3_to_string(t.scale)).unwrap();
+        }
+        if let Some(renderer) = renderers.get(entity) {
+            match describe_renderer(renderer) {
+                Ok(line) => writeln!(out, "    renderer {line}").unwrap(),
+                Err(reason) => writeln!(out, "    # renderer left out: {reason}").unwrap(),
+            }
+        }
+        if let Some(camera) = cameras.get(entity) {
+            writeln!(out, "    camera {}", describe_camera(camera)).unwrap();
+        }
+        if let Some(light) = lights.get(entity) {
+            match light {
+                Light::Directional => writeln!(out, "    light directional").unwrap(),
+            }
+        }
+        if let Some(scripts) = scripts.get(entity) {
+            for script in &scripts.0 {
+                match script.describe() {
+                    Some(line) => writeln!(out, "    {line}").unwrap(),
+                    None => writeln!(out, "    # script left out: not describable").unwrap(),
+                }
+            }
+        }
+        if let Some(s) = ssaos.get(entity) {
+            writeln!(out, "    ssao radius={} intensity={} bias={} samples={} blur={}", s.radius, s.intensity, s.bias, s.samples, s.blur).unwrap();
+        }
+        if let Some(stack) = post_stacks.get(entity) {
+            for entry in &stack.passes {
+                match describe_post_pass(&entry.pass) {
+                    Ok(line) if entry.enabled => writeln!(out, "    post {line}").unwrap(),
+                    Ok(line) => writeln!(out, "    post {line} enabled=false").unwrap(),
+                    Err(reason) => writeln!(out, "    # post {} left out: {reason}", entry.pass.name()).unwrap(),
+                }
+            }
+        }
+    }
+    out
+}
+
+fn describe_renderer(renderer: &MeshRenderer) -> Result<String, String> {
+    let source_mesh = renderer.lod.as_ref().map_or(&renderer.mesh, |lod| &lod.levels[0].mesh);
+    let mesh = assets::mesh_name(source_mesh).ok_or("mesh was not loaded from a file")?;
+    let missing = || "texture was not loaded from a file".to_string();
+
+    let shader = match &renderer.shader {
+        Shader::TextureShader(s) => format!("shader=texture texture={} wireframe={}", assets::texture_name(&s.texture).ok_or_else(missing)?, s.wireframe),
+        Shader::LitTextureShader(s) => format!("shader=lit texture={} wireframe={}", assets::texture_name(&s.texture).ok_or_else(missing)?, s.wireframe),
+        Shader::TransparentTextureShader(s) => format!("shader=transparent texture={} alpha={}", assets::texture_name(&s.texture).ok_or_else(missing)?, s.alpha),
+        Shader::AnimatedTextureShader(s) => format!("shader=animated texture={} wireframe={}", assets::animated_texture_name(&s.texture).ok_or_else(missing)?, s.wireframe),
+    };
+
+    let mut line = format!("mesh={mesh} {shader}");
+    if renderer.shader.filter() == TextureFilter::Bilinear {
+        line.push_str(" filter=bilinear");
+    }
+    if let Some(lod) = &renderer.lod {
+        let (levels, ratio, first_screen_size) = lod.generated_from().ok_or("LOD levels were not generated")?;
+        write!(line, " lod={levels},{ratio},{first_screen_size} crossfade={} hysteresis={}", lod.crossfade_duration, lod.hysteresis).unwrap();
+    }
+
+    let (wireframe, default) = (renderer.wireframe, Wireframe::default());
+    if wireframe.color != default.color {
+        let c = wireframe.color;
+        write!(line, " edge_color={},{},{},{}", c.x, c.y, c.z, c.w).unwrap();
+    }
+    if wireframe.width != default.width {
+        write!(line, " edge_width={}", wireframe.width).unwrap();
+    }
+    if wireframe.overlay {
+        line.push_str(" edge_overlay=true");
+    }
+    Ok(line)
+}
+
+fn describe_post_pass(pass: &PostPass) -> Result<String, String> {
+    let name = pass.name();
+    let line = match pass {
+        PostPass::Fxaa(p) => format!("{name} span_max={} edge_threshold={} edge_threshold_min={}", p.span_max, p.edge_threshold, p.edge_threshold_min),
+        PostPass::Bloom(p) => format!("{name} threshold={} intensity={} radius={}", p.threshold, p.intensity, p.radius),
+        PostPass::Vignette(p) => format!("{name} intensity={} radius={} softness={}", p.intensity, p.radius, p.softness),
+        PostPass::ColorGrading(p) => format!("{name} lut={} strength={}", p.source.as_ref().ok_or("LUT was not loaded from a file")?, p.strength),
+        PostPass::DepthOfField(p) => format!("{name} focus={} range={} radius={}", p.focus_distance, p.focus_range, p.max_radius),
+        PostPass::Fog(p) => {
+            let c = p.color;
+            format!("{name} color={},{},{},{} density={} start={}", c.x, c.y, c.z, c.w, p.density, p.start)
+        }
+    };
+    Ok(line)
+}
+
+fn describe_camera(camera: &Camera) -> String {
+    let mut line = match camera.projection {
+        Projection::Perspective { fov_degrees } => format!("fov={fov_degrees}"),
+        Projection::Orthographic { size } => format!("orthographic={size}"),
+        Projection::Frustum { left, right, bottom, top } => format!("frustum={left},{right},{bottom},{top}"),
+    };
+    if let Some(aspect) = camera.aspect {
+        write!(line, " aspect={aspect}").unwrap();
+    }
+    if camera.viewport != Viewport::FULL {
+        let v = camera.viewport;
+        write!(line, " viewport={},{},{},{}", v.x, v.y, v.width, v.height).unwrap();
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::scripts::Script;
+    use crate::types::{handle::Handle, mesh_builder};
+
+    const SCENE: &str = "
+# comment
+entity Camera
+    transform position=0,1,-2
+    camera orthographic=2 viewport=0,0,0.5,1
+    controller orbit target=0,0.5,0 distance=2
+    ssao radius=0.1 samples=8
+    post fog color=0,0,0,1 density=0.3
+    post fxaa enabled=false
+
+entity Cube
+    transform position=1,0,0 rotation=0,0.5,0 scale=2,2,2
+    renderer mesh=Cube.obj shader=lit texture=color.png filter=bilinear wireframe=true lod=2,0.5,0.3 crossfade=0.25 edge_color=1,0,0,1 edge_width=2
+    spin speed=0,1,0   # turns
+    bob amplitude=0.5 frequency=2
+
+entity
+    transform forward=0,-1,1
+    light directional
+";
+
+    #[test]
+    fn parses_components() {
+        let world = parse(SCENE).unwrap();
+        let camera = world.find("Camera").unwrap();
+        let cube = world.find("Cube").unwrap();
+        assert_eq!(world.entities().count(), 3);
+
+        let cam = world.get::<Camera>(camera).unwrap();
+        assert_eq!(cam.projection, Projection::Orthographic { size: 2.0 });
+        assert_eq!(cam.viewport, Viewport::new(0.0, 0.0, 0.5, 1.0));
+        assert_eq!(cam.transform.position, Float3::new(0.0, 1.0, -2.0));
+
+        let post = world.get::<PostStack>(camera).unwrap();
+        assert_eq!(post.passes.iter().map(|e| (e.pass.name(), e.enabled)).collect::<Vec<_>>(), [("fog", true), ("fxaa", false)]);
+        assert_eq!(post.passes[0].pass, PostPass::Fog(Fog { color: Float4::new(0.0, 0.0, 0.0, 1.0), density: 0.3, ..Fog::default() }));
+
+        assert_eq!(*world.get::<Ssao>(camera).unwrap(), Ssao { radius: 0.1, samples: 8, ..Ssao::default() });
+
+        let renderer = world.get::<MeshRenderer>(cube).unwrap();
+        assert!(renderer.shader.render_as_wireframe());
+        assert_eq!(renderer.shader.filter(), TextureFilter::Bilinear);
+        assert_eq!(renderer.lod.as_ref().unwrap().levels.len(), 2);
+        assert_eq!(world.get::<Transform>(cube).unwrap().scale, Float3::TWO);
+        assert_eq!(world.get::<Scripts>(cube).unwrap().0.len(), 2);
+
+        let light = world.entities().last().unwrap();
+        assert_eq!(world.get::<Light>(light).as_deref(), Some(&Light::Directional));
+        assert!((world.get::<Transform>(light).unwrap().rotation.x + std::f32::consts::FRAC_PI_4).abs() < 1e-5);
+    }
+
+    #[test]
+    fn round_trips() {
+        let text = to_string(&parse(SCENE).unwrap());
+        assert_eq!(to_string(&parse(&text).unwrap()), text);
+        assert!(text.contains("spin speed=0,1,0\n    bob amplitude=0.5 frequency=2\n"), "{text}");
+        assert!(text.contains("texture=color.png wireframe=true filter=bilinear lod=2,0.5,0.3 crossfade=0.25"), "{text}");
+        assert!(text.contains("edge_color=1,0,0,1 edge_width=2\n"), "{text}");
+        assert!(text.contains("ssao radius=0.1 intensity=1.5 bias=0.01 samples=8 blur=true\n"), "{text}");
+        assert!(text.contains("post fog color=0,0,0,1 density=0.3 start=0.5\n    post fxaa span_max=8 edge_threshold=0.125 edge_threshold_min=0.0312 enabled=false\n"), "{text}");
+    }
+
+    #[test]
+    fn loads_the_test_scene() {
+        let world = load(assets::resolve("test.scene"));
+        for name in ["Camera", "Dragon", "Floor", "SmallWorld", "Sun"] {
+            assert!(world.find(name).is_some(), "{name}");
+        }
+        assert_eq!(parse(&to_string(&world)).unwrap().entities().count(), 5);
+    }
+
+    struct Wander;
+
+    impl Script for Wander {
+        fn update(&mut self, _world: &World, _entity: Entity, _delta_time: f32) {}
+    }
+
+    #[test]
+    fn leaves_out_what_it_cant_describe() {
+        let mut world = World::new();
+        let shader = Shader::TextureShader(TextureShader::new(assets::load_texture("color.png"), false));
+        world.spawn()
+            .with(Name::new("Generated"))
+            .with(MeshRenderer::new(Handle::new(mesh_builder::cube(1.0)), shader))
+            .with(Scripts::new().with(Wander));
+
+        let text = to_string(&world);
+        assert!(text.contains("# renderer left out: mesh was not loaded from a file"), "{text}");
+        assert!(text.contains("# script left out"), "{text}");
+        assert_eq!(parse(&text).unwrap().entities().count(), 1);
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let error = |text: &str| parse(text).err().unwrap();
+        assert_eq!(error("transform"), "line 1: `transform` outside of an entity");
+        assert_eq!(error("entity A\n\n  wobble"), "line 3: unknown component `wobble`");
+        assert_eq!(error("entity A\ntransform position=1,2"), "line 2: `position` needs 3 numbers");
+        assert_eq!(error("entity A\ntransform positon=1,2,3"), "line 2: unknown field `positon`");
+        assert_eq!(error("entity A\ncamera fov=wide"), "line 2: invalid number `wide` for `fov`");
+        assert_eq!(error("entity A\ncontroller fly"), "line 2: controller needs a camera before it");
+        assert_eq!(error("entity A\nlight point"), "line 2: unknown light `point`");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,319 @@
+use std::{collections::VecDeque, sync::Mutex};
+use once_cell::sync::Lazy;
+
+use crate::{math::numerics::float4::Float4, rasterizer::render_target::RenderTarget, types::scene::Scene};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transition {
+    Cut,
+    // Fades to `color` over the first half of `duration`, swaps scenes, then fades back in
+    Fade { duration: f32, color: Float4 },
+}
+
+impl Transition {
+    pub fn fade(duration: f32) -> Self {
+        Transition::Fade { duration, color: Float4::new(0.0, 0.0, 0.0, 1.0) }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    Push(String),
+    Pop,
+    Switch(String),
+    Quit,
+}
+
+// Scene changes requested since the last update. Scenes request them from inside their own
+// update, the manager applies them at the start of the next one.
+#[derive(Debug, Default)]
+struct Requests {
+    queue: VecDeque<(Command, Transition)>,
+    quit: bool,
+}
+
+static REQUESTS: Lazy<Mutex<Requests>> = Lazy::new(|| { Mutex::new(Requests::default()) });
+
+fn request(command: Command, transition: Transition) {
+    REQUESTS.lock().unwrap().queue.push_back((command, transition));
+}
+
+// Starts the named scene on top of the current one, which stays paused below it
+pub fn push(name: &str, transition: Transition) {
+    request(Command::Push(name.to_string()), transition);
+}
+
+// Exits the top scene and resumes the one below, popping the last scene quits
+pub fn pop(transition: Transition) {
+    request(Command::Pop, transition);
+}
+
+// Replaces the top scene
+#[allow(dead_code)]
+pub fn switch(name: &str, transition: Transition) {
+    request(Command::Switch(name.to_string()), transition);
+}
+
+pub fn quit(transition: Transition) {
+    request(Command::Quit, transition);
+}
+
+// Set once every scene has exited, the engine stops its loop
+pub fn quit_requested() -> bool {
+    REQUESTS.lock().unwrap().quit
+}
+
+// Creates a scene by name, None for unknown names
+pub type SceneFactory = fn(&str) -> Option<Box<dyn Scene>>;
+
+struct Fade {
+    command: Option<Command>, // applied at the darkest point
+    duration: f32,
+    color: Float4,
+    elapsed: f32,
+}
+
+impl Fade {
+    // 0 -> 1 over the first half, back to 0 over the second
+    fn amount(&self) -> f32 {
+        let half = self.duration * 0.5;
+        if half <= 0.0 {
+            return 0.0;
+        }
+        (1.0 - (self.elapsed / half - 1.0).abs()).clamp(0.0, 1.0)
+    }
+}
+
+// Stack of scenes, itself a scene so the engine runs it like any other.
+// Only the top scene updates, scenes below overlays keep rendering.
+pub struct SceneManager {
+    factory: SceneFactory,
+    initial: String,
+    stack: Vec<Box<dyn Scene>>,
+    fade: Option<Fade>,
+}
+
+impl SceneManager {
+    pub fn new(factory: SceneFactory, initial: &str) -> Self {
+        Self {
+            factory,
+            initial: initial.to_string(),
+            stack: Vec::new(),
+            fade: None,
+        }
+    }
+
+    fn create(&self, name: &str) -> Box<dyn Scene> {
+        (self.factory)(name).unwrap_or_else(|| panic!("Unknown scene: {name}"))
+    }
+
+    fn apply(&mut self, command: Command, render_target: &mut RenderTarget) {
+        match command {
+            Command::Push(name) => {
+                let mut scene = self.create(&name);
+                if let Some(top) = self.stack.last_mut() {
+                    top.focus_changed(false);
+                }
+                scene.start(render_target);
+                self.stack.push(scene);
+            }
+            Command::Pop => {
+                if let Some(mut top) = self.stack.pop() {
+                    top.exit();
+                }
+                match self.stack.last_mut() {
+                    Some(top) => top.focus_changed(true),
+                    None => REQUESTS.lock().unwrap().quit = true,
+                }
+            }
+            Command::Switch(name) => {
+                let mut scene = self.create(&name);
+                if let Some(mut top) = self.stack.pop() {
+                    top.exit();
+                }
+                scene.start(render_target);
+                self.stack.push(scene);
+            }
+            Command::Quit => {
+                self.exit();
+                REQUESTS.lock().unwrap().quit = true;
+            }
+        }
+    }
+
+    // Applies queued commands until one starts a fade
+    fn process_requests(&mut self, delta_time: f32, render_target: &mut RenderTarget) {
+        if let Some(fade) = &mut self.fade {
+            fade.elapsed += delta_time;
+            if fade.elapsed >= fade.duration * 0.5 && let Some(command) = fade.command.take() {
+                self.apply(command, render_target);
+            }
+            if self.fade.as_ref().is_some_and(|f| f.elapsed >= f.duration) {
+                self.fade = None;
+            }
+            return;
+        }
+
+        loop {
+            let Some((command, transition)) = REQUESTS.lock().unwrap().queue.pop_front() else { break };
+            match transition {
+                Transition::Cut => self.apply(command, render_target),
+                Transition::Fade { duration, color } => {
+                    self.fade = Some(Fade { command: Some(command), duration, color, elapsed: 0.0 });
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Scene for SceneManager {
+    fn start(&mut self, render_target: &mut RenderTarget) {
+        *REQUESTS.lock().unwrap() = Requests::default();
+
+        let mut scene = self.create(&self.initial);
+        scene.start(render_target);
+        self.stack.push(scene);
+    }
+
+    fn update(&mut self, delta_time: f32, render_target: &mut RenderTarget) {
+        self.process_requests(delta_time, render_target);
+        if let Some(top) = self.stack.last_mut() {
+            top.update(delta_time, render_target);
+        }
+    }
+
+    fn fixed_update(&mut self, fixed_delta_time: f32) {
+        if let Some(top) = self.stack.last_mut() {
+            top.fixed_update(fixed_delta_time);
+        }
+    }
+
+    fn render(&mut self, alpha: f32, render_target: &mut RenderTarget) {
+        // Lowest scene that's visible through the overlays above it
+        let first = self.stack.iter().rposition(|s| !s.is_overlay()).unwrap_or(0);
+        for scene in self.stack[first..].iter_mut() {
+            scene.render(alpha, render_target);
+        }
+
+        if let Some(fade) = &self.fade {
+            render_target.blend_overlay(Float4 { w: fade.color.w * fade.amount(), ..fade.color });
+        }
+    }
+
+    fn resize(&mut self, new_width: u32, new_height: u32, render_target: &mut RenderTarget) {
+        for scene in self.stack.iter_mut() {
+            scene.resize(new_width, new_height, render_target);
+        }
+    }
+
+    fn exit(&mut self) {
+        while let Some(mut scene) = self.stack.pop() {
+            scene.exit();
+        }
+    }
+
+    fn focus_changed(&mut self, focused: bool) {
+        if let Some(top) = self.stack.last_mut() {
+            top.focus_changed(focused);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static LOG: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn log(entry: String) {
+        LOG.with(|l| l.borrow_mut().push(entry));
+    }
+
+    fn take_log() -> Vec<String> {
+        LOG.with(|l| std::mem::take(&mut *l.borrow_mut()))
+    }
+
+    struct LogScene {
+        name: &'static str,
+        overlay: bool,
+    }
+
+    impl Scene for LogScene {
+        fn resize(&mut self, _new_width: u32, _new_height: u32, _render_target: &mut RenderTarget) {}
+        fn update(&mut self, _delta_time: f32, _render_target: &mut RenderTarget) {
+            log(format!("{} update", self.name));
+        }
+        fn start(&mut self, _render_target: &mut RenderTarget) {
+            log(format!("{} start", self.name));
+        }
+        fn render(&mut self, _alpha: f32, _render_target: &mut RenderTarget) {
+            log(format!("{} render", self.name));
+        }
+        fn exit(&mut self) {
+            log(format!("{} exit", self.name));
+        }
+        fn focus_changed(&mut self, focused: bool) {
+            log(format!("{} focus {focused}", self.name));
+        }
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    fn factory(name: &str) -> Option<Box<dyn Scene>> {
+        match name {
+            "level" => Some(Box::new(LogScene { name: "level", overlay: false })),
+            "other" => Some(Box::new(LogScene { name: "other", overlay: false })),
+            "menu" => Some(Box::new(LogScene { name: "menu", overlay: true })),
+            _ => None,
+        }
+    }
+
+    // Never pops the last scene, the quit flag is shared with the other tests
+    #[test]
+    fn stack_lifecycle_and_fades() {
+        let mut render_target = RenderTarget::new(2, 2);
+        let mut manager = SceneManager::new(factory, "level");
+        manager.start(&mut render_target);
+        assert_eq!(take_log(), ["level start"]);
+
+        push("menu", Transition::Cut);
+        manager.update(0.1, &mut render_target);
+        manager.render(0.0, &mut render_target);
+        assert_eq!(take_log(), ["level focus false", "menu start", "menu update", "level render", "menu render"]);
+
+        // The pop waits for the darkest point of the fade
+        render_target.clear(Float4::ONE);
+        pop(Transition::Fade { duration: 1.0, color: Float4::new(0.0, 0.0, 0.0, 1.0) });
+        manager.update(0.1, &mut render_target);
+        manager.update(0.25, &mut render_target);
+        assert_eq!(manager.stack.len(), 2);
+        render_target.clear(Float4::ONE);
+        manager.render(0.0, &mut render_target);
+        assert!((render_target.get_pixel_color(0, 0).x - 0.5).abs() < 1e-5);
+        take_log();
+
+        manager.update(0.25, &mut render_target);
+        assert_eq!(manager.stack.len(), 1);
+        assert_eq!(take_log(), ["menu exit", "level focus true", "level update"]);
+
+        manager.update(0.5, &mut render_target);
+        render_target.clear(Float4::ONE);
+        manager.render(0.0, &mut render_target);
+        assert_eq!(render_target.get_pixel_color(0, 0), Float4::ONE);
+        take_log();
+
+        switch("other", Transition::Cut);
+        manager.update(0.1, &mut render_target);
+        manager.render(0.0, &mut render_target);
+        assert_eq!(take_log(), ["level exit", "other start", "other update", "other render"]);
+
+        manager.exit();
+        assert_eq!(take_log(), ["other exit"]);
+        assert!(!quit_requested());
+    }
+}
\ No newline at end of file
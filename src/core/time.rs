@@ -0,0 +1,25 @@
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+
+// Scene time advanced by the engine once per frame. Code that animates over time reads this
+// instead of the wall clock, so replays with a fixed timestep produce the same frames.
+#[derive(Debug, Default)]
+struct Clock {
+    elapsed: f64,
+}
+
+static CLOCK: Lazy<Mutex<Clock>> = Lazy::new(|| { Mutex::new(Clock::default()) });
+
+pub fn advance(delta_time: f32) {
+    let mut clock = CLOCK.lock().unwrap();
+    clock.elapsed += delta_time as f64;
+}
+
+pub fn reset() {
+    *CLOCK.lock().unwrap() = Clock::default();
+}
+
+// Seconds since the engine started
+pub fn now() -> f64 {
+    CLOCK.lock().unwrap().elapsed
+}
\ No newline at end of file
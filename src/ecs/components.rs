@@ -0,0 +1,18 @@
+// Transform (types::transform), MeshRenderer (types::mesh_renderer) and Camera (rasterizer::camera)
+// are used as components directly, these are the ones that only make sense on entities.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Name(pub String);
+
+impl Name {
+    pub fn new(name: &str) -> Self {
+        Self(name.to_string())
+    }
+}
+
+// Lit shaders take their light direction from the first light, see systems::apply_lights
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    // Shines along the entity's forward axis
+    Directional,
+}
\ No newline at end of file
@@ -0,0 +1,4 @@
+pub mod components;
+pub mod scripts;
+pub mod systems;
+pub mod world;
\ No newline at end of file
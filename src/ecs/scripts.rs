@@ -0,0 +1,99 @@
+use crate::{core::camera_controller::CameraController, ecs::world::{Entity, World}, math::numerics::float3::Float3, rasterizer::camera::Camera, types::transform::Transform};
+
+// Per-entity logic, run by systems::run_scripts once per frame.
+// A script can read and write any component except `Scripts`, which is borrowed while it runs.
+pub trait Script {
+    fn update(&mut self, world: &World, entity: Entity, delta_time: f32);
+
+    // Line for core::scene_file, None if the script can't be written to a scene file
+    fn describe(&self) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Default)]
+pub struct Scripts(pub Vec<Box<dyn Script>>);
+
+impl Scripts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, script: impl Script + 'static) -> Self {
+        self.0.push(Box::new(script));
+        self
+    }
+}
+
+// Rotates around the local axes, `speed` in radians per second for pitch, yaw and roll
+pub struct Spin {
+    pub speed: Float3,
+}
+
+impl Spin {
+    pub fn new(speed: Float3) -> Self {
+        Self { speed }
+    }
+}
+
+impl Script for Spin {
+    fn update(&mut self, world: &World, entity: Entity, delta_time: f32) {
+        if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+            transform.rotate(self.speed * delta_time);
+        }
+    }
+
+    fn describe(&self) -> Option<String> {
+        Some(format!("spin speed={},{},{}", self.speed.x, self.speed.y, self.speed.z))
+    }
+}
+
+// Moves up and down around the height it had on its first update
+pub struct Bob {
+    pub amplitude: f32,
+    pub frequency: f32, // cycles per second
+
+    base: Option<f32>,
+    time: f32,
+}
+
+impl Bob {
+    pub fn new(amplitude: f32, frequency: f32) -> Self {
+        Self { amplitude, frequency, base: None, time: 0.0 }
+    }
+}
+
+impl Script for Bob {
+    fn update(&mut self, world: &World, entity: Entity, delta_time: f32) {
+        let Some(mut transform) = world.get_mut::<Transform>(entity) else { return };
+        let base = *self.base.get_or_insert(transform.position.y);
+
+        self.time += delta_time;
+        let offset = (self.time * self.frequency * std::f32::consts::TAU).sin() * self.amplitude;
+        let position = transform.position;
+        transform.set_position(Float3::new(position.x, base + offset, position.z));
+    }
+
+    fn describe(&self) -> Option<String> {
+        Some(format!("bob amplitude={} frequency={}", self.amplitude, self.frequency))
+    }
+}
+
+// Drives the entity's camera, the entity's Transform stays the source of truth
+impl Script for CameraController {
+    fn update(&mut self, world: &World, entity: Entity, delta_time: f32) {
+        let (Some(mut transform), Some(mut camera)) = (world.get_mut::<Transform>(entity), world.get_mut::<Camera>(entity)) else { return };
+
+        camera.transform = transform.clone();
+        CameraController::update(self, &mut camera, delta_time);
+        *transform = camera.transform.clone();
+    }
+
+    fn describe(&self) -> Option<String> {
+        Some(match self {
+            CameraController::Fly(c) => format!("controller fly speed={}", c.speed),
+            CameraController::Orbit(c) => format!("controller orbit target={},{},{} distance={}", c.target.x, c.target.y, c.target.z, c.distance),
+            CameraController::FirstPerson(_) => "controller first_person".to_string(),
+        })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,137 @@
+use crate::{ecs::{components::Light, scripts::Scripts, world::World}, rasterizer::camera::Camera, shaders::shader_base::Shader, types::{mesh_renderer::MeshRenderer, transform::Transform}};
+
+pub type System = fn(&mut World, f32);
+
+// Systems run by name in a fixed order
+pub struct Schedule {
+    systems: Vec<(&'static str, System)>,
+}
+
+impl Default for Schedule {
+    // Scripts first, then everything that copies their results into other components
+    fn default() -> Self {
+        Self::empty()
+            .with("scripts", run_scripts)
+            .with("cameras", sync_cameras)
+            .with("lights", apply_lights)
+    }
+}
+
+impl Schedule {
+    pub fn empty() -> Self {
+        Self { systems: Vec::new() }
+    }
+
+    pub fn with(mut self, name: &'static str, system: System) -> Self {
+        self.add(name, system);
+        self
+    }
+
+    pub fn add(&mut self, name: &'static str, system: System) {
+        self.systems.push((name, system));
+    }
+
+    #[allow(dead_code)]
+    pub fn add_before(&mut self, before: &str, name: &'static str, system: System) {
+        let index = self.index_of(before);
+        self.systems.insert(index, (name, system));
+    }
+
+    #[allow(dead_code)]
+    pub fn add_after(&mut self, after: &str, name: &'static str, system: System) {
+        let index = self.index_of(after);
+        self.systems.insert(index + 1, (name, system));
+    }
+
+    fn index_of(&self, name: &str) -> usize {
+        self.systems.iter().position(|(n, _)| *n == name).unwrap_or_else(|| panic!("No system named {name}"))
+    }
+
+    pub fn run(&self, world: &mut World, delta_time: f32) {
+        for (_, system) in &self.systems {
+            system(world, delta_time);
+        }
+    }
+}
+
+pub fn run_scripts(world: &mut World, delta_time: f32) {
+    let mut scripts = world.write::<Scripts>();
+    for (entity, scripts) in scripts.iter_mut() {
+        for script in scripts.0.iter_mut() {
+            script.update(world, entity, delta_time);
+        }
+    }
+}
+
+// Cameras render from their entity's Transform
+pub fn sync_cameras(world: &mut World, _delta_time: f32) {
+    let transforms = world.read::<Transform>();
+    for (entity, camera) in world.write::<Camera>().iter_mut() {
+        if let Some(transform) = transforms.get(entity) {
+            camera.transform = transform.clone();
+        }
+    }
+}
+
+// Points every lit shader at the first light
+pub fn apply_lights(world: &mut World, _delta_time: f32) {
+    let transforms = world.read::<Transform>();
+    let direction_to_light = world.read::<Light>().iter().find_map(|(entity, light)| match light {
+        Light::Directional => transforms.get(entity).map(|t| t.backward()),
+    });
+    let Some(direction_to_light) = direction_to_light else { return };
+
+    for (_, renderer) in world.write::<MeshRenderer>().iter_mut() {
+        if let Shader::LitTextureShader(s) = &mut renderer.shader {
+            s.direction_to_light = direction_to_light;
+        }
+    }
+}
+
+// The first camera, placed at its entity's transform
+#[allow(dead_code)]
+pub fn active_camera(world: &World) -> Option<Camera> {
+    let (entity, camera) = world.read::<Camera>().iter().next().map(|(e, c)| (e, c.clone()))?;
+    let mut camera = camera;
+    if let Some(transform) = world.get::<Transform>(entity) {
+        camera.transform = transform.clone();
+    }
+    Some(camera)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ecs::scripts::Spin, math::numerics::{float3::Float3, float4::Float4}, shaders::lit_texture_shader::LitTextureShader, types::{handle::Handle, mesh::Mesh, texture::Texture}};
+
+    #[test]
+    fn schedule_order() {
+        let mut schedule = Schedule::default();
+        schedule.add_before("cameras", "physics", |_, _| {});
+        schedule.add_after("lights", "audio", |_, _| {});
+        let names: Vec<_> = schedule.systems.iter().map(|(n, _)| *n).collect();
+        assert_eq!(names, ["scripts", "physics", "cameras", "lights", "audio"]);
+    }
+
+    #[test]
+    fn scripts_move_cameras_and_lights() {
+        let mut world = World::new();
+        let camera = world.spawn().with(Transform::default()).with(Camera::new(90.0)).with(Scripts::new().with(Spin::new(Float3::new(0.0, 1.0, 0.0)))).id();
+
+        let mut sun = Transform::default();
+        sun.set_forward(Float3::new(-0.5, -1.0, -0.3));
+        world.spawn().with(sun).with(Light::Directional);
+
+        let shader = Shader::LitTextureShader(LitTextureShader::new(Float3::ZERO, Handle::new(Texture::from_texels(1, 1, vec![Float4::ONE])), false));
+        let lit = world.spawn().with(Transform::default()).with(MeshRenderer::new(Handle::new(Mesh::empty()), shader)).id();
+
+        Schedule::default().run(&mut world, 0.5);
+
+        assert!((world.get::<Camera>(camera).unwrap().transform.rotation.y - 0.5).abs() < 1e-6);
+        assert!((active_camera(&world).unwrap().transform.rotation.y - 0.5).abs() < 1e-6);
+
+        let Shader::LitTextureShader(s) = &world.get::<MeshRenderer>(lit).unwrap().shader else { unreachable!() };
+        let expected = Float3::new(0.5, 1.0, 0.3).normalize();
+        assert!((s.direction_to_light - expected).length() < 1e-5);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,275 @@
+use std::{any::{type_name, Any, TypeId}, cell::{Ref, RefCell, RefMut}, collections::HashMap};
+
+use crate::{ecs::{components::{Light, Name}, scripts::Scripts}, post::stack::PostStack, rasterizer::{camera::Camera, ssao::Ssao}, types::{mesh_renderer::MeshRenderer, transform::Transform}};
+
+// Index into the component storages, the generation tells a reused index apart from the despawned entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+// Components of one type, indexed by entity index
+pub struct Storage<T> {
+    slots: Vec<Option<(u32, T)>>, // generation of the owner and the component
+}
+
+impl<T> Storage<T> {
+    fn new() -> Self {
+        Self { slots: Vec::new() }
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.slots.get(entity.index as usize)? {
+            Some((generation, component)) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.slots.get_mut(entity.index as usize)? {
+            Some((generation, component)) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        let index = entity.index as usize;
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, || None);
+        }
+        self.slots[index].replace((entity.generation, component)).filter(|(g, _)| *g == entity.generation).map(|(_, c)| c)
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        self.get(entity)?;
+        self.slots[entity.index as usize].take().map(|(_, c)| c)
+    }
+
+    // In spawn order of the entity indices
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.as_ref().map(|(generation, c)| (Entity { index: index as u32, generation: *generation }, c))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.slots.iter_mut().enumerate().filter_map(|(index, slot)| {
+            slot.as_mut().map(|(generation, c)| (Entity { index: index as u32, generation: *generation }, c))
+        })
+    }
+}
+
+trait AnyStorage {
+    fn remove_entity(&self, entity: Entity);
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: 'static> AnyStorage for RefCell<Storage<T>> {
+    fn remove_entity(&self, entity: Entity) {
+        self.borrow_mut().remove(entity);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// Entities and their components. Each component type lives in its own storage behind a RefCell,
+// so systems can read one type while writing another: `world.read::<Transform>()` next to
+// `world.write::<MeshRenderer>()`. Borrowing the same type mutably twice panics.
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    // Comes with the built-in component types registered
+    pub fn new() -> Self {
+        let mut world = Self {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+            storages: HashMap::new(),
+        };
+        world.register::<Name>();
+        world.register::<Transform>();
+        world.register::<MeshRenderer>();
+        world.register::<Camera>();
+        world.register::<Light>();
+        world.register::<Scripts>();
+        world.register::<PostStack>();
+        world.register::<Ssao>();
+        world
+    }
+
+    // Other component types are registered on their first insert, or here so they can be read before that
+    pub fn register<T: 'static>(&mut self) {
+        self.storages.entry(TypeId::of::<T>()).or_insert_with(|| Box::new(RefCell::new(Storage::<T>::new())));
+    }
+
+    pub fn spawn(&mut self) -> EntityBuilder<'_> {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.generations.push(0);
+                self.alive.push(false);
+                self.generations.len() as u32 - 1
+            }
+        };
+        self.alive[index as usize] = true;
+
+        let entity = Entity { index, generation: self.generations[index as usize] };
+        EntityBuilder { world: self, entity }
+    }
+
+    // Removes the entity and all its components, false if it was already gone
+    #[allow(dead_code)]
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        for storage in self.storages.values() {
+            storage.remove_entity(entity);
+        }
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] += 1;
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        index < self.alive.len() && self.alive[index] && self.generations[index] == entity.generation
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        (0..self.alive.len())
+            .filter(|&i| self.alive[i])
+            .map(|i| Entity { index: i as u32, generation: self.generations[i] })
+    }
+
+    // Adds or replaces a component, returns the replaced one
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T> {
+        assert!(self.is_alive(entity), "Inserting a component on a despawned entity");
+        self.register::<T>();
+        self.write::<T>().insert(entity, component)
+    }
+
+    #[allow(dead_code)]
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.storages.get(&TypeId::of::<T>())?;
+        self.write::<T>().remove(entity)
+    }
+
+    fn storage<T: 'static>(&self) -> &RefCell<Storage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .unwrap_or_else(|| panic!("Component type not registered: {}", type_name::<T>()))
+            .as_any()
+            .downcast_ref()
+            .unwrap()
+    }
+
+    pub fn read<T: 'static>(&self) -> Ref<'_, Storage<T>> {
+        self.storage::<T>().borrow()
+    }
+
+    pub fn write<T: 'static>(&self) -> RefMut<'_, Storage<T>> {
+        self.storage::<T>().borrow_mut()
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.read::<T>(), |s| s.get(entity)).ok()
+    }
+
+    pub fn get_mut<T: 'static>(&self, entity: Entity) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(self.write::<T>(), |s| s.get_mut(entity)).ok()
+    }
+
+    // First entity with this `Name`
+    pub fn find(&self, name: &str) -> Option<Entity> {
+        self.read::<Name>().iter().find(|(_, n)| n.0 == name).map(|(e, _)| e)
+    }
+
+    // The entity's name, or `Entity <index>` for unnamed ones
+    pub fn name(&self, entity: Entity) -> String {
+        self.get::<Name>(entity).map_or_else(|| format!("Entity {}", entity.index), |n| n.0.clone())
+    }
+}
+
+pub struct EntityBuilder<'a> {
+    world: &'a mut World,
+    entity: Entity,
+}
+
+impl EntityBuilder<'_> {
+    pub fn with<T: 'static>(self, component: T) -> Self {
+        self.world.insert(self.entity, component);
+        self
+    }
+
+    pub fn id(self) -> Entity {
+        self.entity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn despawned_entities_lose_their_components() {
+        let mut world = World::new();
+        let a = world.spawn().with(Name::new("a")).with(5u32).id();
+        let b = world.spawn().with(Name::new("b")).id();
+
+        assert_eq!(world.find("b"), Some(b));
+        assert_eq!(*world.get::<u32>(a).unwrap(), 5);
+        assert!(world.get::<u32>(b).is_none());
+
+        assert!(world.despawn(a));
+        assert!(!world.despawn(a));
+        assert!(world.get::<Name>(a).is_none());
+
+        // The slot is reused, the stale handle stays dead
+        let c = world.spawn().with(Name::new("c")).id();
+        assert_eq!(c.index(), a.index());
+        assert!(!world.is_alive(a));
+        assert!(world.get::<Name>(a).is_none());
+        assert!(world.get::<u32>(c).is_none());
+        assert_eq!(world.entities().collect::<Vec<_>>(), [c, b]);
+    }
+
+    #[test]
+    fn different_types_borrow_together() {
+        let mut world = World::new();
+        let e = world.spawn().with(1u32).with(2.0f32).id();
+
+        let ints = world.read::<u32>();
+        let mut floats = world.write::<f32>();
+        *floats.get_mut(e).unwrap() += *ints.get(e).unwrap() as f32;
+        assert_eq!(floats.get(e), Some(&3.0));
+        assert_eq!(floats.iter().count(), 1);
+    }
+}
\ No newline at end of file
@@ -1,15 +1,75 @@
-use crate::core::engine::Engine;
-use crate::scenes::test_scene::TestScene;
+use crate::core::engine::{Engine, LoopSettings};
+use crate::core::recording::Recording;
+use crate::core::scene_manager::SceneManager;
 
 mod math;
 mod core;
+mod ecs;
 mod scenes;
 mod shaders;
 mod rasterizer;
+mod post;
 mod types;
+mod ui;
+
+// Options:
+//   --scene <name>      scene to start with, one of scenes::NAMES or a .scene file
+//   --record <file>     write the session's input to a file when the window closes
+//   --replay <file>     play a recording back instead of reading the window
+//   --timestep <secs>   replay with a fixed frame delta instead of the recorded ones
+//   --fps <n>           limit the frame rate
+//   --headless          replay without a window, prints a checksum per frame
+struct Options {
+    scene: String,
+    record: Option<String>,
+    replay: Option<String>,
+    timestep: Option<f32>,
+    fps: Option<f32>,
+    headless: bool,
+}
+
+fn parse_options() -> Options {
+    let mut options = Options { scene: scenes::NAMES[0].to_string(), record: None, replay: None, timestep: None, fps: None, headless: false };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("Missing value for {arg}"));
+        match arg.as_str() {
+            "--scene" => options.scene = value(),
+            "--record" => options.record = Some(value()),
+            "--replay" => options.replay = Some(value()),
+            "--timestep" => options.timestep = Some(value().parse().expect("Failed to parse timestep")),
+            "--fps" => options.fps = Some(value().parse().expect("Failed to parse fps")),
+            "--headless" => options.headless = true,
+            other => panic!("Unknown argument: {other}"),
+        }
+    }
+    options
+}
 
 fn main() {
-    let mut scene = TestScene::new();
+    let options = parse_options();
+    if scenes::create(&options.scene).is_none() {
+        panic!("Unknown scene: {} (available: {} or a .scene file)", options.scene, scenes::NAMES.join(", "));
+    }
+    let mut scene = SceneManager::new(scenes::create, &options.scene);
+
+    if options.headless {
+        let recording = Recording::load(options.replay.expect("--headless needs --replay <file>"));
+        Engine::replay_headless(&mut scene, &recording, options.timestep, LoopSettings::default(), |frame, render_target| {
+            println!("Frame {frame}: {:016x}", render_target.checksum());
+        });
+        return;
+    }
+
     let mut engine = Engine::new();
-    engine.run(&mut scene);
+    engine.set_loop_settings(LoopSettings { target_fps: options.fps, ..LoopSettings::default() });
+    if let Some(path) = options.record {
+        engine.record(path);
+    }
+    if let Some(path) = options.replay {
+        engine.replay(Recording::load(path), options.timestep);
+    }
+    if let Some(path) = engine.run(&mut scene) {
+        println!("Saved recording to {}", path.display());
+    }
 }
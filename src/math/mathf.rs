@@ -1,4 +1,4 @@
-use crate::math::numerics::{float2::Float2, float3::Float3};
+use crate::math::numerics::{float2::Float2, float3::Float3, float4::Float4};
 
 // Test if point p is inside triangle ABC
 // Note: non-clockwise triangles are considered 'back-faces' and are ignored
@@ -75,6 +75,31 @@ pub fn lerp_float2(a: Float2, b: Float2, t: f32) -> Float2 {
 	a + (b - a) * t.clamp(0.0, 1.0)
 }
 
+#[inline(always)]
+#[allow(dead_code)]
+pub fn lerp_float4(a: Float4, b: Float4, t: f32) -> Float4 {
+	a + (b - a) * t.clamp(0.0, 1.0)
+}
+
+// 0 below `edge0`, 1 above `edge1`, smooth in between
+#[inline(always)]
+#[allow(dead_code)]
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[inline(always)]
+#[allow(dead_code)]
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+#[inline(always)]
+#[allow(dead_code)]
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
 
 #[inline(always)]
 #[allow(dead_code)]
@@ -0,0 +1,81 @@
+use rayon::prelude::*;
+
+use crate::math::numerics::float4::Float4;
+use crate::post::image::{luma, update_pixels, Image};
+use crate::rasterizer::render_target::RenderTarget;
+
+// Glow around bright pixels: what's above the threshold is blurred at half resolution and added back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bloom {
+    pub threshold: f32, // luma where pixels start to glow
+    pub intensity: f32,
+    pub radius: f32,    // blur radius in half resolution pixels
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self { threshold: 0.6, intensity: 0.8, radius: 4.0 }
+    }
+}
+
+impl Bloom {
+    pub fn apply(&self, render_target: &mut RenderTarget) {
+        if render_target.width() == 0 || render_target.height() == 0 {
+            return;
+        }
+
+        render_target.resolve();
+        let bright = self.bright_pass(render_target);
+        let blurred = blur(&bright, self.radius);
+
+        update_pixels(render_target, |color, _, x, y| {
+            let glow = blurred.sample((x as f32 + 0.5) * 0.5, (y as f32 + 0.5) * 0.5) * self.intensity;
+            Float4 { x: color.x + glow.x, y: color.y + glow.y, z: color.z + glow.z, w: color.w }
+        });
+    }
+
+    // Half resolution, each pixel the average of four with the part below the threshold removed
+    fn bright_pass(&self, render_target: &RenderTarget) -> Image {
+        let (width, height) = (render_target.width() as i32, render_target.height() as i32);
+        let source = render_target.color_buffer();
+        let get = |x: i32, y: i32| source[(y.min(height - 1) * width + x.min(width - 1)) as usize];
+
+        let mut bright = Image::new((width as u32).div_ceil(2), (height as u32).div_ceil(2));
+        let half_width = bright.width as usize;
+        bright.pixels.par_chunks_mut(half_width).enumerate().for_each(|(y, row)| {
+            let sy = y as i32 * 2;
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let sx = x as i32 * 2;
+                let average = (get(sx, sy) + get(sx + 1, sy) + get(sx, sy + 1) + get(sx + 1, sy + 1)) * 0.25;
+                let l = luma(average);
+                *pixel = average * ((l - self.threshold).max(0.0) / l.max(1e-4));
+            }
+        });
+        bright
+    }
+}
+
+// Separable gaussian blur, rows then columns
+fn blur(image: &Image, radius: f32) -> Image {
+    let taps = radius.ceil().max(0.0) as i32;
+    let sigma = (radius * 0.5).max(0.5);
+    let weights: Vec<f32> = (-taps..=taps).map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp()).collect();
+    let total: f32 = weights.iter().sum();
+    let weights: Vec<f32> = weights.iter().map(|w| w / total).collect();
+
+    let pass = |source: &Image, dx: i32, dy: i32| {
+        let mut out = Image::new(source.width, source.height);
+        out.pixels.par_chunks_mut(source.width as usize).enumerate().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let mut sum = Float4::ZERO;
+                for (i, weight) in (-taps..=taps).zip(&weights) {
+                    sum += source.get(x as i32 + i * dx, y as i32 + i * dy) * *weight;
+                }
+                *pixel = sum;
+            }
+        });
+        out
+    };
+
+    pass(&pass(image, 1, 0), 0, 1)
+}
\ No newline at end of file
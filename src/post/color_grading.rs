@@ -0,0 +1,143 @@
+use crate::core::assets;
+use crate::math::mathf as f;
+use crate::math::numerics::{float3::Float3, float4::Float4};
+use crate::post::image::update_pixels;
+use crate::rasterizer::render_target::RenderTarget;
+use crate::types::texture::Texture;
+
+// 3D color lookup table with `size` steps per channel, sampled trilinearly
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lut {
+    size: u32,
+    colors: Vec<Float3>, // red fastest, then green, then blue
+}
+
+impl Lut {
+    pub fn identity(size: u32) -> Self {
+        Self::from_fn(size, |c| c)
+    }
+
+    pub fn from_fn(size: u32, grade: impl Fn(Float3) -> Float3) -> Self {
+        assert!(size >= 2, "LUT needs at least 2 steps per channel");
+        let step = 1.0 / (size - 1) as f32;
+        let mut colors = Vec::with_capacity((size * size * size) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    colors.push(grade(Float3::new(r as f32 * step, g as f32 * step, b as f32 * step)));
+                }
+            }
+        }
+        Self { size, colors }
+    }
+
+    // A strip of square tiles side by side, `height` of them: red along x within a tile, green up
+    // and blue from tile to tile. The layout of the usual 256x16 and 1024x32 LUT images.
+    pub fn from_texture(texture: &Texture) -> Result<Self, String> {
+        let size = texture.height();
+        if size < 2 || texture.width() != size * size {
+            return Err(format!("LUT image must be {0}x{1} for {1} steps, not {2}x{1}", size * size, size, texture.width()));
+        }
+
+        let mut colors = Vec::with_capacity((size * size * size) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let c = texture.get_pixel_srgb(b * size + r, g);
+                    colors.push(Float3::new(c.x, c.y, c.z));
+                }
+            }
+        }
+        Ok(Self { size, colors })
+    }
+
+    #[inline(always)]
+    fn get(&self, r: usize, g: usize, b: usize) -> Float3 {
+        let size = self.size as usize;
+        self.colors[(b * size + g) * size + r]
+    }
+
+    pub fn apply(&self, color: Float3) -> Float3 {
+        let max = (self.size - 1) as f32;
+        let position = |c: f32| {
+            let p = c.clamp(0.0, 1.0) * max;
+            let low = (p.floor() as usize).min(self.size as usize - 2);
+            (low, p - low as f32)
+        };
+        let (r, tr) = position(color.x);
+        let (g, tg) = position(color.y);
+        let (b, tb) = position(color.z);
+
+        let lerp_r = |g: usize, b: usize| f::lerp_float3(self.get(r, g, b), self.get(r + 1, g, b), tr);
+        let lerp_g = |b: usize| f::lerp_float3(lerp_r(g, b), lerp_r(g + 1, b), tg);
+        f::lerp_float3(lerp_g(b), lerp_g(b + 1), tb)
+    }
+}
+
+// Maps colors through a LUT, blended with the original by `strength`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorGrading {
+    pub lut: Lut,
+    pub strength: f32,
+    pub source: Option<String>, // asset name of the LUT image, for scene files
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        Self::new(Lut::identity(16))
+    }
+}
+
+impl ColorGrading {
+    pub fn new(lut: Lut) -> Self {
+        Self { lut, strength: 1.0, source: None }
+    }
+
+    // LUT image by asset name
+    pub fn load(name: &str) -> Result<Self, String> {
+        let texture = assets::load_texture(name);
+        let lut = Lut::from_texture(&texture).map_err(|e| format!("{name}: {e}"))?;
+        Ok(Self { source: Some(name.to_string()), ..Self::new(lut) })
+    }
+
+    pub fn apply(&self, render_target: &mut RenderTarget) {
+        let strength = self.strength.clamp(0.0, 1.0);
+        update_pixels(render_target, |color, _, _, _| {
+            let graded = self.lut.apply(Float3::new(color.x, color.y, color.z));
+            let graded = Float4::new(graded.x, graded.y, graded.z, color.w);
+            f::lerp_float4(color, graded, strength)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_lut_keeps_colors() {
+        let lut = Lut::identity(8);
+        for c in [Float3::ZERO, Float3::ONE, Float3::new(0.3, 0.55, 0.9)] {
+            let out = lut.apply(c);
+            assert!((out - c).length() < 1e-5, "{out:?}");
+        }
+    }
+
+    #[test]
+    fn reads_the_strip_layout() {
+        let size = 4;
+        let grade = |c: Float3| Float3::new(c.y, c.z, c.x);
+        let mut texels = Vec::new();
+        for g in 0..size {
+            for b in 0..size {
+                for r in 0..size {
+                    let c = grade(Float3::new(r as f32, g as f32, b as f32) / (size - 1) as f32);
+                    texels.push(Float4::new(c.x, c.y, c.z, 1.0));
+                }
+            }
+        }
+        let lut = Lut::from_texture(&Texture::from_texels(size * size, size, texels)).unwrap();
+        assert_eq!(lut, Lut::from_fn(size, grade));
+        assert!(Lut::from_texture(&Texture::from_texels(4, 4, vec![Float4::ONE; 16])).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,57 @@
+use crate::post::image::map_pixels;
+use crate::rasterizer::render_target::RenderTarget;
+
+const TAPS: usize = 24;
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+// Blurs by distance from the focus plane, each pixel gathers a disc of its circle of confusion.
+// Taps that are sharper than their distance to the pixel are left out, so in focus objects don't
+// bleed into the blur around them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthOfField {
+    pub focus_distance: f32, // view depth that stays sharp
+    pub focus_range: f32,    // depth difference at which the blur is strongest
+    pub max_radius: f32,     // in pixels
+}
+
+impl Default for DepthOfField {
+    fn default() -> Self {
+        Self { focus_distance: 1.0, focus_range: 1.0, max_radius: 6.0 }
+    }
+}
+
+impl DepthOfField {
+    // Blur radius in pixels at a view depth, empty pixels count as infinitely far
+    pub fn circle_of_confusion(&self, depth: f32) -> f32 {
+        let t = ((depth - self.focus_distance).abs() / self.focus_range.max(1e-4)).min(1.0);
+        t * self.max_radius
+    }
+
+    pub fn apply(&self, render_target: &mut RenderTarget) {
+        let (width, height) = (render_target.width() as i32, render_target.height() as i32);
+        map_pixels(render_target, |source, depth, x, y| {
+            let (xi, yi) = (x as i32, y as i32);
+            let center = source.get(xi, yi);
+            let radius = self.circle_of_confusion(depth[(yi * width + xi) as usize]);
+            if radius < 0.5 {
+                return center;
+            }
+
+            let mut sum = center;
+            let mut total = 1.0;
+            for i in 0..TAPS {
+                // Spiral that fills the disc evenly
+                let distance = ((i as f32 + 0.5) / TAPS as f32).sqrt() * radius;
+                let angle = i as f32 * GOLDEN_ANGLE;
+                let tx = (xi + (angle.cos() * distance).round() as i32).clamp(0, width - 1);
+                let ty = (yi + (angle.sin() * distance).round() as i32).clamp(0, height - 1);
+
+                let tap_radius = self.circle_of_confusion(depth[(ty * width + tx) as usize]);
+                let weight = (tap_radius - distance + 1.0).clamp(0.0, 1.0);
+                sum += source.get(tx, ty) * weight;
+                total += weight;
+            }
+            sum / total
+        });
+    }
+}
\ No newline at end of file
@@ -0,0 +1,35 @@
+use crate::math::mathf as f;
+use crate::math::numerics::float4::Float4;
+use crate::post::image::update_pixels;
+use crate::rasterizer::render_target::RenderTarget;
+
+// Exponential fog by view depth. Pixels nothing was drawn to count as infinitely far and get the full fog.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    pub color: Float4, // alpha is the strongest the fog gets
+    pub density: f32,  // per world unit
+    pub start: f32,    // depth where it begins
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self { color: Float4::new(0.6, 0.65, 0.7, 1.0), density: 0.5, start: 0.5 }
+    }
+}
+
+impl Fog {
+    pub fn amount(&self, depth: f32) -> f32 {
+        if self.density <= 0.0 {
+            return 0.0;
+        }
+        let distance = (depth - self.start).max(0.0);
+        (1.0 - (-self.density * distance).exp()) * self.color.w.clamp(0.0, 1.0)
+    }
+
+    pub fn apply(&self, render_target: &mut RenderTarget) {
+        update_pixels(render_target, |color, depth, _, _| {
+            let fogged = f::lerp_float4(color, self.color, self.amount(depth));
+            Float4 { w: color.w.max(fogged.w), ..fogged }
+        });
+    }
+}
\ No newline at end of file
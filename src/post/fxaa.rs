@@ -0,0 +1,57 @@
+use crate::post::image::{luma, map_pixels};
+use crate::rasterizer::render_target::RenderTarget;
+
+const REDUCE_MIN: f32 = 1.0 / 128.0;
+const REDUCE_MUL: f32 = 1.0 / 8.0;
+
+// Fast approximate anti-aliasing: finds edges by luma contrast and blurs along them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fxaa {
+    pub span_max: f32,           // longest blur along an edge in pixels
+    pub edge_threshold: f32,     // contrast relative to the brightest neighbour needed to count as an edge
+    pub edge_threshold_min: f32, // and the absolute minimum, keeps dark areas untouched
+}
+
+impl Default for Fxaa {
+    fn default() -> Self {
+        Self { span_max: 8.0, edge_threshold: 0.125, edge_threshold_min: 0.0312 }
+    }
+}
+
+impl Fxaa {
+    pub fn apply(&self, render_target: &mut RenderTarget) {
+        map_pixels(render_target, |source, _, x, y| {
+            let (xi, yi) = (x as i32, y as i32);
+            let center = source.get(xi, yi);
+
+            let luma_m = luma(center);
+            let luma_nw = luma(source.get(xi - 1, yi + 1));
+            let luma_ne = luma(source.get(xi + 1, yi + 1));
+            let luma_sw = luma(source.get(xi - 1, yi - 1));
+            let luma_se = luma(source.get(xi + 1, yi - 1));
+
+            let luma_min = luma_m.min(luma_nw).min(luma_ne).min(luma_sw).min(luma_se);
+            let luma_max = luma_m.max(luma_nw).max(luma_ne).max(luma_sw).max(luma_se);
+            if luma_max - luma_min < self.edge_threshold_min.max(luma_max * self.edge_threshold) {
+                return center;
+            }
+
+            // Perpendicular to the luma gradient, that is along the edge
+            let dir_x = -((luma_nw + luma_ne) - (luma_sw + luma_se));
+            let dir_y = (luma_nw + luma_sw) - (luma_ne + luma_se);
+            let reduce = ((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL).max(REDUCE_MIN);
+            let scale = 1.0 / (dir_x.abs().min(dir_y.abs()) + reduce);
+            let dir_x = (dir_x * scale).clamp(-self.span_max, self.span_max);
+            let dir_y = (dir_y * scale).clamp(-self.span_max, self.span_max);
+
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let tap = |t: f32| source.sample(px + dir_x * t, py + dir_y * t);
+            let near = (tap(1.0 / 3.0 - 0.5) + tap(2.0 / 3.0 - 0.5)) * 0.5;
+            let far = near * 0.5 + (tap(-0.5) + tap(0.5)) * 0.25;
+
+            // The wider blur overshoots when it crosses into another edge
+            let luma_far = luma(far);
+            if luma_far < luma_min || luma_far > luma_max { near } else { far }
+        });
+    }
+}
\ No newline at end of file
@@ -0,0 +1,77 @@
+use rayon::prelude::*;
+
+use crate::math::numerics::float4::Float4;
+use crate::rasterizer::render_target::RenderTarget;
+
+// Colors with their size, rows from the bottom like RenderTarget
+#[derive(Clone)]
+pub struct Image {
+    pub pixels: Vec<Float4>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { pixels: vec![Float4::ZERO; (width * height) as usize], width, height }
+    }
+
+    // Pixel clamped to the edges
+    #[inline(always)]
+    pub fn get(&self, x: i32, y: i32) -> Float4 {
+        let x = x.clamp(0, self.width as i32 - 1) as usize;
+        let y = y.clamp(0, self.height as i32 - 1) as usize;
+        self.pixels[y * self.width as usize + x]
+    }
+
+    // Bilinear sample at a position in pixels, pixel centers are at +0.5
+    #[inline(always)]
+    pub fn sample(&self, x: f32, y: f32) -> Float4 {
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+
+        let bottom = self.get(x0, y0) * (1.0 - tx) + self.get(x0 + 1, y0) * tx;
+        let top = self.get(x0, y0 + 1) * (1.0 - tx) + self.get(x0 + 1, y0 + 1) * tx;
+        bottom * (1.0 - ty) + top * ty
+    }
+}
+
+// Replaces every pixel by `f(source, depth, x, y)`, rows in parallel. `source` is the frame from
+// before the pass, so neighbours can be read while the pixels are written.
+pub fn map_pixels(render_target: &mut RenderTarget, f: impl Fn(&Image, &[f32], u32, u32) -> Float4 + Sync) {
+    let (width, height) = (render_target.width(), render_target.height());
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let (color, depth) = render_target.buffers_mut();
+    let source = Image { pixels: color.to_vec(), width, height };
+    let depth = &*depth;
+    color.par_chunks_mut(width as usize).enumerate().for_each(|(y, row)| {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = f(&source, depth, x as u32, y as u32);
+        }
+    });
+}
+
+// Updates every pixel from its own color and depth, rows in parallel
+pub fn update_pixels(render_target: &mut RenderTarget, f: impl Fn(Float4, f32, u32, u32) -> Float4 + Sync) {
+    let width = render_target.width() as usize;
+    if width == 0 {
+        return;
+    }
+
+    let (color, depth) = render_target.buffers_mut();
+    color.par_chunks_mut(width).zip(depth.par_chunks(width)).enumerate().for_each(|(y, (row, depth_row))| {
+        for (x, (pixel, depth)) in row.iter_mut().zip(depth_row).enumerate() {
+            *pixel = f(*pixel, *depth, x as u32, y as u32);
+        }
+    });
+}
+
+#[inline(always)]
+pub fn luma(c: Float4) -> f32 {
+    c.x * 0.299 + c.y * 0.587 + c.z * 0.114
+}
\ No newline at end of file
@@ -0,0 +1,8 @@
+pub mod stack;
+pub mod image;
+pub mod fxaa;
+pub mod bloom;
+pub mod vignette;
+pub mod color_grading;
+pub mod depth_of_field;
+pub mod fog;
\ No newline at end of file
@@ -0,0 +1,135 @@
+use crate::post::{bloom::Bloom, color_grading::ColorGrading, depth_of_field::DepthOfField, fog::Fog, fxaa::Fxaa, vignette::Vignette};
+use crate::rasterizer::render_target::RenderTarget;
+
+// A full screen pass over the resolved color and depth buffers
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostPass {
+    Fxaa(Fxaa),
+    Bloom(Bloom),
+    Vignette(Vignette),
+    ColorGrading(ColorGrading),
+    DepthOfField(DepthOfField),
+    Fog(Fog),
+}
+
+impl PostPass {
+    pub fn apply(&self, render_target: &mut RenderTarget) {
+        match self {
+            PostPass::Fxaa(p) => p.apply(render_target),
+            PostPass::Bloom(p) => p.apply(render_target),
+            PostPass::Vignette(p) => p.apply(render_target),
+            PostPass::ColorGrading(p) => p.apply(render_target),
+            PostPass::DepthOfField(p) => p.apply(render_target),
+            PostPass::Fog(p) => p.apply(render_target),
+        }
+    }
+
+    // Used by core::scene_file and to toggle passes
+    pub fn name(&self) -> &'static str {
+        match self {
+            PostPass::Fxaa(_) => "fxaa",
+            PostPass::Bloom(_) => "bloom",
+            PostPass::Vignette(_) => "vignette",
+            PostPass::ColorGrading(_) => "color_grading",
+            PostPass::DepthOfField(_) => "depth_of_field",
+            PostPass::Fog(_) => "fog",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostEntry {
+    pub pass: PostPass,
+    pub enabled: bool,
+}
+
+// Camera component, the enabled passes run in order over what the camera rendered
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PostStack {
+    pub passes: Vec<PostEntry>,
+}
+
+impl PostStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, pass: PostPass, enabled: bool) {
+        self.passes.push(PostEntry { pass, enabled });
+    }
+
+    // Resolves MSAA first, anything drawn afterwards (like UI) isn't processed
+    pub fn apply(&self, render_target: &mut RenderTarget) {
+        render_target.resolve();
+        for entry in self.passes.iter().filter(|e| e.enabled) {
+            entry.pass.apply(render_target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::numerics::float4::Float4;
+
+    fn flat(color: Float4, depth: f32) -> RenderTarget {
+        let mut render_target = RenderTarget::new(16, 16);
+        render_target.clear(color);
+        for y in 0..16 {
+            for x in 0..16 {
+                render_target.set_pixel(x, y, color, depth);
+            }
+        }
+        render_target
+    }
+
+    #[test]
+    fn disabled_passes_are_skipped() {
+        let mut stack = PostStack::new();
+        stack.push(PostPass::Fxaa(Fxaa::default()), true);
+        stack.push(PostPass::Fog(Fog::default()), false);
+
+        // Fog would turn everything at infinite depth into the fog color
+        let gray = Float4::new(0.5, 0.5, 0.5, 1.0);
+        let mut render_target = flat(gray, f32::INFINITY);
+        stack.apply(&mut render_target);
+        assert_eq!(render_target.get_pixel_color(8, 8), gray);
+
+        stack.passes[1].enabled = true;
+        stack.apply(&mut render_target);
+        assert_eq!(render_target.get_pixel_color(8, 8), Fog::default().color);
+    }
+
+    #[test]
+    fn flat_in_focus_frames_are_left_alone() {
+        let gray = Float4::new(0.4, 0.4, 0.4, 1.0);
+        let mut stack = PostStack::new();
+        stack.push(PostPass::Fxaa(Fxaa::default()), true);
+        stack.push(PostPass::Bloom(Bloom::default()), true);
+        stack.push(PostPass::DepthOfField(DepthOfField { focus_distance: 2.0, ..DepthOfField::default() }), true);
+        stack.push(PostPass::ColorGrading(ColorGrading::default()), true);
+        let mut render_target = flat(gray, 2.0);
+        stack.apply(&mut render_target);
+        for c in render_target.color_buffer() {
+            assert!((c.x - 0.4).abs() < 1e-4 && c.w == 1.0, "{c:?}");
+        }
+    }
+
+    #[test]
+    fn fxaa_smooths_edges_and_vignette_darkens_corners() {
+        let mut render_target = flat(Float4::new(0.0, 0.0, 0.0, 1.0), 1.0);
+        for y in 0..16 {
+            for x in 0..y {
+                render_target.set_pixel(x, y, Float4::ONE, 1.0);
+            }
+        }
+        Fxaa::default().apply(&mut render_target);
+        let edge = render_target.get_pixel_color(8, 8).x;
+        assert!(edge > 0.0 && edge < 1.0, "{edge}");
+        assert_eq!(render_target.get_pixel_color(2, 12).x, 1.0);
+
+        let mut render_target = flat(Float4::ONE, 1.0);
+        Vignette::default().apply(&mut render_target);
+        assert!(render_target.get_pixel_color(0, 0).x < render_target.get_pixel_color(8, 8).x);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,32 @@
+use crate::math::mathf as f;
+use crate::math::numerics::float4::Float4;
+use crate::post::image::update_pixels;
+use crate::rasterizer::render_target::RenderTarget;
+
+// Darkens towards the corners. Distances are 0 at the center and 1 in the corners.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vignette {
+    pub intensity: f32, // how dark it gets, 0 to 1
+    pub radius: f32,    // distance where it's fully dark
+    pub softness: f32,  // how far in from `radius` it starts
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self { intensity: 0.4, radius: 1.0, softness: 0.6 }
+    }
+}
+
+impl Vignette {
+    pub fn apply(&self, render_target: &mut RenderTarget) {
+        let (width, height) = (render_target.width() as f32, render_target.height() as f32);
+        update_pixels(render_target, |color, _, x, y| {
+            let u = (x as f32 + 0.5) / width * 2.0 - 1.0;
+            let v = (y as f32 + 0.5) / height * 2.0 - 1.0;
+            let distance = (u * u + v * v).sqrt() * std::f32::consts::FRAC_1_SQRT_2;
+
+            let shade = 1.0 - f::smoothstep(self.radius - self.softness, self.radius, distance) * self.intensity.clamp(0.0, 1.0);
+            Float4 { x: color.x * shade, y: color.y * shade, z: color.z * shade, w: color.w }
+        });
+    }
+}
\ No newline at end of file
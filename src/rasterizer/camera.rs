@@ -1,15 +1,253 @@
-use crate::types::transform::Transform;
+use crate::{math::numerics::{float2::Float2, float3::Float3}, types::{ray::Ray, transform::Transform}};
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Projection {
+    // Vertical field of view, the horizontal one follows the aspect ratio
+    Perspective { fov_degrees: f32 },
+    // Half of the vertical extent in world units
+    Orthographic { size: f32 },
+    // Off-axis frustum, edges of the view plane at distance 1. Ignores the aspect ratio.
+    Frustum { left: f32, right: f32, bottom: f32, top: f32 },
+}
+
+// Part of the render target the camera draws into, as fractions of its size (y up like the render target)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub const FULL: Self = Self { x: 0.0, y: 0.0, width: 1.0, height: 1.0 };
+
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+}
+
+#[derive(Clone)]
 pub struct Camera {
-    pub fov_degrees: f32,
+    pub projection: Projection,
+    pub aspect: Option<f32>, // width / height, None follows the viewport
+    pub viewport: Viewport,
     pub transform: Transform,
 }
 
 impl Camera {
+    #[allow(dead_code)]
     pub fn new(fov_degrees: f32) -> Self {
+        Self::with_projection(Projection::Perspective { fov_degrees })
+    }
+
+    pub fn with_projection(projection: Projection) -> Self {
         Self {
-            fov_degrees,
+            projection,
+            aspect: None,
+            viewport: Viewport::FULL,
             transform: Transform::default(),
         }
     }
+
+    // Orthographic cameras don't divide by depth
+    pub fn is_perspective(&self) -> bool {
+        !matches!(self.projection, Projection::Orthographic { .. })
+    }
+
+    // (x, y, width, height) in pixels of a render target
+    pub fn viewport_rect(&self, width: u32, height: u32) -> (f32, f32, f32, f32) {
+        let (w, h) = (width as f32, height as f32);
+        (self.viewport.x * w, self.viewport.y * h, self.viewport.width * w, self.viewport.height * h)
+    }
+
+    pub fn aspect_ratio(&self, width: u32, height: u32) -> f32 {
+        self.aspect.unwrap_or_else(|| {
+            let (_, _, w, h) = self.viewport_rect(width, height);
+            w / h
+        })
+    }
+
+    // (left, right, bottom, top) of the view volume. For perspective projections these are
+    // positions on the view plane at distance 1, for orthographic ones world units.
+    pub fn extents(&self, width: u32, height: u32) -> (f32, f32, f32, f32) {
+        match self.projection {
+            Projection::Perspective { fov_degrees } => {
+                let tan_y = (fov_degrees.to_radians() / 2.0).tan();
+                let tan_x = tan_y * self.aspect_ratio(width, height);
+                (-tan_x, tan_x, -tan_y, tan_y)
+            }
+            Projection::Orthographic { size } => {
+                let half_width = size * self.aspect_ratio(width, height);
+                (-half_width, half_width, -size, size)
+            }
+            Projection::Frustum { left, right, bottom, top } => (left, right, bottom, top),
+        }
+    }
+
+    // View space point to render target position
+    #[inline(always)]
+    pub fn view_to_screen(&self, view: Float3, width: u32, height: u32) -> Float2 {
+        let (left, right, bottom, top) = self.extents(width, height);
+        let (vx, vy, vw, vh) = self.viewport_rect(width, height);
+        let (x, y) = if self.is_perspective() { (view.x / view.z, view.y / view.z) } else { (view.x, view.y) };
+
+        Float2::new(vx + (x - left) / (right - left) * vw, vy + (y - bottom) / (top - bottom) * vh)
+    }
+
+    // Screen pixels covered by one world unit at view depth `z`, horizontally and vertically
+    pub fn pixels_per_world_unit(&self, z: f32, width: u32, height: u32) -> Float2 {
+        let (left, right, bottom, top) = self.extents(width, height);
+        let (_, _, vw, vh) = self.viewport_rect(width, height);
+        let z = if self.is_perspective() { z } else { 1.0 };
+        Float2::new(vw / ((right - left) * z), vh / ((top - bottom) * z))
+    }
+
+    // World space ray through a render target position, the inverse of view_to_screen.
+    // Positions use the render target's convention (y up, pixel centers at +0.5).
+    pub fn screen_point_to_ray(&self, screen_pos: Float2, width: u32, height: u32) -> Ray {
+        let (left, right, bottom, top) = self.extents(width, height);
+        let (vx, vy, vw, vh) = self.viewport_rect(width, height);
+        let x = left + (screen_pos.x - vx) / vw * (right - left);
+        let y = bottom + (screen_pos.y - vy) / vh * (top - bottom);
+
+        let (origin, target) = if self.is_perspective() {
+            (Float3::ZERO, Float3::new(x, y, 1.0))
+        } else {
+            (Float3::new(x, y, 0.0), Float3::new(x, y, 1.0))
+        };
+
+        let origin = self.transform.to_world_point(origin);
+        Ray::new(origin, self.transform.to_world_point(target) - origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cameras() -> Vec<Camera> {
+        let mut cameras = vec![
+            Camera::new(70.0),
+            Camera::with_projection(Projection::Orthographic { size: 1.5 }),
+            Camera::with_projection(Projection::Frustum { left: -0.3, right: 0.9, bottom: -0.2, top: 0.5 }),
+        ];
+        for cam in &mut cameras {
+            cam.transform.set_position(Float3::new(1.0, 2.0, -3.0));
+            cam.transform.set_rotation(Float3::new(0.3, -0.7, 0.1));
+            cam.viewport = Viewport::new(0.25, 0.1, 0.5, 0.8);
+        }
+        cameras
+    }
+
+    fn assert_extents(actual: (f32, f32, f32, f32), expected: (f32, f32, f32, f32)) {
+        let (a, e) = ([actual.0, actual.1, actual.2, actual.3], [expected.0, expected.1, expected.2, expected.3]);
+        assert!(a.iter().zip(e).all(|(a, e)| (a - e).abs() < 1e-5), "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn extents_follow_the_projection_and_aspect() {
+        let (width, height) = (320, 200);
+
+        let mut cam = Camera::new(90.0);
+        assert_extents(cam.extents(width, height), (-1.6, 1.6, -1.0, 1.0));
+        cam.viewport = Viewport::new(0.5, 0.0, 0.5, 1.0);
+        assert_extents(cam.extents(width, height), (-0.8, 0.8, -1.0, 1.0));
+        cam.aspect = Some(2.0);
+        assert_extents(cam.extents(width, height), (-2.0, 2.0, -1.0, 1.0));
+
+        let mut cam = Camera::with_projection(Projection::Orthographic { size: 1.5 });
+        assert_extents(cam.extents(width, height), (-2.4, 2.4, -1.5, 1.5));
+        cam.aspect = Some(1.0);
+        assert_extents(cam.extents(width, height), (-1.5, 1.5, -1.5, 1.5));
+
+        // Off-axis frustums are used as given
+        let mut cam = Camera::with_projection(Projection::Frustum { left: -0.3, right: 0.9, bottom: -0.2, top: 0.5 });
+        assert_extents(cam.extents(width, height), (-0.3, 0.9, -0.2, 0.5));
+        cam.viewport = Viewport::new(0.0, 0.0, 0.25, 1.0);
+        cam.aspect = Some(3.0);
+        assert_extents(cam.extents(width, height), (-0.3, 0.9, -0.2, 0.5));
+    }
+
+    #[test]
+    fn view_to_screen_maps_the_view_volume_onto_the_viewport() {
+        let (width, height) = (320, 200);
+        for cam in cameras() {
+            let (left, right, bottom, top) = cam.extents(width, height);
+            let (vx, vy, vw, vh) = cam.viewport_rect(width, height);
+            assert_eq!((vx, vy, vw, vh), (80.0, 20.0, 160.0, 160.0));
+
+            // Perspective cameras scale the extents with depth, orthographic ones don't
+            for z in [0.5, 1.0, 4.0] {
+                let depth = if cam.is_perspective() { z } else { 1.0 };
+                let corner = |x: f32, y: f32| cam.view_to_screen(Float3::new(x * depth, y * depth, z), width, height);
+
+                assert!((corner(left, bottom) - Float2::new(vx, vy)).length() < 1e-3, "{:?}", cam.projection);
+                assert!((corner(right, top) - Float2::new(vx + vw, vy + vh)).length() < 1e-3, "{:?}", cam.projection);
+                assert!((corner(left, top) - Float2::new(vx, vy + vh)).length() < 1e-3, "{:?}", cam.projection);
+                let center = corner((left + right) / 2.0, (bottom + top) / 2.0);
+                assert!((center - Float2::new(vx + vw / 2.0, vy + vh / 2.0)).length() < 1e-3, "{:?}", cam.projection);
+
+                // One world unit covers pixels_per_world_unit pixels at that depth
+                let ppu = cam.pixels_per_world_unit(z, width, height);
+                let step = cam.view_to_screen(Float3::new(1.0, 1.0, z), width, height) - cam.view_to_screen(Float3::new(0.0, 0.0, z), width, height);
+                assert!((step - ppu).length() < 1e-3, "{:?}", cam.projection);
+            }
+        }
+    }
+
+    #[test]
+    fn view_to_screen_inverts_screen_point_to_ray() {
+        let (width, height) = (320, 200);
+        for cam in cameras() {
+            for screen in [Float2::new(80.5, 20.5), Float2::new(160.0, 100.0), Float2::new(239.5, 179.5), Float2::new(100.25, 150.75)] {
+                let ray = cam.screen_point_to_ray(screen, width, height);
+                for t in [0.5, 1.0, 10.0] {
+                    let view = cam.transform.to_local_point(ray.at(t));
+                    assert!(view.z > 0.0, "{:?}", cam.projection);
+                    assert!((cam.view_to_screen(view, width, height) - screen).length() < 1e-3, "{:?}: {screen:?}", cam.projection);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn screen_point_to_ray_inverts_view_to_screen() {
+        let (width, height) = (320, 200);
+        for cam in cameras() {
+            for view in [Float3::new(0.0, 0.0, 1.0), Float3::new(0.4, -0.3, 2.5), Float3::new(-1.2, 0.8, 7.0), Float3::new(0.05, 0.1, 0.3)] {
+                let screen = cam.view_to_screen(view, width, height);
+                let ray = cam.screen_point_to_ray(screen, width, height);
+
+                // The world point seen at that pixel lies on the ray, in front of the camera
+                let world = cam.transform.to_world_point(view);
+                let t = (world - ray.origin).dot(ray.direction);
+                assert!(t > 0.0, "{:?}", cam.projection);
+                assert!((ray.at(t) - world).length() < 1e-4, "{:?}: {view:?}", cam.projection);
+
+                // And every point along it projects back to the same pixel
+                let further = cam.transform.to_local_point(ray.at(t * 1.5));
+                assert!((cam.view_to_screen(further, width, height) - screen).length() < 1e-3, "{:?}", cam.projection);
+            }
+        }
+    }
+
+    #[test]
+    fn rays_start_at_the_eye_or_the_view_plane() {
+        let (width, height) = (320, 200);
+        let cameras = cameras();
+        let (perspective, orthographic) = (&cameras[0], &cameras[1]);
+
+        let a = perspective.screen_point_to_ray(Float2::new(100.0, 50.0), width, height);
+        let b = perspective.screen_point_to_ray(Float2::new(200.0, 150.0), width, height);
+        assert!((a.origin - perspective.transform.position).length() < 1e-5);
+        assert!((a.origin - b.origin).length() < 1e-5 && (a.direction - b.direction).length() > 0.1);
+
+        // Orthographic rays are parallel, along the camera's forward direction
+        let a = orthographic.screen_point_to_ray(Float2::new(100.0, 50.0), width, height);
+        let b = orthographic.screen_point_to_ray(Float2::new(200.0, 150.0), width, height);
+        assert!((a.direction - b.direction).length() < 1e-5);
+        assert!((a.direction - orthographic.transform.forward()).length() < 1e-5);
+        assert!((a.origin - b.origin).length() > 0.1);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,155 @@
+use crate::{math::numerics::float3::Float3, rasterizer::{camera::Camera, render_target::RenderTarget}, types::bounds::BoundingSphere};
+
+// Same distance process_model clips triangles at
+pub const NEAR_CLIP_DST: f32 = 0.01;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CullReason {
+    Frustum,
+    Occlusion,
+}
+
+// Side planes of the view volume in view space, as inward normals and offsets
+pub struct Frustum {
+    planes: [(Float3, f32); 4],
+}
+
+impl Frustum {
+    pub fn new(cam: &Camera, render_target: &RenderTarget) -> Self {
+        let (left, right, bottom, top) = cam.extents(render_target.width(), render_target.height());
+
+        let planes = if cam.is_perspective() {
+            // Planes through the eye and the edges of the view plane at distance 1
+            [
+                Float3::new(1.0, 0.0, -left),
+                Float3::new(-1.0, 0.0, right),
+                Float3::new(0.0, 1.0, -bottom),
+                Float3::new(0.0, -1.0, top),
+            ].map(|n| (n.normalize(), 0.0))
+        } else {
+            [
+                (Float3::UNIT_X, -left),
+                (Float3::ZERO - Float3::UNIT_X, right),
+                (Float3::UNIT_Y, -bottom),
+                (Float3::ZERO - Float3::UNIT_Y, top),
+            ]
+        };
+
+        Self { planes }
+    }
+
+    // Sphere center in view space. Conservative: spheres near a frustum corner may pass.
+    pub fn intersects_sphere(&self, center: Float3, radius: f32) -> bool {
+        if center.z + radius <= NEAR_CLIP_DST {
+            return false;
+        }
+        self.planes.iter().all(|(normal, offset)| normal.dot(center) + offset >= -radius)
+    }
+}
+
+// Max-depth pyramid of a finished frame. Level 0 is the full depth buffer,
+// every further level halves the resolution and keeps the farthest depth of the 2x2 block below.
+pub struct HiZBuffer {
+    levels: Vec<(u32, u32, Vec<f32>)>,
+    cam: Camera, // camera the pyramid was rendered with
+}
+
+impl HiZBuffer {
+    pub fn build(depth: &[f32], width: u32, height: u32, cam: &Camera) -> Self {
+        let mut levels = vec![(width, height, depth.to_vec())];
+
+        while let Some((w, h, data)) = levels.last().filter(|(w, h, _)| *w > 1 || *h > 1) {
+            let (nw, nh) = (w.div_ceil(2), h.div_ceil(2));
+            let mut next = vec![0.0; (nw * nh) as usize];
+            for y in 0..nh {
+                for x in 0..nw {
+                    let mut farthest: f32 = 0.0;
+                    for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                        let (px, py) = ((x * 2 + sx).min(w - 1), (y * 2 + sy).min(h - 1));
+                        farthest = farthest.max(data[(py * w + px) as usize]);
+                    }
+                    next[(y * nw + x) as usize] = farthest;
+                }
+            }
+            levels.push((nw, nh, next));
+        }
+
+        Self { levels, cam: cam.clone() }
+    }
+
+    // True if the sphere was completely hidden behind the depth this pyramid was built from.
+    // Tested from the camera the pyramid was rendered with, so a model shows up at most one frame late.
+    pub fn is_occluded(&self, sphere: &BoundingSphere) -> bool {
+        let view = self.cam.transform.to_local_point(sphere.center);
+        let nearest = view.z - sphere.radius;
+        if nearest <= NEAR_CLIP_DST {
+            return false;
+        }
+
+        let (width, height, _) = self.levels[0];
+        let center = self.cam.view_to_screen(view, width, height);
+        let pixels_per_unit = self.cam.pixels_per_world_unit(nearest, width, height);
+        let radius_px = sphere.radius * pixels_per_unit.x.max(pixels_per_unit.y);
+
+        let min_x = ((center.x - radius_px).floor().max(0.0)) as u32;
+        let min_y = ((center.y - radius_px).floor().max(0.0)) as u32;
+        let max_x = (center.x + radius_px).ceil().min(width as f32 - 1.0);
+        let max_y = (center.y + radius_px).ceil().min(height as f32 - 1.0);
+        if max_x < min_x as f32 || max_y < min_y as f32 {
+            // Outside of the old view, nothing known about it
+            return false;
+        }
+        let (max_x, max_y) = (max_x as u32, max_y as u32);
+
+        // Coarsest level where the rect spans at most 2x2 texels
+        let extent = (max_x - min_x).max(max_y - min_y).max(1);
+        let level = ((32 - extent.leading_zeros()) as usize).min(self.levels.len() - 1);
+        let (lw, _, data) = &self.levels[level];
+
+        for y in (min_y >> level)..=(max_y >> level) {
+            for x in (min_x >> level)..=(max_x >> level) {
+                if data[(y * lw + x) as usize] >= nearest {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rasterizer::camera::Projection;
+
+    #[test]
+    fn frustum_rejects_spheres_outside_the_sides_and_behind() {
+        let render_target = RenderTarget::new(32, 32);
+        let frustum = Frustum::new(&Camera::new(90.0), &render_target);
+        assert!(frustum.intersects_sphere(Float3::new(0.0, 0.0, 5.0), 1.0));
+        // Its center is right of the 45° side plane, but it reaches back in
+        assert!(frustum.intersects_sphere(Float3::new(5.5, 0.0, 5.0), 1.0));
+        assert!(!frustum.intersects_sphere(Float3::new(7.0, 0.0, 5.0), 1.0));
+        assert!(!frustum.intersects_sphere(Float3::new(0.0, -7.0, 5.0), 1.0));
+        assert!(!frustum.intersects_sphere(Float3::new(0.0, 0.0, -5.0), 1.0));
+
+        let frustum = Frustum::new(&Camera::with_projection(Projection::Orthographic { size: 2.0 }), &render_target);
+        assert!(!frustum.intersects_sphere(Float3::new(3.0, 0.0, 5.0), 0.5));
+        assert!(frustum.intersects_sphere(Float3::new(3.0, 0.0, 5.0), 1.5));
+    }
+
+    #[test]
+    fn hi_z_hides_spheres_behind_the_depth_only() {
+        // Bottom half covered at depth 2, top half empty
+        let depth: Vec<f32> = (0..32 * 32).map(|i| if i / 32 < 16 { 2.0 } else { f32::INFINITY }).collect();
+        let hi_z = HiZBuffer::build(&depth, 32, 32, &Camera::new(90.0));
+
+        assert!(hi_z.is_occluded(&BoundingSphere::new(Float3::new(0.0, -2.5, 5.0), 0.5)));
+        // Nothing drawn in front of it
+        assert!(!hi_z.is_occluded(&BoundingSphere::new(Float3::new(0.0, 2.5, 5.0), 0.5)));
+        // In front of the occluder
+        assert!(!hi_z.is_occluded(&BoundingSphere::new(Float3::new(0.0, -0.75, 1.5), 0.1)));
+        // Partly behind the empty half
+        assert!(!hi_z.is_occluded(&BoundingSphere::new(Float3::new(0.0, -0.2, 5.0), 1.0)));
+    }
+}
\ No newline at end of file
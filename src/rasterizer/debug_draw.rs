@@ -0,0 +1,361 @@
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+
+use crate::math::numerics::{float3::Float3, float4::Float4};
+use crate::rasterizer::{camera::Camera, culling::NEAR_CLIP_DST, rasterizer::{self, LineDepth, LineStyle}, render_target::RenderTarget};
+use crate::types::{bounds::Aabb, transform::Transform};
+use crate::ui::font::BitmapFont;
+
+pub const RED: Float4 = Float4::new(1.0, 0.2, 0.2, 1.0);
+pub const GREEN: Float4 = Float4::new(0.2, 1.0, 0.2, 1.0);
+pub const BLUE: Float4 = Float4::new(0.3, 0.4, 1.0, 1.0);
+pub const YELLOW: Float4 = Float4::new(1.0, 0.9, 0.2, 1.0);
+pub const WHITE: Float4 = Float4::ONE;
+
+const CIRCLE_SEGMENTS: usize = 24;
+
+struct Line {
+    start: Float3,
+    end: Float3,
+    color: Float4,
+    depth_test: bool,
+}
+
+struct Label {
+    position: Float3,
+    text: String,
+    color: Float4,
+    depth_test: bool,
+}
+
+// Shapes in world space, drawn over the frame as lines. Everything is broken into lines when it's
+// added, so drawing only has to project them.
+pub struct DebugDraw {
+    lines: Vec<Line>,
+    labels: Vec<Label>,
+    depth_test: bool,
+    font: BitmapFont,
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self { lines: Vec::new(), labels: Vec::new(), depth_test: true, font: BitmapFont::builtin() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.labels.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.labels.clear();
+        self.depth_test = true;
+    }
+
+    // Whether shapes added from now on are hidden behind geometry, on until the next clear
+    pub fn set_depth_test(&mut self, enabled: bool) {
+        self.depth_test = enabled;
+    }
+
+    pub fn line(&mut self, start: Float3, end: Float3, color: Float4) {
+        self.lines.push(Line { start, end, color, depth_test: self.depth_test });
+    }
+
+    // Line with a four-sided head at `end`
+    pub fn arrow(&mut self, start: Float3, end: Float3, color: Float4) {
+        self.line(start, end, color);
+
+        let direction = end - start;
+        let length = direction.length();
+        if length < f32::EPSILON {
+            return;
+        }
+        let (side, up) = perpendiculars(direction / length);
+        let head = length * 0.15;
+        let base = end - direction / length * head;
+        for offset in [side, side * -1.0, up, up * -1.0] {
+            self.line(end, base + offset * (head * 0.5), color);
+        }
+    }
+
+    pub fn aabb(&mut self, bounds: &Aabb, color: Float4) {
+        self.box_corners(bounds.corners(), color);
+    }
+
+    // Box given in the transform's local space, drawn rotated and scaled with it
+    pub fn obb(&mut self, local_bounds: &Aabb, transform: &Transform, color: Float4) {
+        self.box_corners(local_bounds.corners().map(|c| transform.to_world_point(c)), color);
+    }
+
+    // Corners in Aabb::corners order, the index bits are the x, y and z side
+    fn box_corners(&mut self, corners: [Float3; 8], color: Float4) {
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.line(corners[i], corners[i | bit], color);
+                }
+            }
+        }
+    }
+
+    pub fn circle(&mut self, center: Float3, normal: Float3, radius: f32, color: Float4) {
+        let (a, b) = perpendiculars(normal.normalize());
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + (a * angle.cos() + b * angle.sin()) * radius
+        };
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    // Three circles around the axes
+    pub fn sphere(&mut self, center: Float3, radius: f32, color: Float4) {
+        for normal in [Float3::UNIT_X, Float3::UNIT_Y, Float3::UNIT_Z] {
+            self.circle(center, normal, radius, color);
+        }
+    }
+
+    // The transform's right, up and forward axes in red, green and blue
+    pub fn axes(&mut self, transform: &Transform, length: f32) {
+        let origin = transform.position;
+        self.arrow(origin, origin + transform.right() * length, RED);
+        self.arrow(origin, origin + transform.up() * length, GREEN);
+        self.arrow(origin, origin + transform.forward() * length, BLUE);
+    }
+
+    // Square grid on the XZ plane through `center`, `cells` cells along each side
+    pub fn grid(&mut self, center: Float3, size: f32, cells: u32, color: Float4) {
+        let half = size * 0.5;
+        for i in 0..=cells {
+            let offset = -half + size * i as f32 / cells.max(1) as f32;
+            self.line(center + Float3::new(offset, 0.0, -half), center + Float3::new(offset, 0.0, half), color);
+            self.line(center + Float3::new(-half, 0.0, offset), center + Float3::new(half, 0.0, offset), color);
+        }
+    }
+
+    // View volume of `cam` from the near clip distance to `far`, `aspect` is width / height of its viewport
+    pub fn frustum(&mut self, cam: &Camera, aspect: f32, far: f32, color: Float4) {
+        let cam = Camera { aspect: Some(aspect), ..cam.clone() };
+        let (left, right, bottom, top) = cam.extents(1, 1);
+        let scale = |distance: f32| if cam.is_perspective() { distance } else { 1.0 };
+
+        let mut corners = [Float3::ZERO; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let x = if i & 1 == 0 { left } else { right };
+            let y = if i & 2 == 0 { bottom } else { top };
+            let z = if i & 4 == 0 { NEAR_CLIP_DST } else { far };
+            *corner = cam.transform.to_world_point(Float3::new(x * scale(z), y * scale(z), z));
+        }
+        self.box_corners(corners, color);
+    }
+
+    // Text centered on a world position
+    pub fn text(&mut self, position: Float3, text: &str, color: Float4) {
+        self.labels.push(Label { position, text: text.to_string(), color, depth_test: self.depth_test });
+    }
+
+    // Draws everything as seen by `cam`, without writing depth
+    pub fn render(&self, render_target: &mut RenderTarget, cam: &Camera) {
+        let scissor = rasterizer::viewport_pixels(render_target, cam);
+        let (width, height) = (render_target.width(), render_target.height());
+
+        for line in &self.lines {
+            let start = cam.transform.to_local_point(line.start);
+            let end = cam.transform.to_local_point(line.end);
+            let Some((start, end)) = clip_near(start, end) else { continue };
+
+            let to_screen = |view: Float3| {
+                let screen = cam.view_to_screen(view, width, height);
+                Float3::new(screen.x, screen.y, view.z)
+            };
+            let depth = if line.depth_test { LineDepth::Test } else { LineDepth::Ignore };
+            let style = LineStyle::new(line.color, 1.0, depth);
+            rasterizer::draw_line(render_target, scissor, to_screen(start), to_screen(end), &style, cam.is_perspective());
+        }
+
+        for label in &self.labels {
+            let view = cam.transform.to_local_point(label.position);
+            if view.z < NEAR_CLIP_DST {
+                continue;
+            }
+            let screen = cam.view_to_screen(view, width, height);
+            let (x, y) = (screen.x.floor() as i32, screen.y.floor() as i32);
+            if x < scissor[0] || y < scissor[1] || x > scissor[2] || y > scissor[3] {
+                continue;
+            }
+            if label.depth_test && view.z > render_target.get_pixel_depth(x as u32, y as u32) {
+                continue;
+            }
+
+            // Font positions are y down
+            let (text_width, text_height) = self.font.measure(&label.text);
+            let top = height as i32 - 1 - y - text_height as i32 / 2;
+            self.font.draw(render_target, &label.text, x - text_width as i32 / 2, top, label.color);
+        }
+    }
+}
+
+// Two unit vectors perpendicular to `direction` and each other
+fn perpendiculars(direction: Float3) -> (Float3, Float3) {
+    let helper = if direction.y.abs() < 0.9 { Float3::UNIT_Y } else { Float3::UNIT_X };
+    let a = direction.cross(helper).normalize();
+    (a, direction.cross(a).normalize())
+}
+
+// View space segment cut at the near clip distance, None when it's all behind it
+fn clip_near(start: Float3, end: Float3) -> Option<(Float3, Float3)> {
+    match (start.z >= NEAR_CLIP_DST, end.z >= NEAR_CLIP_DST) {
+        (true, true) => Some((start, end)),
+        (false, false) => None,
+        (start_in, _) => {
+            let t = (NEAR_CLIP_DST - start.z) / (end.z - start.z);
+            let clipped = start + (end - start) * t;
+            Some(if start_in { (start, clipped) } else { (clipped, end) })
+        }
+    }
+}
+
+// Queue scenes add to during update. The engine clears it at the start of every frame and
+// rasterizer::render draws it after the models, for every camera.
+static QUEUE: Lazy<Mutex<DebugDraw>> = Lazy::new(|| { Mutex::new(DebugDraw::new()) });
+
+pub fn clear() {
+    QUEUE.lock().unwrap().clear();
+}
+
+#[allow(dead_code)]
+pub fn set_depth_test(enabled: bool) {
+    QUEUE.lock().unwrap().set_depth_test(enabled);
+}
+
+#[allow(dead_code)]
+pub fn line(start: Float3, end: Float3, color: Float4) {
+    QUEUE.lock().unwrap().line(start, end, color);
+}
+
+pub fn arrow(start: Float3, end: Float3, color: Float4) {
+    QUEUE.lock().unwrap().arrow(start, end, color);
+}
+
+#[allow(dead_code)]
+pub fn aabb(bounds: &Aabb, color: Float4) {
+    QUEUE.lock().unwrap().aabb(bounds, color);
+}
+
+pub fn obb(local_bounds: &Aabb, transform: &Transform, color: Float4) {
+    QUEUE.lock().unwrap().obb(local_bounds, transform, color);
+}
+
+#[allow(dead_code)]
+pub fn sphere(center: Float3, radius: f32, color: Float4) {
+    QUEUE.lock().unwrap().sphere(center, radius, color);
+}
+
+pub fn axes(transform: &Transform, length: f32) {
+    QUEUE.lock().unwrap().axes(transform, length);
+}
+
+pub fn grid(center: Float3, size: f32, cells: u32, color: Float4) {
+    QUEUE.lock().unwrap().grid(center, size, cells, color);
+}
+
+#[allow(dead_code)]
+pub fn frustum(cam: &Camera, aspect: f32, far: f32, color: Float4) {
+    QUEUE.lock().unwrap().frustum(cam, aspect, far, color);
+}
+
+pub fn text(position: Float3, text: &str, color: Float4) {
+    QUEUE.lock().unwrap().text(position, text, color);
+}
+
+pub fn render(render_target: &mut RenderTarget, cam: &Camera) {
+    let queue = QUEUE.lock().unwrap();
+    if !queue.is_empty() {
+        queue.render(render_target, cam);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera() -> Camera {
+        let mut cam = Camera::new(90.0);
+        cam.transform.set_position(Float3::new(0.0, 0.0, -2.0));
+        cam
+    }
+
+    fn lit_pixels(render_target: &RenderTarget) -> usize {
+        render_target.color_buffer().iter().filter(|c| c.w > 0.0).count()
+    }
+
+    #[test]
+    fn shapes_break_into_lines() {
+        let mut debug = DebugDraw::new();
+        debug.aabb(&Aabb { min: Float3::ZERO, max: Float3::ONE }, WHITE);
+        assert_eq!(debug.lines.len(), 12);
+
+        debug.clear();
+        debug.arrow(Float3::ZERO, Float3::UNIT_X, WHITE);
+        debug.grid(Float3::ZERO, 2.0, 4, WHITE);
+        assert_eq!(debug.lines.len(), 5 + 10);
+
+        // Arrow heads point back along the arrow
+        for line in &debug.lines[1..5] {
+            assert_eq!(line.start, Float3::UNIT_X);
+            assert!(line.end.x < 1.0);
+        }
+    }
+
+    #[test]
+    fn depth_test_hides_lines_without_writing_depth() {
+        let cam = camera();
+        let mut render_target = RenderTarget::new(32, 32);
+        let mut debug = DebugDraw::new();
+        debug.line(Float3::new(-1.0, 0.0, 0.0), Float3::new(1.0, 0.0, 0.0), WHITE);
+
+        debug.render(&mut render_target, &cam);
+        assert!(lit_pixels(&render_target) > 0);
+        assert!(render_target.depth_buffer().iter().all(|d| *d == f32::INFINITY));
+
+        // Geometry in front of the line
+        render_target.clear(Float4::ZERO);
+        for x in 0..32 {
+            for y in 0..32 {
+                render_target.set_pixel(x, y, Float4::new(0.0, 0.0, 0.0, 0.5), 1.0);
+            }
+        }
+        let before = render_target.checksum();
+        debug.render(&mut render_target, &cam);
+        assert_eq!(render_target.checksum(), before);
+
+        debug.clear();
+        debug.set_depth_test(false);
+        debug.line(Float3::new(-1.0, 0.0, 0.0), Float3::new(1.0, 0.0, 0.0), WHITE);
+        debug.render(&mut render_target, &cam);
+        assert_ne!(render_target.checksum(), before);
+    }
+
+    #[test]
+    fn lines_behind_the_camera_are_clipped() {
+        let cam = camera();
+        let mut render_target = RenderTarget::new(32, 32);
+        let mut debug = DebugDraw::new();
+        debug.line(Float3::new(0.0, 0.0, -5.0), Float3::new(0.0, 0.0, -3.0), WHITE);
+        debug.text(Float3::new(0.0, 0.0, -4.0), "behind", WHITE);
+        debug.render(&mut render_target, &cam);
+        assert_eq!(lit_pixels(&render_target), 0);
+
+        // Crossing the near plane, only the part in front is drawn
+        debug.line(Float3::new(0.5, 0.5, -5.0), Float3::new(0.5, 0.5, 5.0), WHITE);
+        debug.render(&mut render_target, &cam);
+        assert!(lit_pixels(&render_target) > 0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,20 @@
+pub struct ModelStats {
+    pub name: String,
+    pub lod: Option<usize>, // selected level, None for renderers without LODs
+}
+
+// What the rasterizer did during the last render call
+#[derive(Default)]
+pub struct FrameStats {
+    pub models: Vec<ModelStats>,
+    pub triangles: usize,
+    pub drawn: usize,
+    pub frustum_culled: usize,
+    pub occlusion_culled: usize,
+}
+
+impl FrameStats {
+    pub fn model(&self, name: &str) -> Option<&ModelStats> {
+        self.models.iter().find(|m| m.name == name)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,53 @@
+use crate::math::numerics::float3::Float3;
+
+// Nearest opaque surface per pixel, drawn before the models are shaded while SSAO is on.
+// Rows from the bottom like RenderTarget.
+pub struct GBuffer {
+    pub depths: Vec<f32>,      // view depth, like the depth buffer but one surface per pixel with MSAA
+    pub normals: Vec<Float3>,  // view space, zero where nothing was drawn
+    pub visibility: Vec<f32>,  // share of the ambient light reaching the surface, filled in by SSAO
+
+    width: u32,
+    height: u32,
+}
+
+impl GBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let count = (width * height) as usize;
+        Self {
+            depths: vec![f32::INFINITY; count],
+            normals: vec![Float3::ZERO; count],
+            visibility: vec![1.0; count],
+            width,
+            height,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.depths.fill(f32::INFINITY);
+        self.normals.fill(Float3::ZERO);
+        self.visibility.fill(1.0);
+    }
+
+    // The nearest surface wins, triangles come in any order
+    pub fn write(&mut self, index: usize, depth: f32, normal: Float3) {
+        if depth < self.depths[index] {
+            self.depths[index] = depth;
+            self.normals[index] = normal;
+        }
+    }
+
+    // Surfaces behind the recorded one, seen through its uncovered MSAA samples, aren't occluded.
+    // About the same depth counts, like the other triangle along a shared edge.
+    pub fn ambient_visibility(&self, index: usize, depth: f32) -> f32 {
+        if (self.depths[index] - depth).abs() <= depth * 0.05 { self.visibility[index] } else { 1.0 }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
\ No newline at end of file
@@ -1,4 +1,10 @@
 pub mod render_target;
+#[allow(clippy::module_inception)]
 pub mod rasterizer;
 pub mod camera;
-pub mod rasterizer_point;
\ No newline at end of file
+pub mod rasterizer_point;
+pub mod frame_stats;
+pub mod culling;
+pub mod debug_draw;
+pub mod g_buffer;
+pub mod ssao;
\ No newline at end of file
@@ -1,68 +1,277 @@
-use crate::{math::numerics::{float2::Float2, float3::Float3, float4::Float4}, rasterizer::{camera::Camera, rasterizer_point::RasterizerPoint, render_target::RenderTarget}, types::{model::Model, transform::Transform}};
+use crate::{ecs::world::World, math::numerics::{float2::Float2, float3::Float3, float4::Float4}, rasterizer::{camera::Camera, debug_draw, culling::{CullReason, Frustum, NEAR_CLIP_DST}, frame_stats::{FrameStats, ModelStats}, rasterizer_point::RasterizerPoint, render_target::{MAX_SAMPLES, RenderTarget}}, shaders::shader_base::Shader, types::{mesh_renderer::{MeshRenderer, Wireframe}, transform::Transform}};
 use crate::math::mathf as f;
 use crate::math::mathi as i;
 
-pub fn render(render_target: &mut RenderTarget, models: &mut Vec<Model>, cam: &Camera) {
-    for model in models.iter_mut() {
-        process_model(model, render_target, cam);
+// Draws every entity with a Transform and a MeshRenderer
+pub fn render(render_target: &mut RenderTarget, world: &World, cam: &Camera) -> FrameStats {
+    let mut stats = FrameStats::default();
+    let frustum = Frustum::new(cam, render_target);
+
+    let transforms = world.read::<Transform>();
+    let mut renderers = world.write::<MeshRenderer>();
+
+    for (entity, renderer) in renderers.iter_mut() {
+        let Some(transform) = transforms.get(entity) else { continue };
+        renderer.shader.refresh_assets();
+        renderer.mesh.refresh();
+
+        let culled = cull_model(transform, renderer, render_target, cam, &frustum);
+        let lod = if culled.is_none() { select_lod(transform, renderer, render_target, cam) } else { None };
+
+        match culled {
+            None => {
+                process_model(transform, renderer, render_target, cam);
+                stats.drawn += 1;
+            }
+            Some(reason) => {
+                renderer.rasterizer_points.clear();
+                renderer.fade_points.clear();
+                match reason {
+                    CullReason::Frustum => stats.frustum_culled += 1,
+                    CullReason::Occlusion => stats.occlusion_culled += 1,
+                }
+            }
+        }
+
+        let triangles = renderer.rasterizer_points.len() / 3;
+        stats.triangles += triangles;
+        stats.models.push(ModelStats { name: world.name(entity), lod });
     }
 
-    for model in models.iter_mut() {
-        for i in (0..model.rasterizer_points.len()).step_by(3) {
-            let r0 = &model.rasterizer_points[i + 0];
-            let r1 = &model.rasterizer_points[i + 1];
-            let r2 = &model.rasterizer_points[i + 2];
-
-            if model.shader.render_as_wireframe() {
-                let line_color = Float4::new(1.0, 1.0, 1.0, 1.0);
-                draw_line(render_target, r0, r1, line_color);
-                draw_line(render_target, r1, r2, line_color);
-                draw_line(render_target, r2, r0, line_color);
-                continue;
+    // Opaque triangles first, the occlusion pyramid is built from their depth alone. Translucent
+    // surfaces and lines write depth too, but what's behind them can still be seen. With SSAO the
+    // opaque surfaces go to the G-buffer before that, so they are shaded with their occlusion.
+    for pass in [DrawPass::GBuffer, DrawPass::Opaque, DrawPass::SeeThrough] {
+        let ssao = render_target.ssao();
+        if pass == DrawPass::GBuffer && ssao.is_none() {
+            continue;
+        }
+        if pass == DrawPass::SeeThrough && render_target.occlusion_culling() {
+            render_target.build_hi_z(cam);
+        }
+        for (entity, renderer) in renderers.iter() {
+            if transforms.contains(entity) {
+                draw_renderer(render_target, cam, renderer, pass);
             }
+        }
+        if pass == DrawPass::GBuffer && let Some(ssao) = ssao {
+            ssao.apply(render_target, cam);
+        }
+    }
+
+    debug_draw::render(render_target, cam);
+
+    stats
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum DrawPass {
+    GBuffer,    // depth and normal of the opaque triangles, only while SSAO is on
+    Opaque,     // filled triangles of opaque shaders
+    SeeThrough, // translucent triangles and all lines
+}
+
+fn draw_renderer(render_target: &mut RenderTarget, cam: &Camera, renderer: &MeshRenderer, pass: DrawPass) {
+    let fade = renderer.lod.as_ref().and_then(|lod| lod.fading()).map(|(_, t)| t);
+    if let Some(t) = fade {
+        draw_triangles(render_target, cam, &renderer.shader, &renderer.wireframe, &renderer.fade_points, Some((t, true)), pass);
+    }
+    draw_triangles(render_target, cam, &renderer.shader, &renderer.wireframe, &renderer.rasterizer_points, fade.map(|t| (t, false)), pass);
+}
+
+// Rejects renderers by their cached bounding sphere before any vertex is transformed
+fn cull_model(transform: &Transform, renderer: &mut MeshRenderer, render_target: &RenderTarget, cam: &Camera, frustum: &Frustum) -> Option<CullReason> {
+    let sphere = renderer.world_bounds(transform);
+
+    let center_view = cam.transform.to_local_point(sphere.center);
+    if !frustum.intersects_sphere(center_view, sphere.radius) {
+        return Some(CullReason::Frustum);
+    }
+
+    if render_target.occlusion_culling() && render_target.hi_z().is_some_and(|hi_z| hi_z.is_occluded(&sphere)) {
+        return Some(CullReason::Occlusion);
+    }
+
+    None
+}
+
+// Picks the LOD level from the size of the projected bounding sphere. While crossfading,
+// the level being faded out is processed into `fade_points`.
+fn select_lod(transform: &Transform, renderer: &mut MeshRenderer, render_target: &RenderTarget, cam: &Camera) -> Option<usize> {
+    let lod = renderer.lod.as_mut()?;
+
+    let sphere = lod.bounds().transformed(transform);
+    let distance = (sphere.center - cam.transform.to_world_point(Float3::ZERO)).length();
+    let (_, _, _, viewport_height) = cam.viewport_rect(render_target.width(), render_target.height());
+    let pixels_per_unit = cam.pixels_per_world_unit(distance, render_target.width(), render_target.height()).y;
+    let screen_size = if cam.is_perspective() && distance <= sphere.radius {
+        f32::INFINITY
+    } else {
+        sphere.radius * 2.0 * pixels_per_unit / viewport_height
+    };
+
+    let level = lod.update(screen_size);
+    let fading = lod.fading().map(|(mesh, _)| mesh.clone());
+    renderer.mesh = lod.current_mesh().clone();
+
+    renderer.fade_points.clear();
+    if let Some(previous) = fading {
+        let current = std::mem::replace(&mut renderer.mesh, previous);
+        process_model(transform, renderer, render_target, cam);
+        std::mem::swap(&mut renderer.rasterizer_points, &mut renderer.fade_points);
+        renderer.mesh = current;
+    }
+
+    Some(level)
+}
+
+// 4x4 ordered dither thresholds
+const BAYER_4X4: [f32; 16] = [0.0, 8.0, 2.0, 10.0, 12.0, 4.0, 14.0, 6.0, 3.0, 11.0, 1.0, 9.0, 15.0, 7.0, 13.0, 5.0];
+
+// The fading in and fading out level cover complementary pixels, so they never overlap
+#[inline(always)]
+fn dither_visible(x: i32, y: i32, fade: f32, fading_out: bool) -> bool {
+    let threshold = (BAYER_4X4[((y & 3) * 4 + (x & 3)) as usize] + 0.5) / 16.0;
+    (threshold < fade) != fading_out
+}
+
+// Inclusive pixel bounds (min_x, min_y, max_x, max_y) of the camera viewport, nothing is drawn outside
+pub fn viewport_pixels(render_target: &RenderTarget, cam: &Camera) -> [i32; 4] {
+    let (x, y, w, h) = cam.viewport_rect(render_target.width(), render_target.height());
+    [
+        i::max(x.round() as i32, 0),
+        i::max(y.round() as i32, 0),
+        i::min((x + w).round() as i32, render_target.width() as i32) - 1,
+        i::min((y + h).round() as i32, render_target.height() as i32) - 1,
+    ]
+}
+
+// `dither` is (fade, fading_out) while crossfading between LOD levels. Shaders that render as
+// wireframe only draw the edges, `wireframe.overlay` draws them over the filled triangles.
+// Only the part belonging to `pass` is drawn.
+fn draw_triangles(render_target: &mut RenderTarget, cam: &Camera, shader: &Shader, wireframe: &Wireframe, points: &[RasterizerPoint], dither: Option<(f32, bool)>, pass: DrawPass) {
+    let fading_out = matches!(dither, Some((_, true)));
+    let edges_only = shader.render_as_wireframe();
+    if edges_only && fading_out {
+        return;
+    }
+
+    if !edges_only && (pass == DrawPass::SeeThrough) == shader.is_transparent() {
+        fill_triangles(render_target, cam, shader, points, dither, pass);
+    }
+    if pass == DrawPass::SeeThrough && (edges_only || (wireframe.overlay && !fading_out)) {
+        // Edges over their own triangles can't write depth, the next triangle would hide them
+        let depth = if edges_only { LineDepth::TestAndWrite } else { LineDepth::Test };
+        let style = LineStyle::new(wireframe.color, wireframe.width, depth);
+        draw_edges(render_target, cam, &style, points);
+    }
+}
 
-            let a = r0.screen_pos;
-            let b = r1.screen_pos;
-            let c = r2.screen_pos;
-
-            let min_x = f::min(a.x, f::min(b.x, c.x));
-            let min_y = f::min(a.y, f::min(b.y, c.y));
-            let max_x = f::max(a.x, f::max(b.x, c.x));
-            let max_y = f::max(a.y, f::max(b.y, c.y));
-
-            let block_start_x = i::clamp(f::floor_to_int(min_x), 0, (render_target.width() - 1) as i32);
-            let block_start_y = i::clamp(f::floor_to_int(min_y), 0, (render_target.height() - 1) as i32);
-            let block_end_x = i::clamp(f::ceil_to_int(max_x), 0, (render_target.width() - 1) as i32);
-            let block_end_y = i::clamp(f::ceil_to_int(max_y), 0, (render_target.height() - 1) as i32);
-
-            let inv_depths = Float3::new(1.0 / r0.depth, 1.0 / r1.depth, 1.0 / r2.depth);
-            let tx = r0.tex_coords * inv_depths.x;
-            let ty = r1.tex_coords * inv_depths.y;
-            let tz = r2.tex_coords * inv_depths.z;
-            let nx = r0.normals * inv_depths.x;
-            let ny = r1.normals * inv_depths.y;
-            let nz = r2.normals * inv_depths.z;
-
-            for y in block_start_y..=block_end_y {
-                for x in block_start_x..=block_end_x {
-                    let p = Float2::new(x as f32 + 0.5, y as f32 + 0.5);
+fn draw_edges(render_target: &mut RenderTarget, cam: &Camera, style: &LineStyle, points: &[RasterizerPoint]) {
+    let scissor = viewport_pixels(render_target, cam);
+    let perspective = cam.is_perspective();
+
+    for triangle in points.chunks_exact(3) {
+        let [p0, p1, p2] = [&triangle[0], &triangle[1], &triangle[2]].map(|r| Float3::new(r.screen_pos.x, r.screen_pos.y, r.depth));
+        draw_line(render_target, scissor, p0, p1, style, perspective);
+        draw_line(render_target, scissor, p1, p2, style, perspective);
+        draw_line(render_target, scissor, p2, p0, style, perspective);
+    }
+}
+
+fn fill_triangles(render_target: &mut RenderTarget, cam: &Camera, shader: &Shader, points: &[RasterizerPoint], dither: Option<(f32, bool)>, pass: DrawPass) {
+    let scissor = viewport_pixels(render_target, cam);
+    // Orthographic projections interpolate linearly in screen space
+    let perspective = cam.is_perspective();
+    let offsets = render_target.sample_offsets();
+    let occluded = pass == DrawPass::Opaque && render_target.g_buffer().is_some();
+
+    for i in (0..points.len()).step_by(3) {
+        let r0 = &points[i];
+        let r1 = &points[i + 1];
+        let r2 = &points[i + 2];
+
+        let a = r0.screen_pos;
+        let b = r1.screen_pos;
+        let c = r2.screen_pos;
+
+        let min_x = f::min(a.x, f::min(b.x, c.x));
+        let min_y = f::min(a.y, f::min(b.y, c.y));
+        let max_x = f::max(a.x, f::max(b.x, c.x));
+        let max_y = f::max(a.y, f::max(b.y, c.y));
+
+        let block_start_x = i::clamp(f::floor_to_int(min_x), scissor[0], scissor[2]);
+        let block_start_y = i::clamp(f::floor_to_int(min_y), scissor[1], scissor[3]);
+        let block_end_x = i::clamp(f::ceil_to_int(max_x), scissor[0], scissor[2]);
+        let block_end_y = i::clamp(f::ceil_to_int(max_y), scissor[1], scissor[3]);
+
+        let inv_depths = if perspective { Float3::new(1.0 / r0.depth, 1.0 / r1.depth, 1.0 / r2.depth) } else { Float3::ONE };
+        let tx = r0.tex_coords * inv_depths.x;
+        let ty = r1.tex_coords * inv_depths.y;
+        let tz = r2.tex_coords * inv_depths.z;
+        let nx = r0.normals * inv_depths.x;
+        let ny = r1.normals * inv_depths.y;
+        let nz = r2.normals * inv_depths.z;
+
+        for y in block_start_y..=block_end_y {
+            for x in block_start_x..=block_end_x {
+                if dither.is_some_and(|(fade, fading_out)| !dither_visible(x, y, fade, fading_out)) {
+                    continue;
+                }
+
+                // Coverage and depth per sample, shaded once at the first covered sample so the
+                // attributes are never extrapolated outside the triangle
+                let mut covered = [false; MAX_SAMPLES];
+                let mut depths = [0.0; MAX_SAMPLES];
+                let mut shade_at = None;
+
+                for (sample, offset) in offsets.iter().enumerate() {
+                    let p = Float2::new(x as f32 + offset.x, y as f32 + offset.y);
                     let mut weight_a = 0.0;
                     let mut weight_b = 0.0;
                     let mut weight_c = 0.0;
 
-                    if f::point_in_triangle(a, b, c, p, &mut weight_a, &mut weight_b, &mut weight_c) {
+                    if !f::point_in_triangle(a, b, c, p, &mut weight_a, &mut weight_b, &mut weight_c) {
+                        continue;
+                    }
+
+                    let (depth, correction) = if perspective {
                         let depth = 1.0 / (inv_depths.x * weight_a + inv_depths.y * weight_b + inv_depths.z * weight_c);
+                        (depth, depth)
+                    } else {
+                        (r0.depth * weight_a + r1.depth * weight_b + r2.depth * weight_c, 1.0)
+                    };
 
-                        if depth >= render_target.get_pixel_depth(x as u32, y as u32) {
-                            continue;
-                        }
+                    if depth >= render_target.get_sample_depth(x as u32, y as u32, sample) {
+                        continue;
+                    }
+
+                    covered[sample] = true;
+                    depths[sample] = depth;
+                    shade_at.get_or_insert((Float3::new(weight_a, weight_b, weight_c), depth, correction));
+                }
 
-                        let uv = (tx * weight_a + ty * weight_b + tz * weight_c) * depth;
-                        let normal = (nx * weight_a + ny * weight_b + nz * weight_c) * depth;
+                let Some((weights, depth, correction)) = shade_at else { continue };
 
-                        let color = model.shader.pixel_color(p, uv, normal, depth);
+                let uv = (tx * weights.x + ty * weights.y + tz * weights.z) * correction;
+                let normal = (nx * weights.x + ny * weights.y + nz * weights.z) * correction;
 
-                        render_target.set_pixel(x as u32, y as u32, color, depth);
+                if pass == DrawPass::GBuffer {
+                    render_target.write_g_buffer(x as u32, y as u32, depth, normal.normalize());
+                    continue;
+                }
+
+                let p = Float2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let color = if occluded {
+                    let ambient_visibility = render_target.ambient_visibility(x as u32, y as u32, depth);
+                    shader.pixel_color_occluded(p, uv, normal, depth, ambient_visibility)
+                } else {
+                    shader.pixel_color(p, uv, normal, depth)
+                };
+
+                for sample in 0..offsets.len() {
+                    if covered[sample] {
+                        render_target.set_sample(x as u32, y as u32, sample, color, depths[sample]);
                     }
                 }
             }
@@ -70,20 +279,19 @@ pub fn render(render_target: &mut RenderTarget, models: &mut Vec<Model>, cam: &C
     }
 }
 
-pub fn process_model(model: &mut Model, render_target: &RenderTarget, cam: &Camera) {
+pub fn process_model(transform: &Transform, renderer: &mut MeshRenderer, render_target: &RenderTarget, cam: &Camera) {
     let mut view_points: [Float3; 3] = [Float3::ZERO, Float3::ZERO, Float3::ZERO];
-    model.rasterizer_points.clear();
+    renderer.rasterizer_points.clear();
 
-    for i in (0..model.mesh.indices.len()).step_by(3) {
-        let idx0 = model.mesh.indices[i + 0] as usize;
-        let idx1 = model.mesh.indices[i + 1] as usize;
-        let idx2 = model.mesh.indices[i + 2] as usize;
+    for i in (0..renderer.mesh.indices.len()).step_by(3) {
+        let idx0 = renderer.mesh.indices[i] as usize;
+        let idx1 = renderer.mesh.indices[i + 1] as usize;
+        let idx2 = renderer.mesh.indices[i + 2] as usize;
 
-        view_points[0] = vertex_to_view(cam, model.mesh.vertices[idx0], &model.transform);
-        view_points[1] = vertex_to_view(cam, model.mesh.vertices[idx1], &model.transform);
-        view_points[2] = vertex_to_view(cam, model.mesh.vertices[idx2], &model.transform);
+        view_points[0] = vertex_to_view(cam, renderer.mesh.vertices[idx0], transform);
+        view_points[1] = vertex_to_view(cam, renderer.mesh.vertices[idx1], transform);
+        view_points[2] = vertex_to_view(cam, renderer.mesh.vertices[idx2], transform);
 
-        const NEAR_CLIP_DST: f32 = 0.01;
         let clip0 = view_points[0].z <= NEAR_CLIP_DST;
         let clip1 = view_points[1].z <= NEAR_CLIP_DST;
         let clip2 = view_points[2].z <= NEAR_CLIP_DST;
@@ -91,9 +299,9 @@ pub fn process_model(model: &mut Model, render_target: &RenderTarget, cam: &Came
 
         match clip_count {
             0 => {
-                add_rasterizer_point(model, render_target, cam, view_points[0], idx0);
-                add_rasterizer_point(model, render_target, cam, view_points[1], idx1);
-                add_rasterizer_point(model, render_target, cam, view_points[2], idx2);
+                add_rasterizer_point(transform, renderer, render_target, cam, view_points[0], idx0);
+                add_rasterizer_point(transform, renderer, render_target, cam, view_points[1], idx1);
+                add_rasterizer_point(transform, renderer, render_target, cam, view_points[2], idx2);
             }
             1 => {
                 let index_clip = if clip0 { 0 } else if clip1 { 1 } else { 2 };
@@ -110,17 +318,17 @@ pub fn process_model(model: &mut Model, render_target: &RenderTarget, cam: &Came
                 let clip_point_a = f::lerp_float3(point_clipped, point_a, frac_a);
                 let clip_point_b = f::lerp_float3(point_clipped, point_b, frac_b);
 
-                let idx_clip = model.mesh.indices[i + index_clip] as usize;
-                let idx_next = model.mesh.indices[i + index_next] as usize;
-                let idx_prev = model.mesh.indices[i + index_prev] as usize;
+                let idx_clip = renderer.mesh.indices[i + index_clip] as usize;
+                let idx_next = renderer.mesh.indices[i + index_next] as usize;
+                let idx_prev = renderer.mesh.indices[i + index_prev] as usize;
 
-                add_rasterizer_point_lerp(model, render_target, cam, clip_point_b, idx_clip, idx_prev, frac_b);
-                add_rasterizer_point_lerp(model, render_target, cam, clip_point_a, idx_clip, idx_next, frac_a);
-                add_rasterizer_point(model, render_target, cam, point_b, idx_prev);
+                add_rasterizer_point_lerp(transform, renderer, render_target, cam, clip_point_b, idx_clip, idx_prev, frac_b);
+                add_rasterizer_point_lerp(transform, renderer, render_target, cam, clip_point_a, idx_clip, idx_next, frac_a);
+                add_rasterizer_point(transform, renderer, render_target, cam, point_b, idx_prev);
 
-                add_rasterizer_point_lerp(model, render_target, cam, clip_point_a, idx_clip, idx_next, frac_a);
-                add_rasterizer_point(model, render_target, cam, point_a, idx_next);
-                add_rasterizer_point(model, render_target, cam, point_b, idx_prev);
+                add_rasterizer_point_lerp(transform, renderer, render_target, cam, clip_point_a, idx_clip, idx_next, frac_a);
+                add_rasterizer_point(transform, renderer, render_target, cam, point_a, idx_next);
+                add_rasterizer_point(transform, renderer, render_target, cam, point_b, idx_prev);
             }
             2 => {
                 let index_non_clip = if !clip0 { 0 } else if !clip1 { 1 } else { 2 };
@@ -137,43 +345,44 @@ pub fn process_model(model: &mut Model, render_target: &RenderTarget, cam: &Came
                 let clip_point_a = f::lerp_float3(point_nc, point_a, frac_a);
                 let clip_point_b = f::lerp_float3(point_nc, point_b, frac_b);
 
-                let idx_nc = model.mesh.indices[i + index_non_clip] as usize;
-                let idx_next = model.mesh.indices[i + index_next] as usize;
-                let idx_prev = model.mesh.indices[i + index_prev] as usize;
+                let idx_nc = renderer.mesh.indices[i + index_non_clip] as usize;
+                let idx_next = renderer.mesh.indices[i + index_next] as usize;
+                let idx_prev = renderer.mesh.indices[i + index_prev] as usize;
 
-                add_rasterizer_point_lerp(model, render_target, cam, clip_point_b, idx_nc, idx_prev, frac_b);
-                add_rasterizer_point(model, render_target, cam, point_nc, idx_nc);
-                add_rasterizer_point_lerp(model, render_target, cam, clip_point_a, idx_nc, idx_next, frac_a);
+                add_rasterizer_point_lerp(transform, renderer, render_target, cam, clip_point_b, idx_nc, idx_prev, frac_b);
+                add_rasterizer_point(transform, renderer, render_target, cam, point_nc, idx_nc);
+                add_rasterizer_point_lerp(transform, renderer, render_target, cam, clip_point_a, idx_nc, idx_next, frac_a);
             }
             _ => {}
         }
     }
 }
 
-fn add_rasterizer_point(model: &mut Model, render_target: &RenderTarget, cam: &Camera, view: Float3, vertex_idx: usize) {
-    let normal_world = normal_to_normalview(&model.transform, model.mesh.normals[vertex_idx]);
+fn add_rasterizer_point(transform: &Transform, renderer: &mut MeshRenderer, render_target: &RenderTarget, cam: &Camera, view: Float3, vertex_idx: usize) {
+    let normal_world = normal_to_normalview(transform, renderer.mesh.normals[vertex_idx]);
     let normal_view = normalview_to_screen(cam, normal_world);
 
-    model.rasterizer_points.push(RasterizerPoint::new(
+    renderer.rasterizer_points.push(RasterizerPoint::new(
         view.z,
         view_to_screen(render_target, cam, view),
-        model.mesh.uvs[vertex_idx],
+        renderer.mesh.uvs[vertex_idx],
         normal_view,
     ));
 }
 
-fn add_rasterizer_point_lerp(model: &mut Model, render_target: &RenderTarget, cam: &Camera, view: Float3, vertex_idx_a: usize, vertex_idx_b: usize, t: f32) {
-    let normal_a_world = normal_to_normalview(&model.transform, model.mesh.normals[vertex_idx_a]);
-    let normal_b_world = normal_to_normalview(&model.transform, model.mesh.normals[vertex_idx_b]);
+#[allow(clippy::too_many_arguments)]
+fn add_rasterizer_point_lerp(transform: &Transform, renderer: &mut MeshRenderer, render_target: &RenderTarget, cam: &Camera, view: Float3, vertex_idx_a: usize, vertex_idx_b: usize, t: f32) {
+    let normal_a_world = normal_to_normalview(transform, renderer.mesh.normals[vertex_idx_a]);
+    let normal_b_world = normal_to_normalview(transform, renderer.mesh.normals[vertex_idx_b]);
 
     let normal_world = f::lerp_float3(normal_a_world, normal_b_world, t).normalize();
 
     let normal_view = normalview_to_screen(cam, normal_world);
 
-    model.rasterizer_points.push(RasterizerPoint::new(
+    renderer.rasterizer_points.push(RasterizerPoint::new(
         view.z,
         view_to_screen(render_target, cam, view),
-        f::lerp_float2(model.mesh.uvs[vertex_idx_a], model.mesh.uvs[vertex_idx_b], t),
+        f::lerp_float2(renderer.mesh.uvs[vertex_idx_a], renderer.mesh.uvs[vertex_idx_b], t),
         normal_view,
     ));
 }
@@ -195,70 +404,253 @@ fn vertex_to_view(cam: &Camera, vertex: Float3, transform: &Transform) -> Float3
 
 #[inline(always)]
 fn view_to_screen(render_target: &RenderTarget, cam: &Camera, view: Float3) -> Float2 {
-    let screen_height_world = (cam.fov_degrees.to_radians() / 2.0).tan() * 2.0;
-    let pixels_per_world_unit = render_target.height() as f32 / screen_height_world / view.z;
-
-    let pixel_offset = Float2::new(view.x, view.y) * pixels_per_world_unit;
-    Float2::new(render_target.width() as f32, render_target.height() as f32) / 2.0 + pixel_offset
+    cam.view_to_screen(view, render_target.width(), render_target.height())
 }
 
-fn draw_line(render_target: &mut RenderTarget, start: &RasterizerPoint, end: &RasterizerPoint, color: Float4) {
-    let x0 = start.screen_pos.x.round() as i32;
-    let y0 = start.screen_pos.y.round() as i32;
-    let x1 = end.screen_pos.x.round() as i32;
-    let y1 = end.screen_pos.y.round() as i32;
+// How a line uses the depth buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineDepth {
+    TestAndWrite, // wireframes, hide what's behind them
+    Test,         // overlays, hidden behind geometry without hiding anything themselves
+    Ignore,       // always on top
+}
 
-    let z0 = start.depth;
-    let z1 = end.depth;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+    pub color: Float4,
+    pub width: f32, // pixels
+    pub depth: LineDepth,
+}
 
-    let dx = (x1 - x0).abs();
-    let dy = (y1 - y0).abs();
+impl LineStyle {
+    pub fn new(color: Float4, width: f32, depth: LineDepth) -> Self {
+        Self { color, width, depth }
+    }
+}
 
-    let steps = if dx > dy { dx } else { dy };
-    let steps = if steps == 0 { 1 } else { steps } as i32;
+// Depth tested lines pass this much (relative) behind the depth buffer,
+// so edges lying on a surface aren't swallowed by it
+const LINE_DEPTH_BIAS: f32 = 0.002;
+
+// Anti-aliased line with round caps. `start` and `end` are render target positions with the view depth
+// in z, `scissor` inclusive pixel bounds. Every pixel within half the width plus one of the segment is
+// covered by how much of it lies inside the line, depth is interpolated perspective-correctly unless
+// `perspective` is false (orthographic cameras).
+pub fn draw_line(render_target: &mut RenderTarget, scissor: [i32; 4], start: Float3, end: Float3, style: &LineStyle, perspective: bool) {
+    if ![start.x, start.y, start.z, end.x, end.y, end.z].iter().all(|v| v.is_finite()) || style.width <= 0.0 {
+        return;
+    }
 
-    let sx = if x0 < x1 { 1 } else { -1 };
-    let sy = if y0 < y1 { 1 } else { -1 };
+    let half_width = style.width * 0.5;
+    let reach = half_width + 1.0;
+    let Some((start, end)) = clip_line(start, end, scissor, reach, perspective) else { return };
 
-    let mut err = dx - dy;
-    let mut x = x0;
-    let mut y = y0;
+    let a = Float2::new(start.x, start.y);
+    let d = Float2::new(end.x - start.x, end.y - start.y);
+    let length_sq = d.x * d.x + d.y * d.y;
+    let depth_at = |t: f32| if perspective { 1.0 / ((1.0 - t) / start.z + t / end.z) } else { start.z + (end.z - start.z) * t };
 
-    let max_iters = (dx + dy + 1000) as usize;
-    let mut iter_count: usize = 0;
-    let mut step: i32 = 0;
+    let mut plot = |x: i32, y: i32| {
+        if x < scissor[0] || y < scissor[1] || x > scissor[2] || y > scissor[3] {
+            return;
+        }
 
-    loop {
-        iter_count += 1;
-        if iter_count > max_iters {
-            break;
+        // Distance from the pixel center to the segment
+        let p = Float2::new(x as f32 + 0.5 - a.x, y as f32 + 0.5 - a.y);
+        let t = if length_sq > 0.0 { ((p.x * d.x + p.y * d.y) / length_sq).clamp(0.0, 1.0) } else { 0.0 };
+        let (ox, oy) = (p.x - d.x * t, p.y - d.y * t);
+        let coverage = (half_width + 0.5 - (ox * ox + oy * oy).sqrt()).clamp(0.0, 1.0);
+        if coverage <= 0.0 {
+            return;
         }
 
-        if x >= 0 && y >= 0 && (x as u32) < render_target.width() && (y as u32) < render_target.height() {
-            let t = (step as f32) / (steps as f32);
-            let depth = z0 * (1.0 - t) + z1 * t;
+        // Depth is tested per sample, so lines behind a silhouette still show through its uncovered samples
+        let (ux, uy) = (x as u32, y as u32);
+        let depth = depth_at(t);
+        let color = Float4 { w: style.color.w * coverage, ..style.color };
+        let write = style.depth == LineDepth::TestAndWrite && coverage >= 0.5;
+        for sample in 0..render_target.sample_offsets().len() {
+            let sample_depth = render_target.get_sample_depth(ux, uy, sample);
+            if style.depth != LineDepth::Ignore && depth * (1.0 - LINE_DEPTH_BIAS) >= sample_depth {
+                continue;
+            }
+            render_target.set_sample(ux, uy, sample, color, if write { depth } else { sample_depth });
+        }
+    };
+
+    // Walk the major axis, covering every pixel the line can reach across it
+    let x_major = d.x.abs() >= d.y.abs();
+    let (major_start, major_end) = if x_major { (start.x.min(end.x), start.x.max(end.x)) } else { (start.y.min(end.y), start.y.max(end.y)) };
+    let (major_d, minor_d) = if x_major { (d.x, d.y) } else { (d.y, d.x) };
+    let slope = if major_d != 0.0 { minor_d / major_d } else { 0.0 };
+    let minor_reach = reach * (1.0 + slope * slope).sqrt();
+    let (major_origin, minor_origin) = if x_major { (start.x, start.y) } else { (start.y, start.x) };
+
+    for major in f::floor_to_int(major_start - reach)..=f::ceil_to_int(major_end + reach) {
+        let center = major as f32 + 0.5;
+        let along = (center.clamp(major_start, major_end) - major_origin) * slope + minor_origin;
+        for minor in f::floor_to_int(along - minor_reach)..=f::ceil_to_int(along + minor_reach) {
+            if x_major { plot(major, minor) } else { plot(minor, major) }
+        }
+    }
+}
 
-            let ux = x as u32;
-            let uy = y as u32;
-            if depth < render_target.get_pixel_depth(ux, uy) {
-                render_target.set_pixel(ux, uy, color, depth);
+// Cuts the segment to the scissor rectangle grown by `margin`, keeping the depth of the new endpoints
+fn clip_line(start: Float3, end: Float3, scissor: [i32; 4], margin: f32, perspective: bool) -> Option<(Float3, Float3)> {
+    let (min_x, min_y) = (scissor[0] as f32 - margin, scissor[1] as f32 - margin);
+    let (max_x, max_y) = ((scissor[2] + 1) as f32 + margin, (scissor[3] + 1) as f32 + margin);
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+
+    // Liang-Barsky
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for (p, q) in [(-dx, start.x - min_x), (dx, max_x - start.x), (-dy, start.y - min_y), (dy, max_y - start.y)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
             }
+            continue;
+        }
+        let r = q / p;
+        if p < 0.0 {
+            t0 = t0.max(r);
+        } else {
+            t1 = t1.min(r);
         }
+    }
+    if t0 > t1 {
+        return None;
+    }
+
+    let point = |t: f32| {
+        let depth = if perspective { 1.0 / ((1.0 - t) / start.z + t / end.z) } else { start.z + (end.z - start.z) * t };
+        Float3::new(start.x + dx * t, start.y + dy * t, depth)
+    };
+    Some((point(t0), point(t1)))
+}
 
-        if x == x1 && y == y1 {
-            break;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shaders::{texture_shader::TextureShader, transparent_texture_shader::TransparentTextureShader}, types::{bounds::BoundingSphere, handle::Handle, mesh_builder, texture::Texture}};
+
+    const SCISSOR: [i32; 4] = [0, 0, 31, 31];
+
+    fn line(render_target: &mut RenderTarget, start: Float3, end: Float3, width: f32, depth: LineDepth) {
+        draw_line(render_target, SCISSOR, start, end, &LineStyle::new(Float4::ONE, width, depth), true);
+    }
+
+    #[test]
+    fn lines_are_antialiased_and_wide() {
+        let mut render_target = RenderTarget::new(32, 32);
+        line(&mut render_target, Float3::new(4.0, 16.0, 1.0), Float3::new(28.0, 16.0, 1.0), 1.0, LineDepth::Ignore);
+        // The line runs along pixel edges, so it half covers the rows on both sides
+        assert!((render_target.get_pixel_color(16, 16).w - 0.5).abs() < 1e-5);
+        assert!((render_target.get_pixel_color(16, 15).w - 0.5).abs() < 1e-5);
+        assert_eq!(render_target.get_pixel_color(16, 17).w, 0.0);
+
+        let mut render_target = RenderTarget::new(32, 32);
+        line(&mut render_target, Float3::new(4.0, 16.5, 1.0), Float3::new(28.0, 16.5, 1.0), 3.0, LineDepth::Ignore);
+        for y in 15..=17 {
+            assert_eq!(render_target.get_pixel_color(16, y).w, 1.0);
         }
+        assert_eq!(render_target.get_pixel_color(16, 18).w, 0.0);
+    }
 
-        let e2 = 2 * err;
-        if e2 > -dy {
-            err -= dy;
-            x += sx;
+    #[test]
+    fn depth_is_perspective_correct_and_biased() {
+        let mut render_target = RenderTarget::new(32, 32);
+        line(&mut render_target, Float3::new(0.0, 16.5, 1.0), Float3::new(32.0, 16.5, 3.0), 1.0, LineDepth::TestAndWrite);
+        // Halfway across the screen 1/z is halfway between 1 and 1/3
+        let depth = render_target.get_pixel_depth(16, 16);
+        assert!((depth - 1.5).abs() < 0.05, "{depth}");
+
+        // A line on top of a surface at the same depth still shows, one behind it doesn't
+        let mut render_target = RenderTarget::new(32, 32);
+        for x in 0..32 {
+            render_target.set_pixel(x, 10, Float4::new(0.0, 0.0, 0.0, 1.0), 2.0);
+            render_target.set_pixel(x, 20, Float4::new(0.0, 0.0, 0.0, 1.0), 2.0);
         }
-        if e2 < dx {
-            err += dx;
-            y += sy;
+        line(&mut render_target, Float3::new(0.0, 10.5, 2.0), Float3::new(32.0, 10.5, 2.0), 1.0, LineDepth::Test);
+        line(&mut render_target, Float3::new(0.0, 20.5, 2.1), Float3::new(32.0, 20.5, 2.1), 1.0, LineDepth::Test);
+        assert_eq!(render_target.get_pixel_color(16, 10), Float4::ONE);
+        assert_eq!(render_target.get_pixel_color(16, 20).x, 0.0);
+        assert_eq!(render_target.get_pixel_depth(16, 10), 2.0);
+    }
+
+    #[test]
+    fn far_off_screen_lines_are_clipped() {
+        let mut render_target = RenderTarget::new(32, 32);
+        line(&mut render_target, Float3::new(-1e9, 8.5, 1.0), Float3::new(1e9, 8.5, 1.0), 1.0, LineDepth::Ignore);
+        line(&mut render_target, Float3::new(-50.0, -50.0, 1.0), Float3::new(-10.0, 100.0, 1.0), 1.0, LineDepth::Ignore);
+        line(&mut render_target, Float3::new(f32::NAN, 0.0, 1.0), Float3::new(10.0, 10.0, 1.0), 1.0, LineDepth::Ignore);
+        let lit = render_target.color_buffer().iter().filter(|c| c.w > 0.0).count();
+        assert_eq!(lit, 32);
+    }
+
+    #[test]
+    fn only_opaque_fills_occlude() {
+        // A cube right in front of the camera, tested against a sphere far behind it
+        let occluded = |shader: Shader| {
+            let mut world = World::new();
+            let mut transform = Transform::default();
+            transform.set_position(Float3::new(0.0, 0.0, 3.0));
+            world.spawn().with(transform).with(MeshRenderer::new(Handle::new(mesh_builder::cube(2.0)), shader));
+
+            let mut render_target = RenderTarget::new(32, 32);
+            render_target.set_occlusion_culling(true);
+            render(&mut render_target, &world, &Camera::new(90.0));
+            render_target.hi_z().unwrap().is_occluded(&BoundingSphere::new(Float3::new(0.0, 0.0, 10.0), 0.2))
+        };
+        let texture = || Handle::new(Texture::from_texels(1, 1, vec![Float4::ONE]));
+
+        assert!(occluded(Shader::TextureShader(TextureShader::new(texture(), false))));
+        assert!(!occluded(Shader::TextureShader(TextureShader::new(texture(), true))));
+        assert!(!occluded(Shader::TransparentTextureShader(TransparentTextureShader::new(texture(), 0.5))));
+    }
+
+    // Triangle covering everything right of x = 10.5 in a 32x32 target
+    fn half_covered(samples: u32, color: Float4) -> RenderTarget {
+        let mut render_target = RenderTarget::new(32, 32);
+        render_target.set_samples(samples);
+        let texture = Handle::new(Texture::from_texels(1, 1, vec![color]));
+        let shader = Shader::TextureShader(TextureShader::new(texture, false));
+        let point = |x, y| RasterizerPoint::new(1.0, Float2::new(x, y), Float2::ZERO, Float3::ZERO);
+        let points = [point(10.5, 0.0), point(10.5, 64.0), point(64.0, 0.0)];
+        fill_triangles(&mut render_target, &Camera::new(90.0), &shader, &points, None, DrawPass::Opaque);
+        render_target
+    }
+
+    #[test]
+    fn msaa_resolves_partial_coverage() {
+        for (samples, expected) in [(1, 1.0), (2, 0.5), (4, 0.5), (8, 0.5)] {
+            let mut render_target = half_covered(samples, Float4::ONE);
+            render_target.resolve();
+            // Pixels on the edge are covered by half their samples, the rest fully or not at all
+            assert!((render_target.get_pixel_color(10, 10).w - expected).abs() < 1e-5, "{samples}x");
+            assert!((render_target.color_buffer()[10 * 32 + 10].w - expected).abs() < 1e-5, "{samples}x");
+            assert_eq!(render_target.get_pixel_color(20, 10).w, 1.0, "{samples}x");
+            assert_eq!(render_target.get_pixel_color(5, 10).w, 0.0, "{samples}x");
+            // A pixel's depth is its farthest sample
+            let depth = render_target.get_pixel_depth(10, 10);
+            assert!(if samples == 1 { (depth - 1.0).abs() < 1e-5 } else { depth == f32::INFINITY }, "{samples}x");
+            assert!((render_target.depth_buffer()[10 * 32 + 20] - 1.0).abs() < 1e-5, "{samples}x");
         }
+    }
 
-        step = step.saturating_add(1);
+    #[test]
+    fn msaa_blends_per_sample() {
+        let mut render_target = half_covered(4, Float4::ONE);
+        render_target.set_pixel(10, 10, Float4::new(0.0, 0.0, 0.0, 0.5), 0.5);
+        render_target.resolve();
+        // Half the samples were white, the other half empty, all get half of the black on top
+        let color = render_target.get_pixel_color(10, 10);
+        assert!((color.x - 0.25).abs() < 1e-5 && (color.w - 0.75).abs() < 1e-5, "{color:?}");
+        assert_eq!(render_target.get_pixel_depth(10, 10), 0.5);
+
+        // Once resolved, drawing goes to the pixels and isn't undone by another resolve
+        render_target.set_pixel(20, 20, Float4::new(1.0, 0.0, 0.0, 1.0), 0.5);
+        render_target.resolve();
+        assert_eq!(render_target.color_buffer()[20 * 32 + 20], Float4::new(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(render_target.sample_offsets().len(), 1);
     }
 }
\ No newline at end of file
@@ -1,9 +1,26 @@
 #![allow(dead_code)]
 
 use std::f32;
+use std::hash::{DefaultHasher, Hasher};
+
+use rayon::prelude::*;
 
 use crate::math::mathi;
-use crate::math::numerics::float4::Float4;
+use crate::math::numerics::{float2::Float2, float3::Float3, float4::Float4};
+use crate::rasterizer::{camera::Camera, culling::HiZBuffer, g_buffer::GBuffer, ssao::Ssao};
+
+// Sample positions within a pixel for each MSAA level, the standard rotated grid patterns
+const SAMPLES_1X: [Float2; 1] = [Float2::new(0.5, 0.5)];
+const SAMPLES_2X: [Float2; 2] = [sample(4, 4), sample(-4, -4)];
+const SAMPLES_4X: [Float2; 4] = [sample(-2, -6), sample(6, -2), sample(-6, 2), sample(2, 6)];
+const SAMPLES_8X: [Float2; 8] = [sample(1, -3), sample(-1, 3), sample(5, 1), sample(-3, -5), sample(-5, 5), sample(-7, -1), sample(3, 7), sample(7, -7)];
+
+// Offset from the pixel center in 1/16 pixels
+const fn sample(x: i32, y: i32) -> Float2 {
+    Float2::new(0.5 + x as f32 / 16.0, 0.5 + y as f32 / 16.0)
+}
+
+pub const MAX_SAMPLES: usize = 8;
 
 pub struct RenderTarget {
     color_buffer: Vec<Float4>,
@@ -11,6 +28,34 @@ pub struct RenderTarget {
 
     width: u32,
     height: u32,
+
+    // With MSAA everything is drawn into the samples, `resolve` averages them into the buffers above.
+    // After that drawing goes to the pixels until the next clear, for post-processing and UI.
+    samples: u32,
+    sample_colors: Vec<Float4>,
+    sample_depths: Vec<f32>,
+    resolved: bool,
+
+    // Depth pyramid of the last rendered frame, kept across clears for occlusion culling
+    occlusion_culling: bool,
+    hi_z: Option<HiZBuffer>,
+
+    // Ambient occlusion of the models, computed from the G-buffer before they are shaded
+    ssao: Option<Ssao>,
+    g_buffer: Option<GBuffer>,
+}
+
+#[inline(always)]
+fn blend(dst: Float4, src: Float4) -> Float4 {
+    let alpha = src.w.clamp(0.0, 1.0);
+    let inv_alpha = 1.0 - alpha;
+
+    Float4 {
+        x: src.x * alpha + dst.x * inv_alpha,
+        y: src.y * alpha + dst.y * inv_alpha,
+        z: src.z * alpha + dst.z * inv_alpha,
+        w: alpha + dst.w * inv_alpha,
+    }
 }
 
 impl RenderTarget {
@@ -21,54 +66,305 @@ impl RenderTarget {
 
             width,
             height,
+
+            samples: 1,
+            sample_colors: Vec::new(),
+            sample_depths: Vec::new(),
+            resolved: false,
+
+            occlusion_culling: false,
+            hi_z: None,
+
+            ssao: None,
+            g_buffer: None,
+        }
+    }
+
+    // 1 (off), 2, 4 or 8 samples per pixel. The samples are cleared.
+    pub fn set_samples(&mut self, samples: u32) {
+        assert!(matches!(samples, 1 | 2 | 4 | 8), "Unsupported MSAA sample count: {samples}");
+        if samples == self.samples {
+            return;
+        }
+
+        self.samples = samples;
+        self.resolved = false;
+        let count = if samples > 1 { (self.width * self.height * samples) as usize } else { 0 };
+        self.sample_colors = vec![Float4::ZERO; count];
+        self.sample_depths = vec![f32::INFINITY; count];
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    // True while drawing goes into the samples
+    pub fn msaa(&self) -> bool {
+        self.samples > 1 && !self.resolved
+    }
+
+    // Sample positions relative to the pixel's bottom left corner, only the center once resolved
+    pub fn sample_offsets(&self) -> &'static [Float2] {
+        match self.samples {
+            _ if !self.msaa() => &SAMPLES_1X,
+            2 => &SAMPLES_2X,
+            4 => &SAMPLES_4X,
+            8 => &SAMPLES_8X,
+            _ => &SAMPLES_1X,
         }
     }
 
     pub fn clear(&mut self, color: Float4) -> &mut Self {
         self.color_buffer.fill(color);
         self.depth_buffer.fill(f32::INFINITY);
+        self.sample_colors.fill(color);
+        self.sample_depths.fill(f32::INFINITY);
+        self.resolved = false;
+        if let Some(g_buffer) = &mut self.g_buffer {
+            g_buffer.clear();
+        }
         self
     }
 
+    #[inline(always)]
+    fn pixel_index(&self, x: u32, y: u32) -> usize {
+        mathi::xy_to_index(x, y, self.width, self.height) as usize
+    }
+
+    // Index of the first sample of a pixel
+    #[inline(always)]
+    fn sample_index(&self, x: u32, y: u32) -> usize {
+        self.pixel_index(x, y) * self.samples as usize
+    }
+
+    // With MSAA the pixel is blended into every sample
     pub fn set_pixel(&mut self, x: u32, y: u32, color: Float4, depth: f32) {
         if color.w < f32::EPSILON {
             return;
         }
 
-        let index = mathi::xy_to_index(x, y, self.width, self.height) as usize;
-        
-        let dst = self.color_buffer[index];
-        let src = color;
-    
-        let alpha = src.w.clamp(0.0, 1.0);
-        let inv_alpha = 1.0 - alpha;
-    
-        let out = Float4 {
-            x: src.x * alpha + dst.x * inv_alpha,
-            y: src.y * alpha + dst.y * inv_alpha,
-            z: src.z * alpha + dst.z * inv_alpha,
-            w: alpha + dst.w * inv_alpha,
-        };
-    
-        self.color_buffer[index] = out;
+        if self.msaa() {
+            let start = self.sample_index(x, y);
+            for i in start..start + self.samples as usize {
+                self.sample_colors[i] = blend(self.sample_colors[i], color);
+                self.sample_depths[i] = depth;
+            }
+            return;
+        }
+
+        let index = self.pixel_index(x, y);
+        self.color_buffer[index] = blend(self.color_buffer[index], color);
         self.depth_buffer[index] = depth;
     }
 
+    // Like set_pixel without touching the depth buffer
+    pub fn blend_pixel(&mut self, x: u32, y: u32, color: Float4) {
+        if color.w < f32::EPSILON {
+            return;
+        }
 
+        if self.msaa() {
+            let start = self.sample_index(x, y);
+            for dst in &mut self.sample_colors[start..start + self.samples as usize] {
+                *dst = blend(*dst, color);
+            }
+            return;
+        }
+
+        let index = self.pixel_index(x, y);
+        self.color_buffer[index] = blend(self.color_buffer[index], color);
+    }
+
+    // Blends into a single sample, the pixel itself without MSAA
+    pub fn set_sample(&mut self, x: u32, y: u32, sample: usize, color: Float4, depth: f32) {
+        if !self.msaa() {
+            self.set_pixel(x, y, color, depth);
+            return;
+        }
+        if color.w < f32::EPSILON {
+            return;
+        }
+
+        let index = self.sample_index(x, y) + sample;
+        self.sample_colors[index] = blend(self.sample_colors[index], color);
+        self.sample_depths[index] = depth;
+    }
+
+    pub fn get_sample_depth(&self, x: u32, y: u32, sample: usize) -> f32 {
+        if !self.msaa() {
+            return self.get_pixel_depth(x, y);
+        }
+        self.sample_depths[self.sample_index(x, y) + sample]
+    }
+
+    // Blends `color` over every pixel by its alpha, depth is left alone. Used for fades and dimming.
+    pub fn blend_overlay(&mut self, color: Float4) {
+        if color.w.clamp(0.0, 1.0) < f32::EPSILON {
+            return;
+        }
+
+        let buffer = if self.msaa() { &mut self.sample_colors } else { &mut self.color_buffer };
+        for dst in buffer.iter_mut() {
+            *dst = blend(*dst, color);
+        }
+    }
+
+    // Blends `color` over a rectangle in window pixels (top left corner, y down) clipped to the target.
+    // Depth is left alone, used for text and UI drawn over the finished frame.
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Float4) {
+        if color.w.clamp(0.0, 1.0) < f32::EPSILON {
+            return;
+        }
+
+        let x0 = x.clamp(0, self.width as i32) as u32;
+        let x1 = (x + width as i32).clamp(0, self.width as i32) as u32;
+        let y0 = y.clamp(0, self.height as i32) as u32;
+        let y1 = (y + height as i32).clamp(0, self.height as i32) as u32;
+
+        let (buffer, samples) = if self.msaa() { (&mut self.sample_colors, self.samples as usize) } else { (&mut self.color_buffer, 1) };
+        for row in y0..y1 {
+            let start = ((self.height - 1 - row) * self.width) as usize;
+            for dst in &mut buffer[(start + x0 as usize) * samples..(start + x1 as usize) * samples] {
+                *dst = blend(*dst, color);
+            }
+        }
+    }
+
+    // Averages the samples into the color buffer and keeps the farthest sample depth, so a pixel only
+    // occludes what all of its samples do. Does nothing without MSAA or when already resolved.
+    pub fn resolve(&mut self) {
+        if !self.msaa() {
+            return;
+        }
+
+        let samples = self.samples as usize;
+        let scale = 1.0 / samples as f32;
+        self.color_buffer
+            .par_iter_mut()
+            .zip(self.sample_colors.par_chunks(samples))
+            .for_each(|(dst, src)| *dst = src.iter().fold(Float4::ZERO, |sum, c| sum + *c) * scale);
+        self.resolve_depth();
+        self.resolved = true;
+    }
+
+    fn resolve_depth(&mut self) {
+        let samples = self.samples as usize;
+        self.depth_buffer
+            .par_iter_mut()
+            .zip(self.sample_depths.par_chunks(samples))
+            .for_each(|(dst, src)| *dst = src.iter().fold(0.0, |max: f32, d| max.max(*d)));
+    }
+
+    // Average of the samples while drawing with MSAA, so it doesn't need a resolve
     pub fn get_pixel_color(&self, x: u32, y: u32) -> Float4 {
-        let index = mathi::xy_to_index(x, y, self.width, self.height) as usize;
-        self.color_buffer[index]
+        if self.msaa() {
+            let start = self.sample_index(x, y);
+            let samples = &self.sample_colors[start..start + self.samples as usize];
+            return samples.iter().fold(Float4::ZERO, |sum, c| sum + *c) * (1.0 / self.samples as f32);
+        }
+        self.color_buffer[self.pixel_index(x, y)]
     }
-    
+
+    // Farthest sample with MSAA
     pub fn get_pixel_depth(&self, x: u32, y: u32) -> f32 {
-        let index = mathi::xy_to_index(x, y, self.width, self.height) as usize;
-        self.depth_buffer[index]
+        if self.msaa() {
+            let start = self.sample_index(x, y);
+            return self.sample_depths[start..start + self.samples as usize].iter().fold(0.0, |max: f32, d| max.max(*d));
+        }
+        self.depth_buffer[self.pixel_index(x, y)]
     }
 
+    // Resolved colors, with MSAA only valid after `resolve`
     pub fn color_buffer(&self) -> &[Float4] {
         self.color_buffer.as_slice()
     }
 
+    pub fn depth_buffer(&self) -> &[f32] {
+        self.depth_buffer.as_slice()
+    }
+
+    // Both buffers for post-processing, resolved first
+    pub fn buffers_mut(&mut self) -> (&mut [Float4], &mut [f32]) {
+        self.resolve();
+        (self.color_buffer.as_mut_slice(), self.depth_buffer.as_mut_slice())
+    }
+
+    pub fn set_occlusion_culling(&mut self, enabled: bool) {
+        self.occlusion_culling = enabled;
+        if !enabled {
+            self.hi_z = None;
+        }
+    }
+
+    pub fn occlusion_culling(&self) -> bool {
+        self.occlusion_culling
+    }
+
+    pub fn hi_z(&self) -> Option<&HiZBuffer> {
+        self.hi_z.as_ref()
+    }
+
+    // The G-buffer is only kept while SSAO is on
+    pub fn set_ssao(&mut self, ssao: Option<Ssao>) {
+        match (ssao.is_some(), self.g_buffer.is_some()) {
+            (true, false) => self.g_buffer = Some(GBuffer::new(self.width, self.height)),
+            (false, true) => self.g_buffer = None,
+            _ => {}
+        }
+        self.ssao = ssao;
+    }
+
+    pub fn ssao(&self) -> Option<Ssao> {
+        self.ssao
+    }
+
+    pub fn g_buffer(&self) -> Option<&GBuffer> {
+        self.g_buffer.as_ref()
+    }
+
+    // Records the nearest opaque surface at a pixel, before anything is shaded
+    #[inline(always)]
+    pub fn write_g_buffer(&mut self, x: u32, y: u32, depth: f32, normal: Float3) {
+        let index = self.pixel_index(x, y);
+        if let Some(g_buffer) = &mut self.g_buffer {
+            g_buffer.write(index, depth, normal);
+        }
+    }
+
+    // What SSAO computed from the G-buffer, read back by the shading pass
+    pub fn set_ambient_visibility(&mut self, visibility: Vec<f32>) {
+        if let Some(g_buffer) = &mut self.g_buffer {
+            g_buffer.visibility = visibility;
+        }
+    }
+
+    // Share of the ambient light reaching a surface drawn at `depth`, 1 without SSAO
+    #[inline(always)]
+    pub fn ambient_visibility(&self, x: u32, y: u32, depth: f32) -> f32 {
+        match &self.g_buffer {
+            Some(g_buffer) => g_buffer.ambient_visibility(self.pixel_index(x, y), depth),
+            None => 1.0,
+        }
+    }
+
+    // Called after a frame is rendered, the pyramid is tested against in the next frame
+    pub fn build_hi_z(&mut self, cam: &Camera) {
+        if self.msaa() {
+            self.resolve_depth();
+        }
+        self.hi_z = Some(HiZBuffer::build(&self.depth_buffer, self.width, self.height, cam));
+    }
+
+    // Hash of the color buffer, equal frames give equal checksums. Used to compare replays.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for c in &self.color_buffer {
+            for v in [c.x, c.y, c.z, c.w] {
+                hasher.write_u32(v.to_bits());
+            }
+        }
+        hasher.finish()
+    }
 
     pub fn width(&self) -> u32 {
         self.width
@@ -0,0 +1,237 @@
+use rayon::prelude::*;
+
+use crate::math::mathf as f;
+use crate::math::numerics::{float2::Float2, float3::Float3};
+use crate::rasterizer::{camera::Camera, g_buffer::GBuffer, render_target::RenderTarget};
+
+// Size of the tile of kernel rotations, and of the blur that evens them out
+const NOISE_SIZE: u32 = 4;
+
+// Screen-space ambient occlusion. Every pixel reconstructs its view position from the G-buffer depth,
+// sends samples into the hemisphere around its normal and counts the ones that end up behind what
+// was drawn. Only the ambient term of lit shaders is darkened. A camera component, set on the
+// RenderTarget by the scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ssao {
+    pub radius: f32,    // of the hemisphere, in world units
+    pub intensity: f32, // exponent on the visibility, higher is darker
+    pub bias: f32,      // depth difference that doesn't occlude, relative to the depth, against surfaces shadowing themselves
+    pub samples: u32,
+    pub blur: bool,
+}
+
+impl Default for Ssao {
+    fn default() -> Self {
+        Self { radius: 0.05, intensity: 1.5, bias: 0.01, samples: 16, blur: true }
+    }
+}
+
+impl Ssao {
+    // Runs after the G-buffer is drawn and before the models are shaded with its result
+    pub fn apply(&self, render_target: &mut RenderTarget, cam: &Camera) {
+        let Some(g_buffer) = render_target.g_buffer() else { return };
+        let visibility = self.visibility(g_buffer, cam);
+        let visibility = if self.blur { blur(g_buffer, &visibility) } else { visibility };
+        render_target.set_ambient_visibility(visibility);
+    }
+
+    // How much ambient light reaches each pixel, 1 where nothing is in the way or nothing was drawn
+    pub fn visibility(&self, g_buffer: &GBuffer, cam: &Camera) -> Vec<f32> {
+        let (width, height) = (g_buffer.width(), g_buffer.height());
+        let mut visibility = vec![1.0; (width * height) as usize];
+        if width == 0 || height == 0 || self.samples == 0 {
+            return visibility;
+        }
+
+        let projection = ScreenProjection::new(cam, width, height);
+        let kernel = kernel(self.samples);
+        let rotations: Vec<Float3> = (0..NOISE_SIZE * NOISE_SIZE)
+            .map(|i| Float3::new(random(1000 + i * 2) * 2.0 - 1.0, random(1001 + i * 2) * 2.0 - 1.0, 0.0))
+            .collect();
+
+        visibility.par_chunks_mut(width as usize).enumerate().for_each(|(y, row)| {
+            for (x, out) in row.iter_mut().enumerate() {
+                let index = y * width as usize + x;
+                let (depth, normal) = (g_buffer.depths[index], g_buffer.normals[index]);
+                if !depth.is_finite() || normal.length() < 0.5 {
+                    continue;
+                }
+
+                let origin = projection.to_view(Float2::new(x as f32 + 0.5, y as f32 + 0.5), depth);
+                let rotation = rotations[((y as u32 % NOISE_SIZE) * NOISE_SIZE + x as u32 % NOISE_SIZE) as usize];
+                let (tangent, bitangent) = basis(normal, rotation);
+
+                let mut occlusion = 0.0;
+                for k in &kernel {
+                    let sample = origin + (tangent * k.x + bitangent * k.y + normal * k.z) * self.radius;
+                    if projection.perspective && sample.z <= 0.0 {
+                        continue;
+                    }
+
+                    let screen = projection.to_screen(sample);
+                    let (sx, sy) = (screen.x.floor() as i32, screen.y.floor() as i32);
+                    if sx < 0 || sy < 0 || sx >= width as i32 || sy >= height as i32 {
+                        continue;
+                    }
+
+                    // Occluders far in front of the pixel are a different object, they fade out
+                    let scene_depth = g_buffer.depths[(sy as u32 * width + sx as u32) as usize];
+                    if scene_depth <= sample.z - self.bias * depth {
+                        occlusion += f::smoothstep(0.0, 1.0, self.radius / (depth - scene_depth).abs().max(1e-6));
+                    }
+                }
+
+                *out = (1.0 - occlusion / kernel.len() as f32).max(0.0).powf(self.intensity);
+            }
+        });
+        visibility
+    }
+}
+
+// Averages the noise tile away, only over pixels at a similar depth so edges stay sharp
+fn blur(g_buffer: &GBuffer, visibility: &[f32]) -> Vec<f32> {
+    let (width, height) = (g_buffer.width() as i32, g_buffer.height() as i32);
+    let half = NOISE_SIZE as i32 / 2;
+    let mut blurred = visibility.to_vec();
+
+    blurred.par_chunks_mut(width as usize).enumerate().for_each(|(y, row)| {
+        let y = y as i32;
+        for (x, out) in row.iter_mut().enumerate() {
+            let x = x as i32;
+            let depth = g_buffer.depths[(y * width + x) as usize];
+            if !depth.is_finite() {
+                continue;
+            }
+
+            let (mut sum, mut total) = (0.0, 0.0);
+            for ty in (y - half).max(0)..(y + half).min(height) {
+                for tx in (x - half).max(0)..(x + half).min(width) {
+                    let index = (ty * width + tx) as usize;
+                    if (g_buffer.depths[index] - depth).abs() <= depth * 0.05 {
+                        sum += visibility[index];
+                        total += 1.0;
+                    }
+                }
+            }
+            if total > 0.0 {
+                *out = sum / total;
+            }
+        }
+    });
+    blurred
+}
+
+// Points in the hemisphere around +z, more of them close to the center where occluders matter most
+fn kernel(samples: u32) -> Vec<Float3> {
+    (0..samples)
+        .map(|i| {
+            let seed = i * 4;
+            let direction = Float3::new(random(seed) * 2.0 - 1.0, random(seed + 1) * 2.0 - 1.0, random(seed + 2).max(0.15));
+            let scale = i as f32 / samples as f32;
+            direction.normalize() * random(seed + 3).max(0.1) * (0.1 + 0.9 * scale * scale)
+        })
+        .collect()
+}
+
+// Tangent and bitangent around `normal`, turned by `rotation` so neighbouring pixels sample differently
+fn basis(normal: Float3, rotation: Float3) -> (Float3, Float3) {
+    let mut tangent = rotation - normal * rotation.dot(normal);
+    if tangent.length() < 1e-4 {
+        let axis = if normal.x.abs() < 0.9 { Float3::UNIT_X } else { Float3::UNIT_Y };
+        tangent = axis - normal * axis.dot(normal);
+    }
+    let tangent = tangent.normalize();
+    (tangent, normal.cross(tangent))
+}
+
+// Hash of `seed` to [0, 1), so the kernel and noise are the same every frame
+fn random(seed: u32) -> f32 {
+    let mut x = seed.wrapping_mul(0x9E37_79B9) ^ 0x85EB_CA6B;
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7FEB_352D);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846C_A68B);
+    x ^= x >> 16;
+    (x >> 8) as f32 / (1 << 24) as f32
+}
+
+// Camera::view_to_screen and its inverse with the projection worked out once per frame
+struct ScreenProjection {
+    extents: (f32, f32, f32, f32),
+    viewport: (f32, f32, f32, f32),
+    perspective: bool,
+}
+
+impl ScreenProjection {
+    fn new(cam: &Camera, width: u32, height: u32) -> Self {
+        Self { extents: cam.extents(width, height), viewport: cam.viewport_rect(width, height), perspective: cam.is_perspective() }
+    }
+
+    #[inline(always)]
+    fn to_screen(&self, view: Float3) -> Float2 {
+        let (left, right, bottom, top) = self.extents;
+        let (vx, vy, vw, vh) = self.viewport;
+        let (x, y) = if self.perspective { (view.x / view.z, view.y / view.z) } else { (view.x, view.y) };
+        Float2::new(vx + (x - left) / (right - left) * vw, vy + (y - bottom) / (top - bottom) * vh)
+    }
+
+    // View position of the surface at a render target position and view depth
+    #[inline(always)]
+    fn to_view(&self, screen: Float2, depth: f32) -> Float3 {
+        let (left, right, bottom, top) = self.extents;
+        let (vx, vy, vw, vh) = self.viewport;
+        let x = left + (screen.x - vx) / vw * (right - left);
+        let y = bottom + (screen.y - vy) / vh * (top - bottom);
+        if self.perspective { Float3::new(x * depth, y * depth, depth) } else { Float3::new(x, y, depth) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math::numerics::float4::Float4, shaders::lit_texture_shader::{AMBIENT, LitTextureShader}, types::{handle::Handle, texture::Texture}};
+
+    #[test]
+    fn positions_round_trip_through_the_camera() {
+        let cam = Camera::new(70.0);
+        let projection = ScreenProjection::new(&cam, 64, 48);
+        let view = Float3::new(0.3, -0.2, 2.5);
+        let screen = projection.to_screen(view);
+        assert!((screen - cam.view_to_screen(view, 64, 48)).length() < 1e-4);
+        assert!((projection.to_view(screen, view.z) - view).length() < 1e-4);
+    }
+
+    #[test]
+    fn darkens_the_ambient_term_in_creases_only() {
+        // A wall at depth 2 with a step 0.1 closer on its left half, everything facing the camera
+        let mut render_target = RenderTarget::new(64, 64);
+        let ssao = Ssao { radius: 0.2, blur: false, ..Ssao::default() };
+        render_target.set_ssao(Some(ssao));
+        render_target.clear(Float4::ZERO);
+
+        let normal = Float3::new(0.0, 0.0, -1.0);
+        for y in 0..64 {
+            for x in 0..64 {
+                render_target.write_g_buffer(x, y, if x < 32 { 1.9 } else { 2.0 }, normal);
+            }
+        }
+
+        let cam = Camera::new(90.0);
+        ssao.apply(&mut render_target, &cam);
+
+        // Open surfaces, including the outer edge of the step, keep their ambient light
+        assert_eq!(render_target.ambient_visibility(50, 32, 2.0), 1.0);
+        assert_eq!(render_target.ambient_visibility(31, 32, 1.9), 1.0);
+        // At the foot of the step some of it is taken away, not from surfaces behind the wall
+        let crease = render_target.ambient_visibility(32, 32, 2.0);
+        assert!((0.0..1.0).contains(&crease), "{crease}");
+        assert_eq!(render_target.ambient_visibility(32, 32, 3.0), 1.0);
+
+        // The light falling straight on the surface stays, only the ambient term is darkened
+        let texture = Handle::new(Texture::from_texels(1, 1, vec![Float4::ONE]));
+        let shader = LitTextureShader::new(normal, texture, false);
+        let lit = |visibility| shader.shade(Float2::ZERO, Float2::ZERO, normal, 2.0, visibility).x;
+        assert!((lit(1.0) - 1.0).abs() < 1e-5);
+        assert!((lit(crease) - (1.0 - AMBIENT * (1.0 - crease))).abs() < 1e-5);
+    }
+}
\ No newline at end of file
@@ -1 +1,18 @@
-pub mod test_scene;
\ No newline at end of file
+use crate::types::scene::Scene;
+
+pub mod pause_scene;
+pub mod test_scene;
+
+// Names accepted by `create`, the first one is the default
+pub const NAMES: &[&str] = &["test", "pause"];
+
+// Scene factory for the scene manager and the `--scene` option.
+// Names ending in `.scene` are scene files (core::scene_file), run like the test scene.
+pub fn create(name: &str) -> Option<Box<dyn Scene>> {
+    match name {
+        file if file.ends_with(".scene") => Some(Box::new(test_scene::TestScene::from_file(file))),
+        "test" => Some(Box::new(test_scene::TestScene::new())),
+        "pause" => Some(Box::new(pause_scene::PauseScene::new())),
+        _ => None,
+    }
+}
\ No newline at end of file
@@ -0,0 +1,47 @@
+#![allow(unused_variables)]
+
+use crate::core::{actions, scene_manager::{self, Transition}};
+use crate::math::numerics::float4::Float4;
+use crate::rasterizer::render_target::RenderTarget;
+use crate::types::scene::Scene;
+use crate::ui::font::BitmapFont;
+
+// Dims the scene below it until `pause` is pressed again, `quit` fades out and closes
+pub struct PauseScene {
+    dim: Float4,
+    font: BitmapFont,
+}
+
+impl PauseScene {
+    pub fn new() -> Self {
+        Self { dim: Float4::new(0.0, 0.0, 0.0, 0.6), font: BitmapFont::builtin().with_scale(3) }
+    }
+}
+
+impl Scene for PauseScene {
+    fn start(&mut self, render_target: &mut RenderTarget) {}
+
+    fn update(&mut self, delta_time: f32, render_target: &mut RenderTarget) {
+        if actions::is_down("pause") {
+            scene_manager::pop(Transition::Cut);
+        }
+        if actions::is_down("quit") {
+            scene_manager::quit(Transition::fade(0.6));
+        }
+    }
+
+    fn render(&mut self, alpha: f32, render_target: &mut RenderTarget) {
+        render_target.blend_overlay(self.dim);
+
+        let (width, height) = self.font.measure("Paused");
+        let x = (render_target.width() as i32 - width as i32) / 2;
+        let y = (render_target.height() as i32 - height as i32) / 2;
+        self.font.draw(render_target, "Paused", x, y, Float4::ONE);
+    }
+
+    fn resize(&mut self, new_width: u32, new_height: u32, render_target: &mut RenderTarget) {}
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
\ No newline at end of file
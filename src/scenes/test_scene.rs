@@ -4,162 +4,325 @@
 
 use minifb::Key;
 
-use crate::core::{input, resource_helper};
+use std::path::{Path, PathBuf};
 
+use crate::core::{actions, assets, input, resource_helper, scene_file, time};
+use crate::core::scene_manager::{self, Transition};
+use crate::core::camera_controller::{CameraController, FirstPersonCamera, FlyCamera, OrbitCamera};
+
+use crate::ecs::components::{Light, Name};
+use crate::ecs::scripts::{Scripts, Spin};
+use crate::ecs::systems::Schedule;
+use crate::ecs::world::{Entity, World};
+
+use crate::math::numerics::float2::Float2;
 use crate::math::numerics::float3::Float3;
 use crate::math::numerics::float4::Float4;
 
-use crate::rasterizer::camera::Camera;
-use crate::rasterizer::rasterizer;
+use crate::post::stack::PostStack;
+
+use crate::rasterizer::camera::{Camera, Projection};
+use crate::rasterizer::frame_stats::FrameStats;
+use crate::rasterizer::{debug_draw, rasterizer};
 use crate::rasterizer::render_target::RenderTarget;
+use crate::rasterizer::ssao::Ssao;
 
 use crate::shaders::shader_base::Shader;
 use crate::shaders::texture_shader::TextureShader;
 use crate::shaders::lit_texture_shader::LitTextureShader;
 use crate::shaders::transparent_texture_shader::TransparentTextureShader;
 
+use crate::types::bvh::SceneBvh;
+use crate::types::handle::Handle;
+use crate::types::lod::LodGroup;
 use crate::types::mesh::Mesh;
-use crate::types::model::Model;
+use crate::types::mesh_renderer::MeshRenderer;
 use crate::types::scene::Scene;
+use crate::types::transform::Transform;
+
+use crate::ui::context::Ui;
 
 pub struct TestScene {
-    models: Vec<Model>,
-    cam: Camera,
+    file: String,
+    world: World,
+    schedule: Schedule,
+    camera: Option<Entity>,
 
-    speed: f32,
+    ui: Ui,
+    show_ui: bool,
+    show_bounds: bool,
+    show_gizmos: bool, // grid, model axes and the light direction
+    msaa: u32,         // samples per pixel, applied to the render target every frame
+    post: bool,        // runs the camera's PostStack
+    ssao: bool,        // uses the camera's Ssao settings
+    stats: FrameStats, // of the last render, shown by the UI
+    fps: f32,
+    pick_start: Option<Float2>, // where the pick button went down, a drag moves the camera instead
+    picked: Option<Entity>,
 }
 
 impl TestScene {
     pub fn new() -> Self {
-        Self {
-            models: Vec::new(),
-            cam: Camera::new(100.0),
+        Self::from_file("test.scene")
+    }
 
-            speed: 0.8,
+    // Scene file by asset name or path, loaded on start
+    pub fn from_file(file: &str) -> Self {
+        Self {
+            file: file.to_string(),
+            world: World::new(),
+            schedule: Schedule::default(),
+            camera: None,
+            ui: Ui::new(),
+            show_ui: true,
+            show_bounds: false,
+            show_gizmos: false,
+            msaa: 4,
+            post: true,
+            ssao: true,
+            stats: FrameStats::default(),
+            fps: 0.0,
+            pick_start: None,
+            picked: None,
         }
     }
 
-    fn get_model(&mut self, name: &str) -> Option<&mut Model> {
-        self.models.iter_mut().find(|model| model.name == name)
+    fn path(&self) -> PathBuf {
+        let path = Path::new(&self.file);
+        if path.exists() { path.to_path_buf() } else { assets::resolve(&self.file) }
+    }
+
+    fn camera_entity(&self) -> Entity {
+        self.camera.expect("Test scene not started")
+    }
+
+    fn camera(&self) -> Camera {
+        self.world.get::<Camera>(self.camera_entity()).unwrap().clone()
+    }
+
+    fn set_controller(&mut self, controller: CameraController) {
+        self.world.insert(self.camera_entity(), Scripts::new().with(controller));
+    }
+
+    // Closest entity under a window position (y down)
+    fn pick(&self, mouse: Float2, render_target: &RenderTarget) -> Option<Entity> {
+        let (width, height) = (render_target.width(), render_target.height());
+        let ray = self.camera().screen_point_to_ray(Float2::new(mouse.x, height as f32 - mouse.y), width, height);
+        SceneBvh::build(&self.world).raycast(&ray, f32::INFINITY).map(|hit| hit.entity)
+    }
+
+    fn debug_ui(&mut self, delta_time: f32, render_target: &RenderTarget) {
+        // Smoothed so the number stays readable
+        if delta_time > 0.0 {
+            self.fps += (1.0 / delta_time - self.fps) * 0.1;
+        }
+
+        self.ui.begin_frame();
+        if !self.show_ui {
+            self.ui.end_frame();
+            return;
+        }
+
+        let stats = &self.stats;
+        self.ui.begin_panel("Debug (F1)", 8, 8, 240);
+        self.ui.label(&format!("{}x{}, {:.0} fps", render_target.width(), render_target.height(), self.fps));
+        self.ui.label(&format!("Triangles: {}, drawn: {}", stats.triangles, stats.drawn));
+        self.ui.label(&format!("Culled: {}/{} (frustum/occlusion)", stats.frustum_culled, stats.occlusion_culled));
+        self.ui.label(&format!("Dragon LOD: {}", stats.model("Dragon").and_then(|m| m.lod).unwrap_or(0)));
+        self.ui.label(&format!("Picked: {}", self.picked.map_or("nothing (click a model)".to_string(), |e| self.world.name(e))));
+
+        let reload = assets::last_reload();
+        for name in &reload.reloaded {
+            self.ui.label(&format!("Reloaded {name}"));
+        }
+        for (name, error) in &reload.failed {
+            self.ui.label(&format!("Failed to reload {name}: {error}"));
+        }
+
+        let renderers: Vec<Entity> = self.world.read::<MeshRenderer>().iter().map(|(e, _)| e).collect();
+        for &entity in &renderers {
+            let label = format!("{} wireframe##{}", self.world.name(entity), entity.index());
+            let mut renderer = self.world.get_mut::<MeshRenderer>(entity).unwrap();
+            let mut wireframe = renderer.shader.render_as_wireframe();
+            if self.ui.checkbox(&label, &mut wireframe) {
+                renderer.shader.set_wireframe(wireframe);
+            }
+        }
+
+        // Edge settings are shown for the first model and applied to all of them
+        if let Some(&first) = renderers.first() {
+            let mut edges = self.world.get::<MeshRenderer>(first).unwrap().wireframe;
+            let changed = self.ui.checkbox("Edges over solid", &mut edges.overlay) | self.ui.slider("Edge width", &mut edges.width, 1.0, 4.0);
+            if changed {
+                for &entity in &renderers {
+                    let mut renderer = self.world.get_mut::<MeshRenderer>(entity).unwrap();
+                    renderer.wireframe.overlay = edges.overlay;
+                    renderer.wireframe.width = edges.width;
+                }
+            }
+        }
+
+        let light = self.world.read::<Light>().iter().next().map(|(e, _)| e);
+        if let Some(light) = light && let Some(mut transform) = self.world.get_mut::<Transform>(light) {
+            let mut pitch = transform.rotation.x.to_degrees();
+            let mut yaw = transform.rotation.y.to_degrees();
+            let changed = self.ui.slider("Light pitch", &mut pitch, -90.0, 90.0) | self.ui.slider("Light yaw", &mut yaw, -180.0, 180.0);
+            if changed {
+                transform.set_rotation(Float3::new(pitch.to_radians(), yaw.to_radians(), 0.0));
+            }
+        }
+
+        self.ui.checkbox("Bounds", &mut self.show_bounds);
+        self.ui.checkbox("Grid and axes", &mut self.show_gizmos);
+        if self.ui.button(&format!("MSAA: {}x", self.msaa)) {
+            self.msaa = if self.msaa >= 8 { 1 } else { self.msaa * 2 };
+        }
+
+        self.ui.checkbox("SSAO", &mut self.ssao);
+        if self.ssao && let Some(mut ssao) = self.world.get_mut::<Ssao>(self.camera_entity()) {
+            self.ui.slider("  Radius", &mut ssao.radius, 0.01, 0.3);
+            self.ui.slider("  Intensity", &mut ssao.intensity, 0.5, 4.0);
+        }
+
+        self.ui.checkbox("Post-processing (F2)", &mut self.post);
+        if self.post && let Some(mut stack) = self.world.get_mut::<PostStack>(self.camera_entity()) {
+            for (i, entry) in stack.passes.iter_mut().enumerate() {
+                self.ui.checkbox(&format!("  {}##post{i}", entry.pass.name()), &mut entry.enabled);
+            }
+        }
+
+        if self.ui.button("Save scene (F5)") {
+            scene_file::save(&self.world, self.path());
+        }
+        self.ui.end_panel();
+        self.ui.end_frame();
     }
 
-    fn create_model(&mut self, name: &str, shader: Shader) -> &mut Model {
-        self.models.push(Model::new(name, Mesh::empty(), shader));
-        self.models.last_mut().unwrap()
+    fn debug_shapes(&mut self) {
+        let transforms = self.world.read::<Transform>();
+        if let Some(picked) = self.picked
+            && let (Some(transform), Some(mut renderer)) = (transforms.get(picked), self.world.get_mut::<MeshRenderer>(picked))
+        {
+            debug_draw::obb(&renderer.bvh().bounds(), transform, debug_draw::WHITE);
+        }
+
+        if self.show_bounds {
+            for (entity, renderer) in self.world.write::<MeshRenderer>().iter_mut() {
+                let Some(transform) = transforms.get(entity) else { continue };
+                let bounds = renderer.bvh().bounds();
+                debug_draw::obb(&bounds, transform, debug_draw::YELLOW);
+                let top = transform.to_world_point(Float3::new(bounds.center().x, bounds.max.y, bounds.center().z));
+                debug_draw::text(top, &self.world.name(entity), debug_draw::WHITE);
+            }
+        }
+
+        if self.show_gizmos {
+            debug_draw::grid(Float3::ZERO, 2.0, 10, Float4::new(0.5, 0.5, 0.5, 0.6));
+            for (entity, _) in self.world.read::<MeshRenderer>().iter() {
+                if let Some(transform) = transforms.get(entity) {
+                    debug_draw::axes(transform, 0.15);
+                }
+            }
+            for (entity, _) in self.world.read::<Light>().iter() {
+                if let Some(transform) = transforms.get(entity) {
+                    let start = transform.position + Float3::new(0.0, 0.6, 0.0);
+                    debug_draw::arrow(start, start + transform.forward() * 0.3, debug_draw::YELLOW);
+                }
+            }
+        }
     }
 }
 
 impl Scene for TestScene {
     fn start(&mut self, render_target: &mut RenderTarget) {
-        #[cfg(feature = "release_paths")]
-        let color = resource_helper::load_texture("assets/color.png");
-        #[cfg(feature = "release_paths")]
-        let dragon_mesh = resource_helper::load_mesh("assets/dragon.obj");
-
-        #[cfg(not(feature = "release_paths"))]
-        let color = resource_helper::load_texture("../rasterizer/src/assets/color.png");
-        #[cfg(not(feature = "release_paths"))]
-        let dragon_mesh = resource_helper::load_mesh("../rasterizer/src/assets/dragon.obj");
-
-        let dragon_model = self.create_model("Dragon", Shader::LitTextureShader(LitTextureShader::new(Float3::UNIT_Y, color, true)));
-        dragon_model.mesh = dragon_mesh;
-        dragon_model.transform.set_scale(Float3::new(0.2, 0.2, 0.2));
-        dragon_model.transform.set_position(Float3::new(0.0, 0.05, 0.0));
-
-        #[cfg(feature = "release_paths")]
-        let floor_texture = resource_helper::load_texture("assets/floortexture.png");
-        #[cfg(feature = "release_paths")]
-        let floor_mesh = resource_helper::load_mesh("assets/Floor.obj");
-
-        #[cfg(not(feature = "release_paths"))]
-        let floor_texture = resource_helper::load_texture("../rasterizer/src/assets/floortexture.png");
-        #[cfg(not(feature = "release_paths"))]
-        let floor_mesh = resource_helper::load_mesh("../rasterizer/src/assets/Floor.obj");
-
-        let floor_model = self.create_model("Floor", Shader::TextureShader(TextureShader::new(floor_texture, true)));
-        floor_model.mesh = floor_mesh;
-
-        #[cfg(feature = "release_paths")]
-        let sw_texture = resource_helper::load_texture("assets/Smallworldtexture.png");
-        #[cfg(feature = "release_paths")]
-        let sw_mesh = resource_helper::load_mesh("assets/SmallWorld.obj");
-
-        #[cfg(not(feature = "release_paths"))]
-        let sw_texture = resource_helper::load_texture("../rasterizer/src/assets/Smallworldtexture.png");
-        #[cfg(not(feature = "release_paths"))]
-        let sw_mesh = resource_helper::load_mesh("../rasterizer/src/assets/SmallWorld.obj");
-
-        let sw_model = self.create_model("SmallWorld", Shader::LitTextureShader(LitTextureShader::new(Float3::new(0.5, 1.0, 0.3), sw_texture, true)));
-        sw_model.mesh = sw_mesh;
-        sw_model.transform.set_position(Float3::new(1.0, 0.01, 0.0));
-        sw_model.transform.set_scale(Float3::new(0.2, 0.2, 0.2));
-
-        self.cam.transform.set_position(Float3::new(0.0, 0.2, -1.0));
+        actions::load(assets::resolve("bindings.cfg"));
+
+        self.world = scene_file::load(self.path());
+        let cameras = self.world.read::<Camera>().iter().map(|(e, _)| e).collect::<Vec<_>>();
+        self.camera = Some(self.world.find("Camera").filter(|e| cameras.contains(e)).or(cameras.first().copied()).expect("Scene has no camera"));
+        render_target.set_occlusion_culling(true);
     }
 
     fn update(&mut self, delta_time: f32, render_target: &mut RenderTarget) {
-        println!("Resolution: {}x{}, Fps: {}", render_target.width(), render_target.height(), 1.0 / delta_time);
-
-        if input::is_down(Key::C) {
-            for model in self.models.iter_mut() {
-                match &mut model.shader {
-                    Shader::TextureShader(s) => s.wireframe = !s.wireframe,
-                    Shader::LitTextureShader(s) => s.wireframe = !s.wireframe,
-                    Shader::TransparentTextureShader(_) => {},
-                }
+        if actions::is_down("toggle_wireframe") {
+            for (_, renderer) in self.world.write::<MeshRenderer>().iter_mut() {
+                let wireframe = renderer.shader.render_as_wireframe();
+                renderer.shader.set_wireframe(!wireframe);
             }
         }
 
-        if input::is_pressed(Key::R) {
-            self.speed += 0.04;
-        }
-        else if input::is_pressed(Key::F) {
-            self.speed -= 0.04;
+        for (_, renderer) in self.world.write::<MeshRenderer>().iter_mut() {
+            if let Shader::AnimatedTextureShader(s) = &mut renderer.shader {
+                s.advance(delta_time);
+            }
         }
 
-        let speed = self.speed * delta_time;
-    
-        if input::is_pressed(Key::W) {
-            self.cam.transform.translate(Float3::new(self.cam.transform.forward().x * speed, 0.0, self.cam.transform.forward().z * speed));
+        if actions::is_down("toggle_projection") {
+            let mut cam = self.world.get_mut::<Camera>(self.camera_entity()).unwrap();
+            cam.projection = match cam.projection {
+                Projection::Orthographic { .. } => Projection::Perspective { fov_degrees: 100.0 },
+                _ => Projection::Orthographic { size: 1.0 },
+            };
         }
-        if input::is_pressed(Key::S) {
-            self.cam.transform.translate(Float3::new(self.cam.transform.backward().x * speed, 0.0, self.cam.transform.backward().z * speed));
+
+        if actions::is_down("fly_camera") {
+            self.set_controller(CameraController::Fly(FlyCamera::new(&self.camera())));
         }
-        if input::is_pressed(Key::D) {
-            self.cam.transform.translate(Float3::new(self.cam.transform.right().x * speed, 0.0, self.cam.transform.right().z * speed));
+        if actions::is_down("orbit_camera") {
+            self.set_controller(CameraController::Orbit(OrbitCamera::new(Float3::new(0.0, 0.1, 0.0), 1.0)));
         }
-        if input::is_pressed(Key::A) {
-            self.cam.transform.translate(Float3::new(self.cam.transform.left().x * speed, 0.0, self.cam.transform.left().z * speed));
+        if actions::is_down("first_person_camera") {
+            self.set_controller(CameraController::FirstPerson(FirstPersonCamera::new(&self.camera())));
         }
 
-        if input::is_pressed(Key::LeftShift) {
-            self.cam.transform.translate(Float3::ZERO - Float3::UNIT_Y * speed);
+        if actions::is_down("toggle_post") {
+            self.post = !self.post;
         }
-        if input::is_pressed(Key::Space) {
-            self.cam.transform.translate(Float3::UNIT_Y * speed);
+        if actions::is_down("toggle_ui") {
+            self.show_ui = !self.show_ui;
+        }
+        self.debug_ui(delta_time, render_target);
+        if self.ui.wants_mouse() {
+            input::consume_mouse();
         }
 
-        let rotation_speed = self.speed * delta_time;
+        if actions::is_down("pick") {
+            self.pick_start = Some(input::mouse_position());
+        }
+        if actions::is_up("pick") && let Some(start) = self.pick_start.take() && (input::mouse_position() - start).length() < 4.0 {
+            self.picked = self.pick(input::mouse_position(), render_target);
+        }
+        self.schedule.run(&mut self.world, delta_time);
+        self.debug_shapes();
 
-        if input::is_pressed(Key::Left) {
-            self.cam.transform.rotate(Float3::new(0.0, rotation_speed, 0.0));
+        if actions::is_down("save_bindings") {
+            actions::save(assets::resolve("bindings.cfg"));
         }
-        if input::is_pressed(Key::Right) {
-            self.cam.transform.rotate(Float3::new(0.0, -rotation_speed, 0.0));
+        if actions::is_down("save_scene") {
+            scene_file::save(&self.world, self.path());
         }
-        if input::is_pressed(Key::Up) {
-            self.cam.transform.rotate(Float3::new(rotation_speed, 0.0, 0.0));
+
+        if actions::is_down("pause") {
+            scene_manager::push("pause", Transition::Cut);
         }
-        if input::is_pressed(Key::Down) {
-            self.cam.transform.rotate(Float3::new(-rotation_speed, 0.0, 0.0));
+        if actions::is_down("quit") {
+            scene_manager::quit(Transition::fade(0.6));
         }
+    }
 
+    fn render(&mut self, alpha: f32, render_target: &mut RenderTarget) {
         // render_target.clear(Float4::new(0.53, 0.81, 0.92, 1.0));
+        render_target.set_samples(self.msaa);
+        render_target.set_ssao(self.world.get::<Ssao>(self.camera_entity()).filter(|_| self.ssao).map(|s| *s));
         render_target.clear(Float4::ZERO);
-        rasterizer::render(render_target, &mut self.models, &self.cam);
+        self.stats = rasterizer::render(render_target, &self.world, &self.camera());
+        if self.post && let Some(stack) = self.world.get::<PostStack>(self.camera_entity()) {
+            stack.apply(render_target);
+        }
+        self.ui.render(render_target);
     }
 
     fn resize(&mut self, new_width: u32, new_height: u32, render_target: &mut RenderTarget) {
-        
+        render_target.set_occlusion_culling(true);
     }
 }
@@ -0,0 +1,31 @@
+use crate::{math::numerics::{float2::Float2, float3::Float3, float4::Float4}, types::{animated_texture::AnimatedTexture, handle::Handle, texture::TextureFilter}};
+
+#[derive(Clone)]
+pub struct AnimatedTextureShader {
+    pub texture: Handle<AnimatedTexture>,
+    pub time: f32,
+    pub wireframe: bool,
+    pub filter: TextureFilter,
+}
+
+impl AnimatedTextureShader {
+    pub fn new(texture: Handle<AnimatedTexture>, wireframe: bool) -> Self {
+        Self {
+            texture,
+            time: 0.0,
+            wireframe,
+            filter: TextureFilter::Nearest,
+        }
+    }
+
+    // Called once per frame by the scene to play the animation
+    pub fn advance(&mut self, delta_time: f32) {
+        self.time += delta_time;
+    }
+
+    #[inline(always)]
+    #[allow(unused_variables)]
+    pub fn pixel_color(&self, pixel_coord: Float2, uv: Float2, normal: Float3, depth: f32) -> Float4 {
+        self.texture.frame_at(self.time).sample(uv.x, uv.y, self.filter)
+    }
+}
\ No newline at end of file
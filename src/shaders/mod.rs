@@ -1,5 +1,6 @@
 pub mod texture_shader;
 pub mod lit_texture_shader;
 pub mod transparent_texture_shader;
+pub mod animated_texture_shader;
 
 pub mod shader_base;
\ No newline at end of file
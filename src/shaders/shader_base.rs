@@ -1,10 +1,12 @@
-use crate::{math::numerics::{float2::Float2, float3::Float3, float4::Float4}, shaders::{lit_texture_shader::LitTextureShader, texture_shader::TextureShader, transparent_texture_shader::TransparentTextureShader}};
+use crate::{math::numerics::{float2::Float2, float3::Float3, float4::Float4}, shaders::{animated_texture_shader::AnimatedTextureShader, lit_texture_shader::LitTextureShader, texture_shader::TextureShader, transparent_texture_shader::TransparentTextureShader}, types::texture::TextureFilter};
 
 #[allow(dead_code)]
+#[allow(clippy::enum_variant_names)]
 pub enum Shader {
     TextureShader(TextureShader),
     LitTextureShader(LitTextureShader),
     TransparentTextureShader(TransparentTextureShader),
+    AnimatedTextureShader(AnimatedTextureShader),
 }
 
 impl Shader {
@@ -14,6 +16,17 @@ impl Shader {
             Shader::TextureShader(s) => s.pixel_color(pixel_coord, uv, normal, depth),
             Shader::LitTextureShader(s) => s.pixel_color(pixel_coord, uv, normal, depth),
             Shader::TransparentTextureShader(s) => s.pixel_color(pixel_coord, uv, normal, depth),
+            Shader::AnimatedTextureShader(s) => s.pixel_color(pixel_coord, uv, normal, depth),
+        }
+    }
+
+    // Like pixel_color, with only `ambient_visibility` of the ambient light reaching the surface.
+    // Unlit shaders ignore it.
+    #[inline(always)]
+    pub fn pixel_color_occluded(&self, pixel_coord: Float2, uv: Float2, normal: Float3, depth: f32, ambient_visibility: f32) -> Float4 {
+        match self {
+            Shader::LitTextureShader(s) => s.shade(pixel_coord, uv, normal, depth, ambient_visibility),
+            _ => self.pixel_color(pixel_coord, uv, normal, depth),
         }
     }
 
@@ -23,6 +36,50 @@ impl Shader {
             Shader::TextureShader(s) => s.wireframe,
             Shader::LitTextureShader(s) => s.wireframe,
             Shader::TransparentTextureShader(_) => false,
+            Shader::AnimatedTextureShader(s) => s.wireframe,
+        }
+    }
+
+    // Blended over what's behind, so it doesn't hide anything
+    pub fn is_transparent(&self) -> bool {
+        matches!(self, Shader::TransparentTextureShader(_))
+    }
+
+    // Transparent shaders always fill
+    pub fn set_wireframe(&mut self, wireframe: bool) {
+        match self {
+            Shader::TextureShader(s) => s.wireframe = wireframe,
+            Shader::LitTextureShader(s) => s.wireframe = wireframe,
+            Shader::TransparentTextureShader(_) => {}
+            Shader::AnimatedTextureShader(s) => s.wireframe = wireframe,
+        }
+    }
+
+    pub fn filter(&self) -> TextureFilter {
+        match self {
+            Shader::TextureShader(s) => s.filter,
+            Shader::LitTextureShader(s) => s.filter,
+            Shader::TransparentTextureShader(s) => s.filter,
+            Shader::AnimatedTextureShader(s) => s.filter,
+        }
+    }
+
+    pub fn set_filter(&mut self, filter: TextureFilter) {
+        match self {
+            Shader::TextureShader(s) => s.filter = filter,
+            Shader::LitTextureShader(s) => s.filter = filter,
+            Shader::TransparentTextureShader(s) => s.filter = filter,
+            Shader::AnimatedTextureShader(s) => s.filter = filter,
+        }
+    }
+
+    // Pick up hot-reloaded textures, called once per frame before drawing
+    pub fn refresh_assets(&mut self) {
+        match self {
+            Shader::TextureShader(s) => { s.texture.refresh(); }
+            Shader::LitTextureShader(s) => { s.texture.refresh(); }
+            Shader::TransparentTextureShader(s) => { s.texture.refresh(); }
+            Shader::AnimatedTextureShader(s) => { s.texture.refresh(); }
         }
     }
 }
\ No newline at end of file
@@ -1,33 +1,27 @@
 #![allow(dead_code)]
 
-use crate::{math::numerics::{float2::Float2, float3::Float3, float4::Float4}, types::texture::Texture};
+use crate::{math::numerics::{float2::Float2, float3::Float3, float4::Float4}, types::{handle::Handle, texture::{Texture, TextureFilter}}};
 
 #[derive(Clone)]
 pub struct TransparentTextureShader {
-    pub texture: Texture,
+    pub texture: Handle<Texture>,
     pub alpha: f32,
+    pub filter: TextureFilter,
 }
 
 impl TransparentTextureShader {
-    pub fn new(texture: Texture, alpha: f32) -> Self {
+    pub fn new(texture: Handle<Texture>, alpha: f32) -> Self {
         Self { 
             texture, 
             alpha,
+            filter: TextureFilter::Nearest,
         }
     }
 
     #[inline(always)]
     #[allow(unused_variables)]
     pub fn pixel_color(&self, pixel_coord: Float2, uv: Float2, normal: Float3, depth: f32) -> Float4 {
-        let u_frac = uv.x - uv.x.floor();
-        let v_frac = uv.y - uv.y.floor();
-        let wscale = self.texture.width().saturating_sub(1);
-        let hscale = self.texture.height().saturating_sub(1);
-
-        let x = (u_frac * wscale as f32) as u32;
-        let y = (v_frac * hscale as f32) as u32;
-
-        let mut color = self.texture.get_pixel(x, y);
+        let mut color = self.texture.sample(uv.x, uv.y, self.filter);
         color.w = self.alpha;
 
         color
@@ -0,0 +1,57 @@
+use crate::types::texture::Texture;
+
+#[derive(Clone)]
+pub struct AnimatedTexture {
+    frames: Vec<Texture>,
+    durations: Vec<f32>, // seconds per frame
+    total_duration: f32,
+}
+
+impl AnimatedTexture {
+    pub fn new(frames: Vec<Texture>, durations: Vec<f32>) -> Self {
+        assert!(!frames.is_empty(), "Animated texture needs at least one frame");
+        assert_eq!(frames.len(), durations.len(), "Every frame needs a duration");
+
+        let total_duration = durations.iter().sum();
+        Self {
+            frames,
+            durations,
+            total_duration,
+        }
+    }
+
+    // Frame index at the given playback time, looping
+    pub fn frame_index_at(&self, time: f32) -> usize {
+        if self.total_duration <= 0.0 {
+            return 0;
+        }
+
+        let mut t = time.rem_euclid(self.total_duration);
+        for (i, duration) in self.durations.iter().enumerate() {
+            if t < *duration {
+                return i;
+            }
+            t -= duration;
+        }
+        self.frames.len() - 1
+    }
+
+    pub fn frame_at(&self, time: f32) -> &Texture {
+        &self.frames[self.frame_index_at(time)]
+    }
+
+    #[allow(dead_code)]
+    pub fn frame(&self, index: usize) -> &Texture {
+        &self.frames[index]
+    }
+
+    #[allow(dead_code)]
+    pub fn duration(&self, index: usize) -> f32 {
+        self.durations[index]
+    }
+
+    #[allow(dead_code)]
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+}
\ No newline at end of file
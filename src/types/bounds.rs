@@ -0,0 +1,132 @@
+use crate::{math::numerics::float3::Float3, types::transform::Transform};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Float3,
+    pub max: Float3,
+}
+
+impl Aabb {
+    // Inverted box that grows to the first point added
+    pub fn empty() -> Self {
+        Self {
+            min: Float3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Float3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn from_points(points: &[Float3]) -> Self {
+        let mut aabb = Self::empty();
+        for p in points {
+            aabb.encapsulate(*p);
+        }
+        aabb
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn encapsulate(&mut self, p: Float3) {
+        self.min = Float3::new(self.min.x.min(p.x), self.min.y.min(p.y), self.min.z.min(p.z));
+        self.max = Float3::new(self.max.x.max(p.x), self.max.y.max(p.y), self.max.z.max(p.z));
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        let mut result = *self;
+        result.encapsulate(other.min);
+        result.encapsulate(other.max);
+        result
+    }
+
+    pub fn center(&self) -> Float3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Float3 {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let s = self.size();
+        2.0 * (s.x * s.y + s.y * s.z + s.z * s.x)
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+            && self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+    pub fn corners(&self) -> [Float3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Float3::new(a.x, a.y, a.z), Float3::new(b.x, a.y, a.z),
+            Float3::new(a.x, b.y, a.z), Float3::new(b.x, b.y, a.z),
+            Float3::new(a.x, a.y, b.z), Float3::new(b.x, a.y, b.z),
+            Float3::new(a.x, b.y, b.z), Float3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    // World space box around the transformed corners
+    pub fn transformed(&self, transform: &Transform) -> Aabb {
+        let mut result = Aabb::empty();
+        for corner in self.corners() {
+            result.encapsulate(transform.to_world_point(corner));
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Float3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Float3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    // Ritter's approximation, within a few percent of the minimal sphere
+    pub fn from_points(points: &[Float3]) -> Self {
+        if points.is_empty() {
+            return Self::new(Float3::ZERO, 0.0);
+        }
+
+        let farthest_from = |p: Float3| {
+            *points.iter().max_by(|a, b| (**a - p).length().total_cmp(&(**b - p).length())).unwrap()
+        };
+        let a = farthest_from(points[0]);
+        let b = farthest_from(a);
+
+        let mut center = (a + b) * 0.5;
+        let mut radius = (b - a).length() * 0.5;
+
+        for p in points {
+            let d = (*p - center).length();
+            if d > radius {
+                let new_radius = (radius + d) * 0.5;
+                center = center + (*p - center) * ((new_radius - radius) / d);
+                radius = new_radius;
+            }
+        }
+
+        Self::new(center, radius)
+    }
+
+    // Radius is scaled by the largest axis scale, so the sphere stays conservative
+    pub fn transformed(&self, transform: &Transform) -> BoundingSphere {
+        let mut scale = transform.scale.x.abs().max(transform.scale.y.abs()).max(transform.scale.z.abs());
+        let mut parent = &transform.parent;
+        while let Some(p) = parent {
+            scale *= p.scale.x.abs().max(p.scale.y.abs()).max(p.scale.z.abs());
+            parent = &p.parent;
+        }
+        BoundingSphere::new(transform.to_world_point(self.center), self.radius * scale)
+    }
+}
\ No newline at end of file
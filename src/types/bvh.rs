@@ -0,0 +1,429 @@
+use std::sync::Arc;
+
+use crate::{ecs::world::{Entity, World}, math::numerics::float3::Float3, types::{bounds::Aabb, mesh::Mesh, mesh_renderer::MeshRenderer, ray::Ray, transform::Transform}};
+
+const SAH_BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+
+#[inline(always)]
+fn axis(v: Float3, a: usize) -> f32 {
+    match a {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+// Leaves have count > 0 and own primitives[first..first + count], inner nodes have children first and first + 1
+#[derive(Clone, Copy)]
+struct BvhNode {
+    bounds: Aabb,
+    first: u32,
+    count: u32,
+}
+
+// Binned SAH bounding volume hierarchy over anything with a box
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    primitives: Vec<u32>,
+    primitive_bounds: Vec<Aabb>,
+}
+
+impl Bvh {
+    pub fn build(bounds: &[Aabb]) -> Self {
+        let centroids: Vec<Float3> = bounds.iter().map(|b| b.center()).collect();
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(bounds.len() * 2),
+            primitives: (0..bounds.len() as u32).collect(),
+            primitive_bounds: bounds.to_vec(),
+        };
+
+        bvh.nodes.push(BvhNode { bounds: Aabb::empty(), first: 0, count: bounds.len() as u32 });
+        bvh.subdivide(0, bounds, &centroids);
+        bvh
+    }
+
+    fn subdivide(&mut self, node_index: usize, bounds: &[Aabb], centroids: &[Float3]) {
+        let node = self.nodes[node_index];
+        let range = node.first as usize..(node.first + node.count) as usize;
+
+        let mut node_bounds = Aabb::empty();
+        let mut centroid_bounds = Aabb::empty();
+        for p in &self.primitives[range.clone()] {
+            node_bounds = node_bounds.union(&bounds[*p as usize]);
+            centroid_bounds.encapsulate(centroids[*p as usize]);
+        }
+        self.nodes[node_index].bounds = node_bounds;
+
+        if range.len() <= MAX_LEAF_SIZE {
+            return;
+        }
+
+        // Cheapest split over all axes, cost = area * primitive count on each side
+        let mut best: Option<(usize, usize, f32)> = None;
+        for a in 0..3 {
+            let min = axis(centroid_bounds.min, a);
+            let extent = axis(centroid_bounds.max, a) - min;
+            if extent <= 0.0 {
+                continue;
+            }
+
+            let bin_of = |c: Float3| (((axis(c, a) - min) / extent * SAH_BINS as f32) as usize).min(SAH_BINS - 1);
+            let mut bins = [(Aabb::empty(), 0usize); SAH_BINS];
+            for p in &self.primitives[range.clone()] {
+                let bin = &mut bins[bin_of(centroids[*p as usize])];
+                bin.0 = bin.0.union(&bounds[*p as usize]);
+                bin.1 += 1;
+            }
+
+            let mut right_cost = [0.0; SAH_BINS];
+            let (mut right_box, mut right_count) = (Aabb::empty(), 0);
+            for i in (1..SAH_BINS).rev() {
+                right_box = right_box.union(&bins[i].0);
+                right_count += bins[i].1;
+                right_cost[i] = right_box.surface_area() * right_count as f32;
+            }
+
+            let (mut left_box, mut left_count) = (Aabb::empty(), 0);
+            for split in 1..SAH_BINS {
+                left_box = left_box.union(&bins[split - 1].0);
+                left_count += bins[split - 1].1;
+                let cost = left_box.surface_area() * left_count as f32 + right_cost[split];
+                if left_count > 0 && left_count < range.len() && best.is_none_or(|b| cost < b.2) {
+                    best = Some((a, split, cost));
+                }
+            }
+        }
+
+        let Some((a, split, cost)) = best else { return };
+        if cost >= node_bounds.surface_area() * range.len() as f32 {
+            return;
+        }
+
+        // Partition primitives in place by bin
+        let min = axis(centroid_bounds.min, a);
+        let extent = axis(centroid_bounds.max, a) - min;
+        let primitives = &mut self.primitives[range.clone()];
+        let mut left = 0;
+        for i in 0..primitives.len() {
+            let c = centroids[primitives[i] as usize];
+            if ((((axis(c, a) - min) / extent * SAH_BINS as f32) as usize).min(SAH_BINS - 1)) < split {
+                primitives.swap(i, left);
+                left += 1;
+            }
+        }
+
+        let first_child = self.nodes.len();
+        self.nodes.push(BvhNode { bounds: Aabb::empty(), first: node.first, count: left as u32 });
+        self.nodes.push(BvhNode { bounds: Aabb::empty(), first: node.first + left as u32, count: node.count - left as u32 });
+        self.nodes[node_index].first = first_child as u32;
+        self.nodes[node_index].count = 0;
+
+        self.subdivide(first_child, bounds, centroids);
+        self.subdivide(first_child + 1, bounds, centroids);
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::empty(), |n| n.bounds)
+    }
+
+    // Closest hit traversal. `hit` tests one primitive and returns its distance if it is closer than the given maximum.
+    pub fn raycast<F: FnMut(u32, f32) -> Option<f32>>(&self, ray: &Ray, max_distance: f32, mut hit: F) -> Option<f32> {
+        if self.primitives.is_empty() {
+            return None;
+        }
+
+        let mut closest = max_distance;
+        let mut found = false;
+        let mut stack = vec![0usize];
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.count > 0 {
+                for p in &self.primitives[node.first as usize..(node.first + node.count) as usize] {
+                    if let Some(t) = hit(*p, closest) {
+                        closest = t;
+                        found = true;
+                    }
+                }
+                continue;
+            }
+
+            // Visit the nearer child first so the farther one is usually rejected
+            let (a, b) = (node.first as usize, node.first as usize + 1);
+            let ta = ray.intersect_aabb(&self.nodes[a].bounds, closest);
+            let tb = ray.intersect_aabb(&self.nodes[b].bounds, closest);
+            match (ta, tb) {
+                (Some(ta), Some(tb)) if ta <= tb => stack.extend([b, a]),
+                (Some(_), Some(_)) => stack.extend([a, b]),
+                (Some(_), None) => stack.push(a),
+                (None, Some(_)) => stack.push(b),
+                (None, None) => {}
+            }
+        }
+
+        if found { Some(closest) } else { None }
+    }
+
+    // Calls `f` for every primitive whose box overlaps `aabb`
+    pub fn query_aabb<F: FnMut(u32)>(&self, aabb: &Aabb, mut f: F) {
+        if self.primitives.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0usize];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.intersects(aabb) {
+                continue;
+            }
+            if node.count > 0 {
+                for p in &self.primitives[node.first as usize..(node.first + node.count) as usize] {
+                    if self.primitive_bounds[*p as usize].intersects(aabb) {
+                        f(*p);
+                    }
+                }
+            }
+            else {
+                stack.extend([node.first as usize, node.first as usize + 1]);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TriangleHit {
+    pub triangle: usize,
+    pub barycentrics: Float3, // weights of the triangle's three vertices
+    pub distance: f32,
+}
+
+// Triangle BVH in model space, keeps its own copy of the triangle corners
+pub struct MeshBvh {
+    bvh: Bvh,
+    triangles: Vec<[Float3; 3]>,
+}
+
+impl MeshBvh {
+    pub fn build(mesh: &Mesh) -> Self {
+        let triangles: Vec<[Float3; 3]> = mesh.indices.chunks_exact(3)
+            .map(|t| [mesh.vertices[t[0] as usize], mesh.vertices[t[1] as usize], mesh.vertices[t[2] as usize]])
+            .collect();
+        let bounds: Vec<Aabb> = triangles.iter().map(|t| Aabb::from_points(t)).collect();
+
+        Self { bvh: Bvh::build(&bounds), triangles }
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.bvh.bounds()
+    }
+
+    pub fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<TriangleHit> {
+        let mut result = None;
+        self.bvh.raycast(ray, max_distance, |p, closest| {
+            let [a, b, c] = self.triangles[p as usize];
+            let (t, barycentrics) = ray.intersect_triangle(a, b, c).filter(|(t, _)| *t < closest)?;
+            result = Some(TriangleHit { triangle: p as usize, barycentrics, distance: t });
+            Some(t)
+        });
+        result
+    }
+
+    // Triangles whose bounds overlap the box
+    #[allow(dead_code)]
+    pub fn query_aabb(&self, aabb: &Aabb) -> Vec<usize> {
+        let mut result = Vec::new();
+        self.bvh.query_aabb(aabb, |p| result.push(p as usize));
+        result
+    }
+}
+
+// Picking only needs the entity, the rest is for tools
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct RayHit {
+    pub entity: Entity,
+    pub triangle: usize,
+    pub barycentrics: Float3,
+    pub distance: f32,
+    pub point: Float3,
+}
+
+struct SceneEntry {
+    entity: Entity,
+    transform: Transform,
+    bvh: Arc<MeshBvh>,
+}
+
+// BVH over the world bounds of the drawable entities. It is a snapshot, rebuild it after entities move.
+pub struct SceneBvh {
+    bvh: Bvh,
+    entries: Vec<SceneEntry>,
+}
+
+impl SceneBvh {
+    pub fn build(world: &World) -> Self {
+        let transforms = world.read::<Transform>();
+        let mut entries = Vec::new();
+        let mut bounds = Vec::new();
+
+        for (entity, renderer) in world.write::<MeshRenderer>().iter_mut() {
+            let Some(transform) = transforms.get(entity) else { continue };
+            let bvh = renderer.bvh();
+            bounds.push(bvh.bounds().transformed(transform));
+            entries.push(SceneEntry { entity, transform: transform.clone(), bvh });
+        }
+
+        Self { bvh: Bvh::build(&bounds), entries }
+    }
+
+    // Closest triangle hit along a world space ray
+    pub fn raycast(&self, ray: &Ray, max_distance: f32) -> Option<RayHit> {
+        let mut result = None;
+
+        self.bvh.raycast(ray, max_distance, |p, closest| {
+            let entry = &self.entries[p as usize];
+
+            // A non-normalized local direction keeps distances in world units
+            let origin = entry.transform.to_local_point(ray.origin);
+            let local_ray = Ray { origin, direction: entry.transform.to_local_point(ray.at(1.0)) - origin };
+
+            let hit = entry.bvh.raycast(&local_ray, closest)?;
+            result = Some(RayHit {
+                entity: entry.entity,
+                triangle: hit.triangle,
+                barycentrics: hit.barycentrics,
+                distance: hit.distance,
+                point: ray.at(hit.distance),
+            });
+            Some(hit.distance)
+        });
+
+        result
+    }
+
+    // Entities whose world bounds overlap the box
+    #[allow(dead_code)]
+    pub fn query_aabb(&self, aabb: &Aabb) -> Vec<Entity> {
+        let mut result = Vec::new();
+        self.bvh.query_aabb(aabb, |p| result.push(self.entries[p as usize].entity));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::core::assets;
+    use crate::shaders::{shader_base::Shader, texture_shader::TextureShader};
+    use crate::types::{handle::Handle, mesh_builder, texture::Texture};
+    use crate::math::numerics::float4::Float4;
+
+    fn random_point(rng: &mut StdRng, bounds: &Aabb) -> Float3 {
+        let size = bounds.max - bounds.min;
+        bounds.min + Float3::new(size.x * rng.random::<f32>(), size.y * rng.random::<f32>(), size.z * rng.random::<f32>())
+    }
+
+    fn brute_force(triangles: &[[Float3; 3]], ray: &Ray, max_distance: f32) -> Option<(usize, f32)> {
+        triangles.iter().enumerate()
+            .filter_map(|(i, [a, b, c])| ray.intersect_triangle(*a, *b, *c).map(|(t, _)| (i, t)))
+            .filter(|(_, t)| *t < max_distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    #[test]
+    fn mesh_raycast_matches_brute_force_on_the_dragon() {
+        let dragon = assets::load_mesh("dragon.obj");
+        let bvh = MeshBvh::build(&dragon);
+        let bounds = bvh.bounds();
+        let radius = (bounds.max - bounds.min).length();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let mut hits = 0;
+        for i in 0..300 {
+            // From outside towards a point inside the bounds, and some from inside the mesh
+            let target = random_point(&mut rng, &bounds);
+            let origin = if i % 4 == 0 {
+                random_point(&mut rng, &bounds)
+            } else {
+                let direction = Float3::new(rng.random::<f32>() - 0.5, rng.random::<f32>() - 0.5, rng.random::<f32>() - 0.5);
+                bounds.center() + direction.normalize() * radius
+            };
+            let ray = Ray::new(origin, target - origin);
+            let max_distance = if i % 3 == 0 { (target - origin).length() } else { f32::INFINITY };
+
+            let expected = brute_force(&bvh.triangles, &ray, max_distance);
+            let hit = bvh.raycast(&ray, max_distance);
+            assert_eq!(hit.is_some(), expected.is_some(), "ray {i}");
+            if let (Some(hit), Some((triangle, distance))) = (hit, expected) {
+                hits += 1;
+                // Triangles sharing the hit point can come out in either order
+                assert!((hit.distance - distance).abs() < 1e-5, "ray {i}: {} != {distance}", hit.distance);
+                if hit.triangle != triangle {
+                    let [a, b, c] = bvh.triangles[triangle];
+                    assert!((ray.intersect_triangle(a, b, c).unwrap().0 - hit.distance).abs() < 1e-5);
+                }
+                let [a, b, c] = bvh.triangles[hit.triangle];
+                let w = hit.barycentrics;
+                assert!((a * w.x + b * w.y + c * w.z - ray.at(hit.distance)).length() < 1e-4);
+            }
+        }
+        assert!(hits > 150, "{hits}");
+    }
+
+    #[test]
+    fn query_matches_brute_force() {
+        let bvh = MeshBvh::build(&mesh_builder::icosphere(1.0, 3));
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..50 {
+            let center = random_point(&mut rng, &bvh.bounds());
+            let half = Float3::ONE * rng.random::<f32>() * 0.3;
+            let query = Aabb { min: center - half, max: center + half };
+
+            let mut found = bvh.query_aabb(&query);
+            found.sort();
+            let expected: Vec<usize> = bvh.triangles.iter().enumerate()
+                .filter(|(_, t)| Aabb::from_points(*t).intersects(&query))
+                .map(|(i, _)| i)
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn scene_raycast_finds_the_closest_entity_in_world_units() {
+        let mut world = World::new();
+        let texture = Handle::new(Texture::from_texels(1, 1, vec![Float4::ONE]));
+        let mut spawn = |name: &str, z: f32, scale: f32| {
+            let mut transform = Transform::default();
+            transform.set_position(Float3::new(0.0, 0.0, z));
+            transform.scale = Float3::ONE * scale;
+            let shader = Shader::TextureShader(TextureShader::new(texture.clone(), false));
+            world.spawn()
+                .with(crate::ecs::components::Name::new(name))
+                .with(transform)
+                .with(MeshRenderer::new(Handle::new(mesh_builder::cube(1.0)), shader))
+                .id()
+        };
+        let far = spawn("Far", 10.0, 1.0);
+        let near = spawn("Near", 5.0, 2.0);
+
+        let scene = SceneBvh::build(&world);
+        let ray = Ray::new(Float3::ZERO, Float3::UNIT_Z);
+        let hit = scene.raycast(&ray, f32::INFINITY).unwrap();
+        assert_eq!(hit.entity, near);
+        // The near cube is scaled to 2 units, its front face is at 4
+        assert!((hit.distance - 4.0).abs() < 1e-5 && (hit.point - Float3::new(0.0, 0.0, 4.0)).length() < 1e-5, "{hit:?}");
+
+        assert!(scene.raycast(&ray, 3.9).is_none());
+        let beside = Ray::new(Float3::new(0.0, 1.5, 0.0), Float3::UNIT_Z);
+        assert_eq!(scene.raycast(&beside, f32::INFINITY).map(|h| h.entity), None);
+        let past_near = Ray::new(Float3::new(0.0, 0.0, 7.0), Float3::UNIT_Z);
+        assert_eq!(scene.raycast(&past_near, f32::INFINITY).map(|h| h.entity), Some(far));
+
+        let around_far = Aabb { min: Float3::new(-1.0, -1.0, 9.0), max: Float3::new(1.0, 1.0, 11.0) };
+        assert_eq!(scene.query_aabb(&around_far), [far]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,89 @@
+use std::{ops::Deref, sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}}};
+
+// Shared storage behind a handle, replaced as a whole when an asset is reloaded
+pub struct AssetSlot<T> {
+    value: Mutex<Arc<T>>,
+    version: AtomicU64,
+}
+
+// Reference-counted asset handle. Reads go through a cached Arc, so sampling a texture
+// per pixel never takes a lock. Call `refresh` once per frame to pick up reloads.
+pub struct Handle<T> {
+    slot: Arc<AssetSlot<T>>,
+    value: Arc<T>,
+    version: u64,
+}
+
+impl<T> Handle<T> {
+    pub fn new(value: T) -> Self {
+        let value = Arc::new(value);
+        Self {
+            slot: Arc::new(AssetSlot {
+                value: Mutex::new(value.clone()),
+                version: AtomicU64::new(0),
+            }),
+            value,
+            version: 0,
+        }
+    }
+
+    // Swap in new contents for every handle sharing this slot
+    pub fn replace(&self, value: T) {
+        *self.slot.value.lock().unwrap() = Arc::new(value);
+        self.slot.version.fetch_add(1, Ordering::Release);
+    }
+
+    // Returns true if the asset changed since the last refresh
+    pub fn refresh(&mut self) -> bool {
+        let version = self.slot.version.load(Ordering::Acquire);
+        if version == self.version {
+            return false;
+        }
+
+        self.value = self.slot.value.lock().unwrap().clone();
+        self.version = version;
+        true
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    // Identifies the shared asset, equal for all handles that ptr_eq
+    pub fn id(&self) -> usize {
+        Arc::as_ptr(&self.slot) as usize
+    }
+
+    pub fn ptr_eq(&self, other: &Handle<T>) -> bool {
+        Arc::ptr_eq(&self.slot, &other.slot)
+    }
+
+    // Number of handles sharing this asset
+    pub fn ref_count(&self) -> usize {
+        Arc::strong_count(&self.slot)
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            slot: self.slot.clone(),
+            value: self.value.clone(),
+            version: self.version,
+        }
+    }
+}
+
+impl<T> Deref for Handle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> From<T> for Handle<T> {
+    fn from(value: T) -> Self {
+        Handle::new(value)
+    }
+}
\ No newline at end of file
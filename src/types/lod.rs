@@ -0,0 +1,235 @@
+use crate::core::time;
+use crate::types::{bounds::BoundingSphere, handle::Handle, mesh::Mesh};
+
+pub struct LodLevel {
+    pub mesh: Handle<Mesh>,
+    // Smallest projected size (bounding sphere height / screen height) this level is used at
+    pub screen_size: f32,
+}
+
+impl LodLevel {
+    pub fn new(mesh: Handle<Mesh>, screen_size: f32) -> Self {
+        Self { mesh, screen_size }
+    }
+}
+
+// Levels ordered from most to least detailed
+pub struct LodGroup {
+    pub levels: Vec<LodLevel>,
+    // Relative margin around each switch point, so a model sitting on a threshold doesn't pop back and forth
+    pub hysteresis: f32,
+    // Seconds to dither between the old and new level after a switch, 0 switches instantly
+    pub crossfade_duration: f32,
+
+    generated: Option<(usize, f32, f32)>, // `generate` arguments, so the group can be described and rebuilt
+    current: usize,
+    previous: Option<(usize, f64)>, // level faded out and when, in scene time
+    bounds: BoundingSphere,
+}
+
+impl LodGroup {
+    pub fn new(levels: Vec<LodLevel>) -> Self {
+        assert!(!levels.is_empty(), "LOD group needs at least one level");
+        let bounds = levels[0].mesh.bounding_sphere();
+        Self {
+            levels,
+            hysteresis: 0.1,
+            crossfade_duration: 0.0,
+            generated: None,
+            current: 0,
+            previous: None,
+            bounds,
+        }
+    }
+
+    // Builds levels by simplifying `mesh`, each one keeping `ratio` of the previous level's triangles.
+    // Level i is used while the model covers at least `first_screen_size * ratio^i` of the screen height.
+    pub fn generate(mesh: Handle<Mesh>, level_count: usize, ratio: f32, first_screen_size: f32) -> Self {
+        let first = LodLevel::new(mesh.clone(), if level_count == 1 { 0.0 } else { first_screen_size });
+        let mut levels = vec![first];
+        levels.extend(Self::simplified_levels(&mesh, level_count, ratio, first_screen_size));
+
+        let mut group = Self::new(levels);
+        group.generated = Some((level_count, ratio, first_screen_size));
+        group
+    }
+
+    // Levels after the first for `generate`
+    fn simplified_levels(mesh: &Mesh, level_count: usize, ratio: f32, first_screen_size: f32) -> Vec<LodLevel> {
+        let mut triangles = mesh.triangle_count() as f32;
+        let mut screen_size = first_screen_size;
+
+        (1..level_count)
+            .map(|i| {
+                triangles *= ratio;
+                screen_size *= ratio;
+                let size = if i == level_count - 1 { 0.0 } else { screen_size };
+                LodLevel::new(Handle::new(mesh.simplify(triangles.max(1.0) as usize)), size)
+            })
+            .collect()
+    }
+
+    // (level_count, ratio, first_screen_size) for groups made by `generate`
+    pub fn generated_from(&self) -> Option<(usize, f32, f32)> {
+        self.generated
+    }
+
+    #[allow(dead_code)]
+    pub fn with_hysteresis(mut self, hysteresis: f32) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_crossfade(mut self, duration: f32) -> Self {
+        self.crossfade_duration = duration;
+        self
+    }
+
+    // Bounding sphere of the most detailed level, in model space
+    pub fn bounds(&self) -> BoundingSphere {
+        self.bounds
+    }
+
+    pub fn current_mesh(&self) -> &Handle<Mesh> {
+        &self.levels[self.current].mesh
+    }
+
+    // Level being faded out and how far the fade has progressed (0..1)
+    pub fn fading(&self) -> Option<(&Handle<Mesh>, f32)> {
+        self.fading_at(time::now())
+    }
+
+    fn fading_at(&self, now: f64) -> Option<(&Handle<Mesh>, f32)> {
+        let (level, start) = self.previous?;
+        let t = (now - start) as f32 / self.crossfade_duration;
+        Some((&self.levels[level].mesh, t.min(1.0)))
+    }
+
+    // Picks the level for this frame from the projected size, returns the selected level
+    pub fn update(&mut self, screen_size: f32) -> usize {
+        self.update_at(screen_size, time::now())
+    }
+
+    fn update_at(&mut self, screen_size: f32, now: f64) -> usize {
+        for level in self.levels.iter_mut().skip(1) {
+            level.mesh.refresh();
+        }
+
+        // Generated levels are rebuilt from a reloaded source, hand-made ones reload on their own
+        if self.levels[0].mesh.refresh() {
+            self.bounds = self.levels[0].mesh.bounding_sphere();
+            if let Some((level_count, ratio, first_screen_size)) = self.generated {
+                let levels = Self::simplified_levels(&self.levels[0].mesh, level_count, ratio, first_screen_size);
+                self.levels.truncate(1);
+                self.levels.extend(levels);
+            }
+        }
+
+        let mut level = self.current;
+        while level > 0 && screen_size >= self.levels[level - 1].screen_size * (1.0 + self.hysteresis) {
+            level -= 1;
+        }
+        while level + 1 < self.levels.len() && screen_size < self.levels[level].screen_size * (1.0 - self.hysteresis) {
+            level += 1;
+        }
+
+        if level != self.current {
+            self.previous = if self.crossfade_duration > 0.0 { Some((self.current, now)) } else { None };
+            self.current = level;
+        }
+        else if self.previous.is_some_and(|(_, start)| (now - start) as f32 >= self.crossfade_duration) {
+            self.previous = None;
+        }
+
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::mesh_builder;
+
+    fn three_levels() -> LodGroup {
+        LodGroup::new(vec![
+            LodLevel::new(Handle::new(mesh_builder::cube(1.0)), 0.5),
+            LodLevel::new(Handle::new(mesh_builder::cube(1.0)), 0.25),
+            LodLevel::new(Handle::new(mesh_builder::cube(1.0)), 0.0),
+        ])
+    }
+
+    #[test]
+    fn hysteresis_keeps_a_model_on_a_threshold_from_flip_flopping() {
+        let mut group = three_levels().with_hysteresis(0.1);
+        assert_eq!(group.update_at(0.6, 0.0), 0);
+
+        // Level 0 is left below 0.45 and only taken back above 0.55
+        let sizes = [0.46, 0.44, 0.46, 0.44, 0.5, 0.54, 0.46];
+        let levels: Vec<usize> = sizes.iter().map(|s| group.update_at(*s, 0.0)).collect();
+        assert_eq!(levels, [0, 1, 1, 1, 1, 1, 1]);
+        assert_eq!(group.update_at(0.56, 0.0), 0);
+
+        // Big changes skip levels in one update
+        assert_eq!(group.update_at(0.01, 0.0), 2);
+        assert_eq!(group.update_at(1.0, 0.0), 0);
+
+        // Without a margin the switch points are exact
+        let mut group = three_levels().with_hysteresis(0.0);
+        let levels: Vec<usize> = [0.5, 0.4999, 0.5, 0.25, 0.2499].iter().map(|s| group.update_at(*s, 0.0)).collect();
+        assert_eq!(levels, [0, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn crossfade_expires_after_its_duration() {
+        let mut group = three_levels().with_crossfade(0.5);
+        group.update_at(0.6, 10.0);
+        assert!(group.fading_at(10.0).is_none());
+
+        assert_eq!(group.update_at(0.3, 10.0), 1);
+        let (mesh, t) = group.fading_at(10.2).unwrap();
+        assert!(mesh.ptr_eq(&group.levels[0].mesh));
+        assert!((t - 0.4).abs() < 1e-5);
+
+        assert_eq!(group.update_at(0.3, 10.4), 1);
+        assert!(group.fading_at(10.4).is_some());
+        assert_eq!(group.fading_at(11.0).unwrap().1, 1.0);
+        assert_eq!(group.update_at(0.3, 10.5), 1);
+        assert!(group.fading_at(10.5).is_none());
+
+        // A switch during a fade starts over from the level being left
+        group.update_at(0.1, 20.0);
+        group.update_at(0.6, 20.1);
+        let (mesh, t) = group.fading_at(20.1).unwrap();
+        assert!(mesh.ptr_eq(&group.levels[2].mesh) && t == 0.0);
+
+        // Without a duration levels switch instantly
+        let mut group = three_levels();
+        group.update_at(0.1, 0.0);
+        assert!(group.fading_at(0.0).is_none());
+    }
+
+    #[test]
+    fn generated_levels_follow_a_reloaded_source() {
+        let source = Handle::new(mesh_builder::icosphere(1.0, 2));
+        let mut group = LodGroup::generate(source.clone(), 3, 0.5, 0.4);
+        let triangles = |group: &LodGroup| group.levels.iter().map(|l| l.mesh.triangle_count()).collect::<Vec<_>>();
+        assert_eq!(triangles(&group), [320, 160, 80]);
+        let old_level = group.levels[1].mesh.clone();
+
+        source.replace(mesh_builder::icosphere(2.0, 3));
+        group.update_at(1.0, 0.0);
+        assert_eq!(triangles(&group), [1280, 640, 320]);
+        assert!((group.bounds().radius - 2.0).abs() < 1e-3);
+        assert!(!group.levels[1].mesh.ptr_eq(&old_level));
+        assert_eq!(group.levels.iter().map(|l| l.screen_size).collect::<Vec<_>>(), [0.4, 0.2, 0.0]);
+
+        // Hand-made levels stay as they are
+        let source = Handle::new(mesh_builder::cube(1.0));
+        let mut group = LodGroup::new(vec![LodLevel::new(source.clone(), 0.5), LodLevel::new(Handle::new(mesh_builder::cube(0.5)), 0.0)]);
+        source.replace(mesh_builder::cube(3.0));
+        group.update_at(1.0, 0.0);
+        assert_eq!(group.levels.len(), 2);
+        assert_eq!(group.levels[1].mesh.vertices, mesh_builder::cube(0.5).vertices);
+    }
+}
\ No newline at end of file
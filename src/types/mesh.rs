@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-use crate::math::numerics::{float2::Float2, float3::Float3};
+use crate::math::numerics::{float2::Float2, float3::Float3, float4::Float4};
 
 #[derive(Clone)]
 pub struct Mesh {
@@ -8,6 +8,7 @@ pub struct Mesh {
     pub indices: Vec<u32>,
     pub normals: Vec<Float3>,
     pub uvs: Vec<Float2>,
+    pub colors: Vec<Float4>, // optional, empty if the source has no vertex colors
 }
 
 impl Mesh {
@@ -17,6 +18,7 @@ impl Mesh {
             indices,
             normals,
             uvs,
+            colors: Vec::new(),
         }
     }
 
@@ -26,6 +28,7 @@ impl Mesh {
             indices: Vec::new(),
             normals: Vec::new(),
             uvs: Vec::new(),
+            colors: Vec::new(),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+use crate::{math::numerics::{float2::Float2, float3::Float3}, types::mesh::Mesh};
+
+// Procedural primitives. All shapes are centered on the origin with y up.
+// Front faces wind so that (b - a) x (c - a) points along the outward normal,
+// which is what the rasterizer sees as clockwise on screen.
+
+pub struct MeshBuilder {
+    vertices: Vec<Float3>,
+    normals: Vec<Float3>,
+    uvs: Vec<Float2>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    pub fn new() -> Self {
+        Self {
+            vertices: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    pub fn add_vertex(&mut self, position: Float3, normal: Float3, uv: Float2) -> u32 {
+        self.vertices.push(position);
+        self.normals.push(normal.normalize());
+        self.uvs.push(uv);
+        (self.vertices.len() - 1) as u32
+    }
+
+    // Winding is picked so the face points along its vertex normals, zero-area triangles
+    // (e.g. at the poles of a sphere) are dropped
+    pub fn add_triangle(&mut self, a: u32, b: u32, c: u32) {
+        let pa = self.vertices[a as usize];
+        let pb = self.vertices[b as usize];
+        let pc = self.vertices[c as usize];
+
+        let face_normal = (pb - pa).cross(pc - pa);
+        if face_normal == Float3::ZERO {
+            return;
+        }
+
+        let vertex_normal = self.normals[a as usize] + self.normals[b as usize] + self.normals[c as usize];
+        if face_normal.dot(vertex_normal) >= 0.0 {
+            self.indices.extend_from_slice(&[a, b, c]);
+        } else {
+            self.indices.extend_from_slice(&[a, c, b]);
+        }
+    }
+
+    pub fn add_quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.add_triangle(a, b, c);
+        self.add_triangle(a, c, d);
+    }
+
+    // (cols + 1) x (rows + 1) vertices from f(col, row) -> (position, normal, uv)
+    pub fn add_grid<F>(&mut self, cols: u32, rows: u32, f: F)
+    where
+        F: Fn(u32, u32) -> (Float3, Float3, Float2),
+    {
+        let start = self.vertices.len() as u32;
+        for row in 0..=rows {
+            for col in 0..=cols {
+                let (position, normal, uv) = f(col, row);
+                self.add_vertex(position, normal, uv);
+            }
+        }
+
+        let stride = cols + 1;
+        for row in 0..rows {
+            for col in 0..cols {
+                let i00 = start + row * stride + col;
+                let i10 = i00 + 1;
+                let i01 = i00 + stride;
+                let i11 = i01 + 1;
+                self.add_quad(i00, i10, i11, i01);
+            }
+        }
+    }
+
+    // Flat disk in the xz plane at height y, facing up or down
+    pub fn add_disk(&mut self, radius: f32, y: f32, segments: u32, facing_up: bool) {
+        let normal = if facing_up { Float3::UNIT_Y } else { Float3::ZERO - Float3::UNIT_Y };
+        let center = self.add_vertex(Float3::new(0.0, y, 0.0), normal, Float2::HALF);
+
+        for i in 0..=segments {
+            let angle = i as f32 / segments as f32 * TAU;
+            let (sin, cos) = angle.sin_cos();
+            let ring = self.add_vertex(
+                Float3::new(cos * radius, y, sin * radius),
+                normal,
+                Float2::new(0.5 + cos * 0.5, 0.5 + sin * 0.5),
+            );
+            if i > 0 {
+                self.add_triangle(center, ring - 1, ring);
+            }
+        }
+    }
+
+    pub fn build(self) -> Mesh {
+        Mesh::new(self.vertices, self.indices, self.normals, self.uvs)
+    }
+}
+
+// Plane in the xz plane facing +y
+#[allow(dead_code)]
+pub fn plane(width: f32, depth: f32, subdivisions_x: u32, subdivisions_z: u32) -> Mesh {
+    let mut b = MeshBuilder::new();
+    b.add_grid(subdivisions_x, subdivisions_z, |col, row| {
+        let u = col as f32 / subdivisions_x as f32;
+        let v = row as f32 / subdivisions_z as f32;
+        (Float3::new((u - 0.5) * width, 0.0, (v - 0.5) * depth), Float3::UNIT_Y, Float2::new(u, v))
+    });
+    b.build()
+}
+
+#[allow(dead_code)]
+pub fn cube(size: f32) -> Mesh {
+    box_mesh(Float3::new(size, size, size))
+}
+
+// Axis aligned box, every face has its own vertices and a full 0..1 uv square
+#[allow(dead_code)]
+pub fn box_mesh(size: Float3) -> Mesh {
+    let half = size * 0.5;
+    let mut b = MeshBuilder::new();
+
+    // (normal, u axis, v axis)
+    let faces = [
+        (Float3::UNIT_X, Float3::UNIT_Z, Float3::UNIT_Y),
+        (Float3::ZERO - Float3::UNIT_X, Float3::UNIT_Z, Float3::UNIT_Y),
+        (Float3::UNIT_Y, Float3::UNIT_X, Float3::UNIT_Z),
+        (Float3::ZERO - Float3::UNIT_Y, Float3::UNIT_X, Float3::UNIT_Z),
+        (Float3::UNIT_Z, Float3::UNIT_X, Float3::UNIT_Y),
+        (Float3::ZERO - Float3::UNIT_Z, Float3::UNIT_X, Float3::UNIT_Y),
+    ];
+
+    let scale = |v: Float3| Float3::new(v.x * half.x, v.y * half.y, v.z * half.z);
+
+    for (normal, u_axis, v_axis) in faces {
+        b.add_grid(1, 1, |col, row| {
+            let u = col as f32;
+            let v = row as f32;
+            let position = scale(normal + u_axis * (u * 2.0 - 1.0) + v_axis * (v * 2.0 - 1.0));
+            (position, normal, Float2::new(u, v))
+        });
+    }
+    b.build()
+}
+
+#[allow(dead_code)]
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Mesh {
+    let mut b = MeshBuilder::new();
+    b.add_grid(segments, rings, |col, row| {
+        let u = col as f32 / segments as f32;
+        let v = row as f32 / rings as f32;
+        let normal = sphere_point(u, v, row == 0 || row == rings);
+        (normal * radius, normal, Float2::new(u, v))
+    });
+    b.build()
+}
+
+// v = 0 is the south pole, poles are snapped so their triangles collapse exactly
+fn sphere_point(u: f32, v: f32, pole: bool) -> Float3 {
+    let longitude = u * TAU;
+    let latitude = (v - 0.5) * PI;
+    let ring = if pole { 0.0 } else { latitude.cos() };
+    Float3::new(longitude.cos() * ring, latitude.sin(), longitude.sin() * ring)
+}
+
+// Subdivided icosahedron. Vertices are shared, so the uv seam wraps around once.
+#[allow(dead_code)]
+pub fn icosphere(radius: f32, subdivisions: u32) -> Mesh {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let mut points: Vec<Float3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ].iter().map(|(x, y, z)| Float3::new(*x, *y, *z).normalize()).collect();
+
+    let mut faces: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints = HashMap::<(u32, u32), u32>::new();
+        let mut midpoint = |a: u32, b: u32, points: &mut Vec<Float3>| -> u32 {
+            let key = (a.min(b), a.max(b));
+            *midpoints.entry(key).or_insert_with(|| {
+                points.push(((points[a as usize] + points[b as usize]) * 0.5).normalize());
+                (points.len() - 1) as u32
+            })
+        };
+
+        let mut next = Vec::with_capacity(faces.len() * 4);
+        for [a, b, c] in faces {
+            let ab = midpoint(a, b, &mut points);
+            let bc = midpoint(b, c, &mut points);
+            let ca = midpoint(c, a, &mut points);
+            next.extend_from_slice(&[[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+        faces = next;
+    }
+
+    let mut b = MeshBuilder::new();
+    for p in &points {
+        let u = 0.5 + p.z.atan2(p.x) / TAU;
+        let v = 0.5 + p.y.asin() / PI;
+        b.add_vertex(*p * radius, *p, Float2::new(u, v));
+    }
+    for [i0, i1, i2] in faces {
+        b.add_triangle(i0, i1, i2);
+    }
+    b.build()
+}
+
+// Capped cylinder along y
+#[allow(dead_code)]
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> Mesh {
+    let half = height * 0.5;
+    let mut b = MeshBuilder::new();
+
+    b.add_grid(segments, 1, |col, row| {
+        let u = col as f32 / segments as f32;
+        let (sin, cos) = (u * TAU).sin_cos();
+        let y = if row == 0 { -half } else { half };
+        (Float3::new(cos * radius, y, sin * radius), Float3::new(cos, 0.0, sin), Float2::new(u, row as f32))
+    });
+    b.add_disk(radius, half, segments, true);
+    b.add_disk(radius, -half, segments, false);
+    b.build()
+}
+
+// Cone along y with the base at -height / 2 and the tip at +height / 2
+#[allow(dead_code)]
+pub fn cone(radius: f32, height: f32, segments: u32) -> Mesh {
+    let half = height * 0.5;
+    let mut b = MeshBuilder::new();
+
+    b.add_grid(segments, 1, |col, row| {
+        let u = col as f32 / segments as f32;
+        let (sin, cos) = (u * TAU).sin_cos();
+        let normal = Float3::new(cos * height, radius, sin * height);
+        let position = if row == 0 {
+            Float3::new(cos * radius, -half, sin * radius)
+        } else {
+            Float3::new(0.0, half, 0.0)
+        };
+        (position, normal, Float2::new(u, row as f32))
+    });
+    b.add_disk(radius, -half, segments, false);
+    b.build()
+}
+
+// Torus in the xz plane
+#[allow(dead_code)]
+pub fn torus(major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> Mesh {
+    let mut b = MeshBuilder::new();
+    b.add_grid(major_segments, minor_segments, |col, row| {
+        let u = col as f32 / major_segments as f32;
+        let v = row as f32 / minor_segments as f32;
+        let (sin_u, cos_u) = (u * TAU).sin_cos();
+        let (sin_v, cos_v) = (v * TAU).sin_cos();
+
+        let normal = Float3::new(cos_u * cos_v, sin_v, sin_u * cos_v);
+        let center = Float3::new(cos_u * major_radius, 0.0, sin_u * major_radius);
+        (center + normal * minor_radius, normal, Float2::new(u, v))
+    });
+    b.build()
+}
+
+// Cylinder of the given height with a hemisphere on each end, total height is height + 2 * radius
+#[allow(dead_code)]
+pub fn capsule(radius: f32, height: f32, segments: u32, hemisphere_rings: u32) -> Mesh {
+    let half = height * 0.5;
+    let rows = hemisphere_rings * 2 + 1;
+    let mut b = MeshBuilder::new();
+
+    b.add_grid(segments, rows, |col, row| {
+        let u = col as f32 / segments as f32;
+        // Bottom hemisphere goes from the south pole to the equator, the row after it
+        // starts the top hemisphere, the quad row in between is the cylinder
+        let (v, offset) = if row <= hemisphere_rings {
+            (row as f32 / hemisphere_rings as f32 * 0.5, -half)
+        } else {
+            ((row - 1) as f32 / hemisphere_rings as f32 * 0.5, half)
+        };
+        let normal = sphere_point(u, v, row == 0 || row == rows);
+        let position = normal * radius + Float3::new(0.0, offset, 0.0);
+        (position, normal, Float2::new(u, row as f32 / rows as f32))
+    });
+    b.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_valid(mesh: &Mesh, vertex_count: usize, index_count: usize) {
+        assert_eq!(mesh.vertices.len(), vertex_count);
+        assert_eq!(mesh.normals.len(), vertex_count);
+        assert_eq!(mesh.uvs.len(), vertex_count);
+        assert_eq!(mesh.indices.len(), index_count);
+        assert!(mesh.indices.iter().all(|i| (*i as usize) < vertex_count));
+    }
+
+    // Normals and the winding of every triangle both point away from the shape's center
+    fn assert_normals_point_away_from(mesh: &Mesh, center_of: impl Fn(Float3) -> Float3) {
+        for (p, n) in mesh.vertices.iter().zip(&mesh.normals) {
+            assert!((n.length() - 1.0).abs() < 1e-4);
+            assert!(n.dot(*p - center_of(*p)) > 0.0, "normal {n:?} at {p:?} points inward");
+        }
+        assert_front_faces_point_away_from(mesh, center_of);
+    }
+
+    // Front faces have (b - a) x (c - a) pointing out. Checked against the geometry rather than the
+    // vertex normals `add_triangle` winds by.
+    fn assert_front_faces_point_away_from(mesh: &Mesh, center_of: impl Fn(Float3) -> Float3) {
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| mesh.vertices[i as usize]);
+            let centroid = (a + b + c) / 3.0;
+            let face_normal = (b - a).cross(c - a);
+            assert!(face_normal.dot(centroid - center_of(centroid)) > 0.0, "triangle at {centroid:?} faces inward");
+        }
+    }
+
+    #[test]
+    fn plane_counts_and_normals() {
+        let mesh = plane(2.0, 1.0, 4, 3);
+        assert_valid(&mesh, 5 * 4, 4 * 3 * 6);
+        assert!(mesh.normals.iter().all(|n| *n == Float3::UNIT_Y));
+        assert_front_faces_point_away_from(&mesh, |p| p - Float3::UNIT_Y);
+    }
+
+    #[test]
+    #[should_panic(expected = "faces inward")]
+    fn inverted_winding_is_caught() {
+        let mut mesh = cube(1.0);
+        for tri in mesh.indices.chunks_exact_mut(3) {
+            tri.swap(1, 2);
+        }
+        assert_front_faces_point_away_from(&mesh, |_| Float3::ZERO);
+    }
+
+    #[test]
+    fn box_counts_and_normals() {
+        let mesh = box_mesh(Float3::new(1.0, 2.0, 3.0));
+        assert_valid(&mesh, 24, 36);
+        assert_normals_point_away_from(&mesh, |_| Float3::ZERO);
+    }
+
+    #[test]
+    fn uv_sphere_counts_and_normals() {
+        let mesh = uv_sphere(0.5, 16, 8);
+        assert_valid(&mesh, 17 * 9, 6 * 16 * 7);
+        assert_normals_point_away_from(&mesh, |_| Float3::ZERO);
+    }
+
+    #[test]
+    fn icosphere_counts_and_normals() {
+        for subdivisions in 0..3 {
+            let mesh = icosphere(1.0, subdivisions);
+            let faces = 20 * 4usize.pow(subdivisions);
+            assert_valid(&mesh, 10 * 4usize.pow(subdivisions) + 2, faces * 3);
+            assert_normals_point_away_from(&mesh, |_| Float3::ZERO);
+        }
+    }
+
+    #[test]
+    fn cylinder_counts_and_normals() {
+        let mesh = cylinder(0.5, 2.0, 12);
+        assert_valid(&mesh, 2 * 13 + 2 * 14, 6 * 12 + 2 * 3 * 12);
+        assert_normals_point_away_from(&mesh, |p| Float3::new(0.0, p.y * 0.5, 0.0));
+    }
+
+    #[test]
+    fn cone_counts_and_normals() {
+        let mesh = cone(0.5, 1.0, 12);
+        assert_valid(&mesh, 2 * 13 + 14, 3 * 12 + 3 * 12);
+        assert_normals_point_away_from(&mesh, |_| Float3::ZERO);
+    }
+
+    #[test]
+    fn torus_counts_and_normals() {
+        let mesh = torus(1.0, 0.25, 24, 12);
+        assert_valid(&mesh, 25 * 13, 6 * 24 * 12);
+        assert_normals_point_away_from(&mesh, |p| Float3::new(p.x, 0.0, p.z).normalize());
+    }
+
+    #[test]
+    fn capsule_counts_and_normals() {
+        let mesh = capsule(0.5, 1.0, 16, 4);
+        assert_valid(&mesh, 17 * 10, 12 * 16 * 4);
+        assert_normals_point_away_from(&mesh, |p| Float3::new(0.0, p.y.clamp(-0.5, 0.5), 0.0));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,601 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::{math::numerics::{float2::Float2, float3::Float3, float4::Float4}, types::{bounds::{Aabb, BoundingSphere}, mesh::Mesh, transform::Transform}};
+
+fn position_key(p: Float3) -> [u32; 3] {
+    // +0.0 so that -0.0 and 0.0 end up in the same group
+    [(p.x + 0.0).to_bits(), (p.y + 0.0).to_bits(), (p.z + 0.0).to_bits()]
+}
+
+impl Mesh {
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn face_normal(&self, triangle: usize) -> Float3 {
+        let a = self.vertices[self.indices[triangle * 3] as usize];
+        let b = self.vertices[self.indices[triangle * 3 + 1] as usize];
+        let c = self.vertices[self.indices[triangle * 3 + 2] as usize];
+        (b - a).cross(c - a).normalize()
+    }
+
+    #[allow(dead_code)]
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_points(&self.vertices)
+    }
+
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere::from_points(&self.vertices)
+    }
+
+    // Every triangle gets its own three vertices with the face normal
+    #[allow(dead_code)]
+    pub fn compute_flat_normals(&mut self) {
+        let mut result = Mesh::empty();
+        for t in 0..self.triangle_count() {
+            let normal = self.face_normal(t);
+            for k in 0..3 {
+                let v = self.indices[t * 3 + k] as usize;
+                result.indices.push(result.vertices.len() as u32);
+                result.vertices.push(self.vertices[v]);
+                result.normals.push(normal);
+                result.uvs.push(self.uvs.get(v).copied().unwrap_or_default());
+                if let Some(c) = self.colors.get(v) {
+                    result.colors.push(*c);
+                }
+            }
+        }
+        *self = result;
+    }
+
+    // Angle-weighted vertex normals. Faces meeting at more than `crease_angle_degrees` don't
+    // smooth across each other, vertices on such a crease are split.
+    pub fn compute_smooth_normals(&mut self, crease_angle_degrees: f32) {
+        let tri_count = self.triangle_count();
+        let cos_crease = crease_angle_degrees.to_radians().cos();
+
+        let mut face_normals = Vec::with_capacity(tri_count);
+        let mut corner_angles = Vec::with_capacity(tri_count * 3);
+        for t in 0..tri_count {
+            face_normals.push(self.face_normal(t));
+            for k in 0..3 {
+                let p = self.vertices[self.indices[t * 3 + k] as usize];
+                let a = self.vertices[self.indices[t * 3 + (k + 1) % 3] as usize];
+                let b = self.vertices[self.indices[t * 3 + (k + 2) % 3] as usize];
+                let cos = (a - p).normalize().dot((b - p).normalize()).clamp(-1.0, 1.0);
+                corner_angles.push(cos.acos());
+            }
+        }
+
+        // Corners sharing a position, regardless of uv seams
+        let mut corners_at = HashMap::<[u32; 3], Vec<usize>>::new();
+        for (corner, index) in self.indices.iter().enumerate() {
+            corners_at.entry(position_key(self.vertices[*index as usize])).or_default().push(corner);
+        }
+
+        let mut result = Mesh::empty();
+        let mut lookup = HashMap::<(u32, [u32; 3]), u32>::new();
+
+        for corner in 0..self.indices.len() {
+            let v = self.indices[corner];
+            let own_normal = face_normals[corner / 3];
+
+            let mut sum = Float3::ZERO;
+            for other in &corners_at[&position_key(self.vertices[v as usize])] {
+                let other_normal = face_normals[other / 3];
+                if own_normal.dot(other_normal) >= cos_crease {
+                    sum += other_normal * corner_angles[*other];
+                }
+            }
+            let normal = sum.normalize();
+
+            let index = *lookup.entry((v, position_key(normal))).or_insert_with(|| {
+                result.vertices.push(self.vertices[v as usize]);
+                result.normals.push(normal);
+                result.uvs.push(self.uvs.get(v as usize).copied().unwrap_or_default());
+                if let Some(c) = self.colors.get(v as usize) {
+                    result.colors.push(*c);
+                }
+                (result.vertices.len() - 1) as u32
+            });
+            result.indices.push(index);
+        }
+
+        *self = result;
+    }
+
+    // Merge vertices whose position, normal, uv and color all lie within `tolerance`.
+    // Triangles that collapse are removed.
+    #[allow(dead_code)]
+    pub fn weld(&mut self, tolerance: f32) {
+        let cell_size = tolerance.max(f32::EPSILON);
+        let cell_of = |p: Float3| {
+            ((p.x / cell_size).floor() as i64, (p.y / cell_size).floor() as i64, (p.z / cell_size).floor() as i64)
+        };
+
+        let mut grid = HashMap::<(i64, i64, i64), Vec<u32>>::new();
+        let mut remap = Vec::with_capacity(self.vertices.len());
+        let mut result = Mesh::empty();
+        let has_colors = !self.colors.is_empty();
+
+        for v in 0..self.vertices.len() {
+            let p = self.vertices[v];
+            let n = self.normals.get(v).copied().unwrap_or_default();
+            let uv = self.uvs.get(v).copied().unwrap_or_default();
+            let c = self.colors.get(v).copied().unwrap_or(Float4::ONE);
+            let (cx, cy, cz) = cell_of(p);
+
+            let mut found = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let Some(candidates) = grid.get(&(cx + dx, cy + dy, cz + dz)) else { continue };
+                        for candidate in candidates {
+                            let i = *candidate as usize;
+                            let same = (result.vertices[i] - p).length() <= tolerance
+                                && (result.normals[i] - n).length() <= tolerance
+                                && (result.uvs[i] - uv).length() <= tolerance
+                                && (!has_colors || (result.colors[i] - c).length() <= tolerance);
+                            if same {
+                                found = Some(*candidate);
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+
+            let index = found.unwrap_or_else(|| {
+                result.vertices.push(p);
+                result.normals.push(n);
+                result.uvs.push(uv);
+                if has_colors {
+                    result.colors.push(c);
+                }
+                let index = (result.vertices.len() - 1) as u32;
+                grid.entry((cx, cy, cz)).or_default().push(index);
+                index
+            });
+            remap.push(index);
+        }
+
+        for tri in self.indices.chunks_exact(3) {
+            let [a, b, c] = [remap[tri[0] as usize], remap[tri[1] as usize], remap[tri[2] as usize]];
+            if a != b && b != c && c != a {
+                result.indices.extend_from_slice(&[a, b, c]);
+            }
+        }
+
+        *self = result;
+    }
+
+    // Appends another mesh. Missing colors on either side are filled with white.
+    #[allow(dead_code)]
+    pub fn merge(&mut self, other: &Mesh) {
+        let offset = self.vertices.len() as u32;
+
+        if self.colors.is_empty() && !other.colors.is_empty() {
+            self.colors = vec![Float4::ONE; self.vertices.len()];
+        }
+        let fill_colors = !self.colors.is_empty() && other.colors.is_empty();
+
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices.extend(other.indices.iter().map(|i| i + offset));
+        self.normals.extend((0..other.vertices.len()).map(|i| other.normals.get(i).copied().unwrap_or_default()));
+        self.uvs.extend((0..other.vertices.len()).map(|i| other.uvs.get(i).copied().unwrap_or_default()));
+        if fill_colors {
+            self.colors.extend(std::iter::repeat_n(Float4::ONE, other.vertices.len()));
+        } else {
+            self.colors.extend_from_slice(&other.colors);
+        }
+    }
+
+    // Bakes a transform into the vertices. Normals use the inverse scale so they stay
+    // perpendicular under non-uniform scaling, mirroring transforms flip the winding.
+    #[allow(dead_code)]
+    pub fn apply_transform(&mut self, transform: &Transform) {
+        let scale = transform.scale;
+        let origin = transform.to_world_point(Float3::ZERO);
+        let axis = |v: Float3| transform.to_world_point(v) - origin;
+        let mirrored = axis(Float3::new(1.0, 0.0, 0.0)).cross(axis(Float3::new(0.0, 1.0, 0.0))).dot(axis(Float3::new(0.0, 0.0, 1.0))) < 0.0;
+
+        for v in self.vertices.iter_mut() {
+            *v = transform.to_world_point(*v);
+        }
+        for n in self.normals.iter_mut() {
+            *n = transform.to_world_vector(Float3::new(n.x / scale.x, n.y / scale.y, n.z / scale.z));
+        }
+
+        if mirrored {
+            for tri in self.indices.chunks_exact_mut(3) {
+                tri.swap(1, 2);
+            }
+        }
+    }
+
+    // Quadric error metric edge collapse (Garland & Heckbert) down to `target_triangles`, or the
+    // fewest triangles above it when a collapse would remove more than are left to go.
+    // Vertices keep their original positions (no optimal placement), so uvs and normals stay valid.
+    // Corners that are moved onto another position pick the vertex there with the closest uv and normal,
+    // which keeps uv seams intact. Open borders are weighted so they don't shrink.
+    pub fn simplify(&self, target_triangles: usize) -> Mesh {
+        let mut tris: Vec<[u32; 3]> = self.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+        let mut alive = vec![true; tris.len()];
+        let mut alive_count = tris.len();
+        if alive_count <= target_triangles {
+            return self.clone();
+        }
+
+        // Position groups tie together vertices that are split by uv or normal seams
+        let mut group_lookup = HashMap::<[u32; 3], usize>::new();
+        let mut vertex_group = Vec::with_capacity(self.vertices.len());
+        let mut group_members: Vec<Vec<u32>> = Vec::new();
+        let mut group_position: Vec<Float3> = Vec::new();
+        for (v, p) in self.vertices.iter().enumerate() {
+            let group = *group_lookup.entry(position_key(*p)).or_insert_with(|| {
+                group_members.push(Vec::new());
+                group_position.push(*p);
+                group_members.len() - 1
+            });
+            group_members[group].push(v as u32);
+            vertex_group.push(group);
+        }
+
+        let group_count = group_members.len();
+        let mut quadrics = vec![Quadric::default(); group_count];
+        let mut group_tris: Vec<Vec<usize>> = vec![Vec::new(); group_count];
+        let mut edge_use = HashMap::<(usize, usize), u32>::new();
+
+        for (t, tri) in tris.iter().enumerate() {
+            let g = tri.map(|v| vertex_group[v as usize]);
+            let p = g.map(|g| group_position[g]);
+            let cross = (p[1] - p[0]).cross(p[2] - p[0]);
+            let area = cross.length() * 0.5;
+            let normal = cross.normalize();
+
+            let q = Quadric::from_plane(normal, -normal.dot(p[0]), area as f64);
+            for k in 0..3 {
+                quadrics[g[k]].add(&q);
+                group_tris[g[k]].push(t);
+                let edge = (g[k].min(g[(k + 1) % 3]), g[k].max(g[(k + 1) % 3]));
+                *edge_use.entry(edge).or_default() += 1;
+            }
+        }
+
+        // Border edges get a plane perpendicular to the face through the edge
+        const BORDER_WEIGHT: f64 = 1000.0;
+        for tri in &tris {
+            let g = tri.map(|v| vertex_group[v as usize]);
+            let p = g.map(|g| group_position[g]);
+            let normal = (p[1] - p[0]).cross(p[2] - p[0]).normalize();
+            for k in 0..3 {
+                let (a, b) = (g[k], g[(k + 1) % 3]);
+                if edge_use[&(a.min(b), a.max(b))] != 1 {
+                    continue;
+                }
+                let edge = group_position[b] - group_position[a];
+                let border_normal = edge.cross(normal).normalize();
+                let weight = BORDER_WEIGHT * edge.dot(edge) as f64;
+                let q = Quadric::from_plane(border_normal, -border_normal.dot(group_position[a]), weight);
+                quadrics[a].add(&q);
+                quadrics[b].add(&q);
+            }
+        }
+
+        let mut version = vec![0u32; group_count];
+        let mut removed = vec![false; group_count];
+        let mut heap = BinaryHeap::new();
+
+        let push_edge = |heap: &mut BinaryHeap<Collapse>, quadrics: &[Quadric], version: &[u32], a: usize, b: usize| {
+            let mut q = quadrics[a];
+            q.add(&quadrics[b]);
+            let cost_keep_b = q.error(group_position[b]);
+            let cost_keep_a = q.error(group_position[a]);
+            let (keep, remove, cost) = if cost_keep_b <= cost_keep_a { (b, a, cost_keep_b) } else { (a, b, cost_keep_a) };
+            heap.push(Collapse { cost, keep, remove, keep_version: version[keep], remove_version: version[remove] });
+        };
+
+        // Sorted so the result doesn't depend on hash order
+        let mut edges: Vec<(usize, usize)> = edge_use.keys().copied().collect();
+        edges.sort_unstable();
+        for (a, b) in edges {
+            push_edge(&mut heap, &quadrics, &version, a, b);
+        }
+
+        let attribute_distance = |a: u32, b: u32| {
+            let uv = self.uvs.get(a as usize).copied().unwrap_or_default() - self.uvs.get(b as usize).copied().unwrap_or_default();
+            let n = self.normals.get(a as usize).copied().unwrap_or_default() - self.normals.get(b as usize).copied().unwrap_or_default();
+            uv.dot(uv) + n.dot(n)
+        };
+
+        while alive_count > target_triangles {
+            let Some(collapse) = heap.pop() else { break };
+            let (keep, remove) = (collapse.keep, collapse.remove);
+            if removed[keep] || removed[remove] || version[keep] != collapse.keep_version || version[remove] != collapse.remove_version {
+                continue;
+            }
+
+            // Reject collapses that would flip a surviving triangle
+            let mut flips = false;
+            for &t in &group_tris[remove] {
+                if !alive[t] {
+                    continue;
+                }
+                let g = tris[t].map(|v| vertex_group[v as usize]);
+                if g.contains(&keep) {
+                    continue;
+                }
+                let old = g.map(|g| group_position[g]);
+                let new = g.map(|g| if g == remove { group_position[keep] } else { group_position[g] });
+                let old_normal = (old[1] - old[0]).cross(old[2] - old[0]);
+                let new_normal = (new[1] - new[0]).cross(new[2] - new[0]);
+                if old_normal.dot(new_normal) <= 0.0 {
+                    flips = true;
+                    break;
+                }
+            }
+            if flips {
+                continue;
+            }
+
+            // Triangles along the edge disappear, stop before going below the target
+            let collapsing = group_tris[remove].iter().filter(|t| alive[**t] && tris[**t].iter().any(|v| vertex_group[*v as usize] == keep)).count();
+            if alive_count - collapsing < target_triangles {
+                break;
+            }
+
+            let moved: Vec<usize> = group_tris[remove].iter().copied().filter(|t| alive[*t]).collect();
+            for t in moved {
+                let g = tris[t].map(|v| vertex_group[v as usize]);
+                if g.contains(&keep) {
+                    alive[t] = false;
+                    alive_count -= 1;
+                    continue;
+                }
+                for k in 0..3 {
+                    if g[k] == remove {
+                        let old = tris[t][k];
+                        tris[t][k] = *group_members[keep].iter()
+                            .min_by(|a, b| attribute_distance(old, **a).total_cmp(&attribute_distance(old, **b)))
+                            .unwrap();
+                    }
+                }
+                group_tris[keep].push(t);
+            }
+
+            let q = quadrics[remove];
+            quadrics[keep].add(&q);
+            removed[remove] = true;
+            version[keep] += 1;
+
+            let mut neighbors = HashSet::new();
+            for &t in &group_tris[keep] {
+                if alive[t] {
+                    for v in tris[t] {
+                        neighbors.insert(vertex_group[v as usize]);
+                    }
+                }
+            }
+            neighbors.remove(&keep);
+            for n in neighbors {
+                push_edge(&mut heap, &quadrics, &version, keep, n);
+            }
+        }
+
+        // Compact the vertices that are still referenced
+        let mut result = Mesh::empty();
+        let mut remap = HashMap::<u32, u32>::new();
+        for (t, tri) in tris.iter().enumerate() {
+            if !alive[t] {
+                continue;
+            }
+            for v in tri {
+                let index = *remap.entry(*v).or_insert_with(|| {
+                    result.vertices.push(self.vertices[*v as usize]);
+                    result.normals.push(self.normals.get(*v as usize).copied().unwrap_or_default());
+                    result.uvs.push(self.uvs.get(*v as usize).copied().unwrap_or(Float2::ZERO));
+                    if let Some(c) = self.colors.get(*v as usize) {
+                        result.colors.push(*c);
+                    }
+                    (result.vertices.len() - 1) as u32
+                });
+                result.indices.push(index);
+            }
+        }
+        result
+    }
+}
+
+// Symmetric 4x4 error quadric, upper triangle
+#[derive(Clone, Copy, Default)]
+struct Quadric {
+    m: [f64; 10],
+}
+
+impl Quadric {
+    fn from_plane(n: Float3, d: f32, weight: f64) -> Self {
+        let (a, b, c, d) = (n.x as f64, n.y as f64, n.z as f64, d as f64);
+        Self {
+            m: [a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d].map(|v| v * weight),
+        }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for i in 0..10 {
+            self.m[i] += other.m[i];
+        }
+    }
+
+    fn error(&self, p: Float3) -> f64 {
+        let (x, y, z) = (p.x as f64, p.y as f64, p.z as f64);
+        let m = &self.m;
+        m[0] * x * x + 2.0 * m[1] * x * y + 2.0 * m[2] * x * z + 2.0 * m[3] * x
+            + m[4] * y * y + 2.0 * m[5] * y * z + 2.0 * m[6] * y
+            + m[7] * z * z + 2.0 * m[8] * z
+            + m[9]
+    }
+}
+
+struct Collapse {
+    cost: f64,
+    keep: usize,
+    remove: usize,
+    keep_version: u32,
+    remove_version: u32,
+}
+
+// Min-heap on cost
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::resource_helper;
+    use crate::types::mesh_builder;
+
+    // Indices in range and no triangle with two corners at the same position
+    fn assert_no_degenerates(mesh: &Mesh) {
+        assert!(mesh.indices.iter().all(|i| (*i as usize) < mesh.vertices.len()));
+        assert_eq!(mesh.normals.len(), mesh.vertices.len());
+        assert_eq!(mesh.uvs.len(), mesh.vertices.len());
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| position_key(mesh.vertices[i as usize]));
+            assert!(a != b && b != c && c != a, "{tri:?}");
+        }
+    }
+
+    // Eight corners shared by all faces, without normals or uvs
+    fn welded_cube() -> Mesh {
+        let mut mesh = mesh_builder::cube(1.0);
+        mesh.normals.fill(Float3::ZERO);
+        mesh.uvs.fill(Float2::ZERO);
+        mesh.weld(1e-4);
+        mesh
+    }
+
+    #[test]
+    fn simplify_never_goes_below_the_target() {
+        // Every collapse on a closed mesh removes two triangles
+        assert_eq!(mesh_builder::cube(1.0).simplify(1).triangle_count(), 2);
+        assert_eq!(mesh_builder::cube(1.0).simplify(12).triangle_count(), 12);
+
+        let sphere = mesh_builder::icosphere(1.0, 2);
+        for target in [1, 7, 50, 100] {
+            let simplified = sphere.simplify(target);
+            assert!((target..=target + 1).contains(&simplified.triangle_count()), "{target}: {}", simplified.triangle_count());
+            assert_no_degenerates(&simplified);
+        }
+
+        let small_world = resource_helper::load_mesh("src/assets/SmallWorld.obj");
+        assert!(small_world.simplify(6).triangle_count() >= 6);
+    }
+
+    #[test]
+    fn simplified_dragon_stays_valid() {
+        let dragon = resource_helper::load_mesh("src/assets/dragon.obj");
+        for target in [dragon.triangle_count() / 2, dragon.triangle_count() / 10, 20] {
+            let simplified = dragon.simplify(target);
+            assert!(simplified.triangle_count() >= target && simplified.triangle_count() <= target + 2);
+            assert_no_degenerates(&simplified);
+        }
+    }
+
+    #[test]
+    fn smooth_normals_split_at_creases() {
+        let cube = welded_cube();
+        assert_eq!(cube.vertices.len(), 8);
+
+        // Faces meet at 90°: below that every corner is split into its three faces
+        let mut creased = cube.clone();
+        creased.compute_smooth_normals(60.0);
+        assert_eq!(creased.vertices.len(), 24);
+        for n in &creased.normals {
+            assert_eq!([n.x.abs(), n.y.abs(), n.z.abs()].iter().filter(|c| **c > 0.999).count(), 1, "{n:?}");
+        }
+
+        // Above it the corners stay shared and point diagonally outward
+        let mut smooth = cube;
+        smooth.compute_smooth_normals(100.0);
+        assert_eq!(smooth.vertices.len(), 8);
+        for (p, n) in smooth.vertices.iter().zip(&smooth.normals) {
+            assert!((*n - p.normalize()).length() < 1e-4, "{p:?} {n:?}");
+        }
+    }
+
+    #[test]
+    fn weld_joins_a_split_quad() {
+        let p = [Float3::new(0.0, 0.0, 0.0), Float3::new(1.0, 0.0, 0.0), Float3::new(1.0, 1.0, 0.0), Float3::new(0.0, 1.0, 0.0)];
+        let nudge = Float3::new(0.00005, 0.0, 0.0);
+        // Two triangles with their own corners, the shared ones slightly apart, and one that collapses
+        let vertices = vec![p[0], p[1], p[2], p[0] + nudge, p[2], p[3], p[0], p[1], p[1] + nudge];
+        let normals = vec![Float3::UNIT_Z; vertices.len()];
+        let uvs = vertices.iter().map(|v| Float2::new(v.x.round(), v.y.round())).collect();
+        let mut quad = Mesh::new(vertices, (0..9).collect(), normals, uvs);
+
+        quad.weld(1e-3);
+        assert_eq!(quad.vertices.len(), 4);
+        assert_eq!(quad.indices, [0, 1, 2, 0, 2, 3]);
+
+        // Different uvs on the same position are a seam and stay apart
+        let mut seam = quad.clone();
+        seam.merge(&quad);
+        seam.uvs[4] = Float2::new(0.5, 0.5);
+        seam.weld(1e-3);
+        assert_eq!(seam.vertices.len(), 5);
+        assert_eq!(seam.triangle_count(), 4);
+    }
+
+    #[test]
+    fn mirroring_transforms_keep_faces_outward() {
+        let mut transform = Transform::default();
+        transform.scale = Float3::new(-1.0, 2.0, 1.0);
+        let mut cube = mesh_builder::cube(1.0);
+        cube.apply_transform(&transform);
+
+        let center = cube.aabb().center();
+        for t in 0..cube.triangle_count() {
+            let tri = &cube.indices[t * 3..t * 3 + 3];
+            let centroid = tri.iter().fold(Float3::ZERO, |sum, i| sum + cube.vertices[*i as usize]) / 3.0;
+            assert!(cube.face_normal(t).dot(centroid - center) > 0.0, "triangle {t}");
+            // Normals follow the faces under the non-uniform scale
+            for i in tri {
+                assert!(cube.normals[*i as usize].normalize().dot(cube.face_normal(t)) > 0.999);
+            }
+        }
+    }
+
+    #[test]
+    fn merge_offsets_indices_and_fills_colors() {
+        let mut a = mesh_builder::cube(1.0);
+        let mut b = mesh_builder::cube(1.0);
+        b.colors = vec![Float4::new(1.0, 0.0, 0.0, 1.0); b.vertices.len()];
+        let count = a.vertices.len();
+
+        a.merge(&b);
+        assert_eq!(a.vertices.len(), count * 2);
+        assert_eq!(a.triangle_count(), 24);
+        assert_eq!(a.indices[36], b.indices[0] + count as u32);
+        assert_eq!(a.colors.len(), count * 2);
+        assert_eq!(a.colors[0], Float4::ONE);
+        assert_eq!(a.colors[count], Float4::new(1.0, 0.0, 0.0, 1.0));
+    }
+}
\ No newline at end of file
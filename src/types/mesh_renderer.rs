@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use crate::{math::numerics::float4::Float4, rasterizer::rasterizer_point::RasterizerPoint, shaders::shader_base::Shader, types::{bounds::BoundingSphere, bvh::MeshBvh, handle::Handle, lod::LodGroup, mesh::Mesh, transform::Transform}};
+
+// Edges drawn when the shader renders as wireframe, or over the filled triangles with `overlay`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wireframe {
+    pub color: Float4,
+    pub width: f32, // pixels
+    pub overlay: bool,
+}
+
+impl Default for Wireframe {
+    fn default() -> Self {
+        Self { color: Float4::ONE, width: 1.0, overlay: false }
+    }
+}
+
+// Component that makes an entity drawable, along with the per-frame state the rasterizer keeps for it
+pub struct MeshRenderer {
+    pub mesh: Handle<Mesh>,
+    pub shader: Shader,
+    pub lod: Option<LodGroup>, // if set, replaces `mesh` with the level picked each frame
+    pub wireframe: Wireframe,
+    pub rasterizer_points: Vec<RasterizerPoint>,
+    pub fade_points: Vec<RasterizerPoint>, // previous LOD level during a crossfade
+
+    bounds: Option<((usize, u64), BoundingSphere)>, // keyed by mesh id and version
+    bvh: Option<((usize, u64), Arc<MeshBvh>)>,
+}
+
+impl MeshRenderer {
+    pub fn new(mesh: Handle<Mesh>, shader: Shader) -> Self {
+        Self {
+            mesh,
+            shader,
+            lod: None,
+            wireframe: Wireframe::default(),
+            rasterizer_points: Vec::new(),
+            fade_points: Vec::new(),
+            bounds: None,
+            bvh: None,
+        }
+    }
+
+    // Mesh space bounding sphere, only recomputed when the mesh changes.
+    // Renderers with LODs use the bounds of the most detailed level.
+    pub fn local_bounds(&mut self) -> BoundingSphere {
+        if let Some(lod) = &self.lod {
+            return lod.bounds();
+        }
+
+        let key = (self.mesh.id(), self.mesh.version());
+        match self.bounds {
+            Some((cached_key, sphere)) if cached_key == key => sphere,
+            _ => {
+                let sphere = self.mesh.bounding_sphere();
+                self.bounds = Some((key, sphere));
+                sphere
+            }
+        }
+    }
+
+    pub fn world_bounds(&mut self, transform: &Transform) -> BoundingSphere {
+        self.local_bounds().transformed(transform)
+    }
+
+    // Triangle BVH of the current mesh, rebuilt only when the mesh changes
+    pub fn bvh(&mut self) -> Arc<MeshBvh> {
+        let key = (self.mesh.id(), self.mesh.version());
+        match &self.bvh {
+            Some((cached_key, bvh)) if *cached_key == key => bvh.clone(),
+            _ => {
+                let bvh = Arc::new(MeshBvh::build(&self.mesh));
+                self.bvh = Some((key, bvh.clone()));
+                bvh
+            }
+        }
+    }
+
+    pub fn set_lod(&mut self, lod: LodGroup) {
+        self.mesh = lod.current_mesh().clone();
+        self.lod = Some(lod);
+    }
+
+    pub fn with_lod(mut self, lod: LodGroup) -> Self {
+        self.set_lod(lod);
+        self
+    }
+}
\ No newline at end of file
@@ -1,5 +1,13 @@
 pub mod texture;
+pub mod animated_texture;
+pub mod handle;
 pub mod transform;
+pub mod bounds;
+pub mod ray;
+pub mod bvh;
 pub mod mesh;
-pub mod model;
+pub mod mesh_builder;
+pub mod mesh_processing;
+pub mod lod;
+pub mod mesh_renderer;
 pub mod scene;
\ No newline at end of file
@@ -0,0 +1,71 @@
+use crate::{math::numerics::float3::Float3, types::bounds::Aabb};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Float3,
+    pub direction: Float3,
+}
+
+impl Ray {
+    pub fn new(origin: Float3, direction: Float3) -> Self {
+        Self { origin, direction: direction.normalize() }
+    }
+
+    pub fn at(&self, t: f32) -> Float3 {
+        self.origin + self.direction * t
+    }
+
+    // Slab test, returns the entry distance (0 if the origin is inside)
+    pub fn intersect_aabb(&self, aabb: &Aabb, max_t: f32) -> Option<f32> {
+        let mut t_min: f32 = 0.0;
+        let mut t_max = max_t;
+
+        for (origin, dir, min, max) in [
+            (self.origin.x, self.direction.x, aabb.min.x, aabb.max.x),
+            (self.origin.y, self.direction.y, aabb.min.y, aabb.max.y),
+            (self.origin.z, self.direction.z, aabb.min.z, aabb.max.z),
+        ] {
+            let inv = 1.0 / dir;
+            let (t0, t1) = ((min - origin) * inv, (max - origin) * inv);
+            let (t0, t1) = if inv < 0.0 { (t1, t0) } else { (t0, t1) };
+            // NaN from 0 * inf keeps the current interval
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
+
+    // Möller-Trumbore, hits both faces. Returns the distance and the barycentric weights of a, b and c.
+    pub fn intersect_triangle(&self, a: Float3, b: Float3, c: Float3) -> Option<(f32, Float3)> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = self.direction.cross(edge2);
+        let det = edge1.dot(p);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let s = self.origin - a;
+        let u = s.dot(p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = s.cross(edge1);
+        let v = self.direction.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(q) * inv_det;
+        if t < 0.0 {
+            return None;
+        }
+        Some((t, Float3::new(1.0 - u - v, u, v)))
+    }
+}
\ No newline at end of file
@@ -2,6 +2,26 @@ use crate::rasterizer::render_target::RenderTarget;
 
 pub trait Scene {
     fn resize(&mut self, new_width: u32, new_height: u32, render_target: &mut RenderTarget);
+    // Once per frame with the frame's delta time
     fn update(&mut self, delta_time: f32, render_target: &mut RenderTarget);
     fn start(&mut self, render_target: &mut RenderTarget);
+
+    // Runs zero or more times per frame at the engine's fixed timestep, before `update`.
+    // Read input edges like `is_down` in `update`, a frame may run no fixed step or several.
+    fn fixed_update(&mut self, _fixed_delta_time: f32) {}
+
+    // Last call of a frame. `alpha` (0..1) is how far the frame is past the last fixed step,
+    // for interpolating state between the previous and the current step.
+    fn render(&mut self, _alpha: f32, _render_target: &mut RenderTarget) {}
+
+    // Called once when the scene is removed or the engine shuts down
+    fn exit(&mut self) {}
+
+    // The window gained or lost focus, or another scene was pushed on top of this one / popped off it
+    fn focus_changed(&mut self, _focused: bool) {}
+
+    // Overlays (a pause menu) are drawn over the scene below them instead of replacing it
+    fn is_overlay(&self) -> bool {
+        false
+    }
 }
\ No newline at end of file
@@ -1,23 +1,291 @@
-use crate::math::{mathi, numerics::float4::Float4};
+use half::f16;
+use once_cell::sync::Lazy;
+
+use crate::math::{mathf, mathi, numerics::float4::Float4};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+// How texels are stored. Fetches always decode to Float4:
+// Rgba8Srgb decodes to linear like a GPU sRGB format, R8 gives (r, 0, 0, 1), Rg16F gives (r, g, 0, 1)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextureFormat {
+    Rgba8,
+    Rgba8Srgb,
+    #[allow(dead_code)]
+    R8,
+    #[allow(dead_code)]
+    Rg16F,
+    Rgba32F,
+}
+
+impl TextureFormat {
+    pub fn bytes_per_texel(self) -> usize {
+        match self {
+            TextureFormat::Rgba8 | TextureFormat::Rgba8Srgb => 4,
+            TextureFormat::R8 => 1,
+            TextureFormat::Rg16F => 4,
+            TextureFormat::Rgba32F => 16,
+        }
+    }
+}
+
+// Tiled stores 4x4 blocks contiguously, so the four taps of a bilinear fetch share a cache line
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextureLayout {
+    Linear,
+    Tiled,
+}
+
+// How shaders filter between texels
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum TextureFilter {
+    #[default]
+    Nearest,
+    Bilinear,
+}
+
+const TILE_SHIFT: u32 = 2;
+const TILE_SIZE: u32 = 1 << TILE_SHIFT;
+const TILE_MASK: u32 = TILE_SIZE - 1;
+
+#[derive(Clone)]
+enum TexelData {
+    Rgba8(Vec<[u8; 4]>),
+    R8(Vec<u8>),
+    Rg16F(Vec<[f16; 2]>),
+    Rgba32F(Vec<Float4>),
+}
+
+static SRGB_TO_LINEAR: Lazy<[f32; 256]> = Lazy::new(|| {
+    std::array::from_fn(|i| mathf::srgb_to_linear(i as f32 / 255.0))
+});
 
 #[derive(Clone)]
 pub struct Texture {
-    image: Vec<Float4>,
+    data: TexelData,
+    format: TextureFormat,
+    layout: TextureLayout,
     width: u32,
     height: u32,
+    tiles_per_row: u32,
+    color_space: ColorSpace,
 }
 
 impl Texture {
-    pub fn new(image: Vec<Vec<Float4>>) -> Self {
+    // Row-major texels, stored as Rgba32F
+    pub fn from_texels(width: u32, height: u32, texels: Vec<Float4>) -> Self {
+        assert_eq!(texels.len(), (width * height) as usize, "Texel count does not match texture size");
         Texture {
-            image: image.clone().into_iter().flatten().collect(),
-            width: image.len() as u32,
-            height: image[0].len() as u32,
+            data: TexelData::Rgba32F(texels),
+            format: TextureFormat::Rgba32F,
+            layout: TextureLayout::Linear,
+            width,
+            height,
+            tiles_per_row: width.div_ceil(TILE_SIZE),
+            color_space: ColorSpace::Srgb,
         }
     }
 
+    // Row-major 8-bit RGBA texels, without going through floats
+    pub fn from_rgba8(width: u32, height: u32, texels: Vec<[u8; 4]>) -> Self {
+        assert_eq!(texels.len(), (width * height) as usize, "Texel count does not match texture size");
+        Texture {
+            data: TexelData::Rgba8(texels),
+            format: TextureFormat::Rgba8,
+            layout: TextureLayout::Linear,
+            width,
+            height,
+            tiles_per_row: width.div_ceil(TILE_SIZE),
+            color_space: ColorSpace::Srgb,
+        }
+    }
+
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    // Format and layout loaded textures are kept in: 8-bit sRGB sources become Rgba8Srgb so filtering
+    // happens in linear, everything is tiled
+    pub fn compact(&self) -> Texture {
+        let format = match (self.format, self.color_space()) {
+            (TextureFormat::Rgba8, ColorSpace::Srgb) => TextureFormat::Rgba8Srgb,
+            (format, _) => format,
+        };
+        self.convert(format, TextureLayout::Tiled)
+    }
+
+    // Re-encode into another format and memory layout
+    pub fn convert(&self, format: TextureFormat, layout: TextureLayout) -> Texture {
+        let mut texels = Vec::with_capacity((self.width * self.height) as usize);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                texels.push(self.get_pixel(x, y));
+            }
+        }
+
+        // Rgba8Srgb fetches linear values, so the source has to be encoded back to sRGB first
+        let to_srgb = format == TextureFormat::Rgba8Srgb && self.color_space() == ColorSpace::Linear;
+        let from_srgb = format != TextureFormat::Rgba8Srgb && self.format == TextureFormat::Rgba8Srgb;
+
+        let mut result = Texture {
+            data: TexelData::Rgba32F(Vec::new()),
+            format,
+            layout,
+            width: self.width,
+            height: self.height,
+            tiles_per_row: self.width.div_ceil(TILE_SIZE),
+            color_space: if from_srgb { ColorSpace::Srgb } else { self.color_space },
+        };
+
+        let stored_len = result.stored_len();
+        let mut stored = vec![Float4::ZERO; stored_len];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut c = texels[(y * self.width + x) as usize];
+                if to_srgb || from_srgb {
+                    c = Float4::new(mathf::linear_to_srgb(c.x), mathf::linear_to_srgb(c.y), mathf::linear_to_srgb(c.z), c.w);
+                }
+                stored[result.index(x, y)] = c;
+            }
+        }
+
+        let unorm8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        result.data = match format {
+            TextureFormat::Rgba8 | TextureFormat::Rgba8Srgb => TexelData::Rgba8(
+                stored.iter().map(|c| [unorm8(c.x), unorm8(c.y), unorm8(c.z), unorm8(c.w)]).collect()
+            ),
+            TextureFormat::R8 => TexelData::R8(stored.iter().map(|c| unorm8(c.x)).collect()),
+            TextureFormat::Rg16F => TexelData::Rg16F(
+                stored.iter().map(|c| [f16::from_f32(c.x), f16::from_f32(c.y)]).collect()
+            ),
+            TextureFormat::Rgba32F => TexelData::Rgba32F(stored),
+        };
+        result
+    }
+
+    #[inline(always)]
+    fn index(&self, x: u32, y: u32) -> usize {
+        match self.layout {
+            TextureLayout::Linear => mathi::xy_to_index(x, y, self.width, self.height) as usize,
+            TextureLayout::Tiled => {
+                let tile = (y >> TILE_SHIFT) * self.tiles_per_row + (x >> TILE_SHIFT);
+                ((tile << (2 * TILE_SHIFT)) + ((y & TILE_MASK) << TILE_SHIFT) + (x & TILE_MASK)) as usize
+            }
+        }
+    }
+
+    // Tiled textures are padded to whole tiles
+    fn stored_len(&self) -> usize {
+        match self.layout {
+            TextureLayout::Linear => (self.width * self.height) as usize,
+            TextureLayout::Tiled => (self.tiles_per_row * self.height.div_ceil(TILE_SIZE) * TILE_SIZE * TILE_SIZE) as usize,
+        }
+    }
+
+    #[inline(always)]
     pub fn get_pixel(&self, x: u32, y: u32) -> Float4 {
-        self.image[mathi::xy_to_index(x, y, self.width, self.height) as usize]
+        let i = self.index(x, y);
+        match &self.data {
+            TexelData::Rgba32F(d) => d[i],
+            TexelData::Rgba8(d) => {
+                let [r, g, b, a] = d[i];
+                if self.format == TextureFormat::Rgba8Srgb {
+                    let lut = &*SRGB_TO_LINEAR;
+                    Float4::new(lut[r as usize], lut[g as usize], lut[b as usize], a as f32 / 255.0)
+                } else {
+                    Float4::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0)
+                }
+            }
+            TexelData::R8(d) => Float4::new(d[i] as f32 / 255.0, 0.0, 0.0, 1.0),
+            TexelData::Rg16F(d) => Float4::new(d[i][0].to_f32(), d[i][1].to_f32(), 0.0, 1.0),
+        }
+    }
+
+    // Texel converted to linear RGB, alpha is always linear
+    pub fn get_pixel_linear(&self, x: u32, y: u32) -> Float4 {
+        let c = self.get_pixel(x, y);
+        match self.color_space() {
+            ColorSpace::Linear => c,
+            ColorSpace::Srgb => Float4::new(mathf::srgb_to_linear(c.x), mathf::srgb_to_linear(c.y), mathf::srgb_to_linear(c.z), c.w),
+        }
+    }
+
+    // Texel encoded as sRGB, the space the renderer draws in. Rgba8Srgb returns its bytes without
+    // decoding them, linear textures are encoded.
+    #[inline(always)]
+    pub fn get_pixel_srgb(&self, x: u32, y: u32) -> Float4 {
+        if let TexelData::Rgba8(d) = &self.data && self.format == TextureFormat::Rgba8Srgb {
+            let [r, g, b, a] = d[self.index(x, y)];
+            return Float4::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0);
+        }
+        let c = self.get_pixel(x, y);
+        match self.color_space() {
+            ColorSpace::Srgb => c,
+            ColorSpace::Linear => Float4::new(mathf::linear_to_srgb(c.x), mathf::linear_to_srgb(c.y), mathf::linear_to_srgb(c.z), c.w),
+        }
+    }
+
+    // What shaders fetch, as sRGB with wrapping uv
+    #[inline(always)]
+    pub fn sample(&self, u: f32, v: f32, filter: TextureFilter) -> Float4 {
+        match filter {
+            TextureFilter::Nearest => self.sample_nearest(u, v),
+            TextureFilter::Bilinear => self.sample_bilinear(u, v),
+        }
+    }
+
+    // Nearest texel as sRGB, wrapping uv
+    #[inline(always)]
+    pub fn sample_nearest(&self, u: f32, v: f32) -> Float4 {
+        let x = ((u.rem_euclid(1.0) * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v.rem_euclid(1.0) * self.height as f32) as u32).min(self.height - 1);
+        self.get_pixel_srgb(x, y)
+    }
+
+    // Bilinear filter between the four nearest texels, blended in linear and returned as sRGB, wrapping uv
+    pub fn sample_bilinear(&self, u: f32, v: f32) -> Float4 {
+        let fx = u.rem_euclid(1.0) * self.width as f32 - 0.5;
+        let fy = v.rem_euclid(1.0) * self.height as f32 - 0.5;
+        let x0 = fx.floor();
+        let y0 = fy.floor();
+        let tx = fx - x0;
+        let ty = fy - y0;
+
+        let wrap = |v: i32, size: u32| v.rem_euclid(size as i32) as u32;
+        let x0i = wrap(x0 as i32, self.width);
+        let y0i = wrap(y0 as i32, self.height);
+        let x1i = wrap(x0 as i32 + 1, self.width);
+        let y1i = wrap(y0 as i32 + 1, self.height);
+
+        let top = self.get_pixel_linear(x0i, y0i) * (1.0 - tx) + self.get_pixel_linear(x1i, y0i) * tx;
+        let bottom = self.get_pixel_linear(x0i, y1i) * (1.0 - tx) + self.get_pixel_linear(x1i, y1i) * tx;
+        let c = top * (1.0 - ty) + bottom * ty;
+        Float4::new(mathf::linear_to_srgb(c.x), mathf::linear_to_srgb(c.y), mathf::linear_to_srgb(c.z), c.w)
+    }
+
+    // Color space of the values returned by get_pixel
+    pub fn color_space(&self) -> ColorSpace {
+        if self.format == TextureFormat::Rgba8Srgb { ColorSpace::Linear } else { self.color_space }
+    }
+
+    #[allow(dead_code)]
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    #[allow(dead_code)]
+    pub fn layout(&self) -> TextureLayout {
+        self.layout
+    }
+
+    #[allow(dead_code)]
+    pub fn size_in_bytes(&self) -> usize {
+        self.stored_len() * self.format.bytes_per_texel()
     }
 
     pub fn width(&self) -> u32 {
@@ -27,4 +295,118 @@ impl Texture {
     pub fn height(&self) -> u32 {
         self.height
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every texel different and on the 8-bit grid, so conversions through bytes are exact
+    fn gradient(width: u32, height: u32) -> Texture {
+        let texels = (0..width * height)
+            .map(|i| Float4::new((i % 256) as f32 / 255.0, ((i * 7) % 256) as f32 / 255.0, ((i * 13) % 256) as f32 / 255.0, 1.0 - (i % 3) as f32 / 255.0))
+            .collect();
+        Texture::from_texels(width, height, texels)
+    }
+
+    fn assert_same_texels(a: &Texture, b: &Texture, tolerance: f32) {
+        for y in 0..a.height() {
+            for x in 0..a.width() {
+                let (ca, cb) = (a.get_pixel(x, y), b.get_pixel(x, y));
+                assert!((ca - cb).length() <= tolerance, "({x}, {y}): {ca:?} != {cb:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn tiled_indexing_covers_partial_tiles() {
+        for (width, height) in [(5, 7), (1, 1), (4, 9), (13, 3)] {
+            let linear = gradient(width, height);
+            let tiled = linear.convert(TextureFormat::Rgba32F, TextureLayout::Tiled);
+            assert_same_texels(&linear, &tiled, 0.0);
+
+            // Every texel gets its own slot inside the padded storage
+            let mut indices: Vec<usize> = (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).map(|(x, y)| tiled.index(x, y)).collect();
+            assert!(indices.iter().all(|i| *i < tiled.stored_len()));
+            indices.sort();
+            indices.dedup();
+            assert_eq!(indices.len(), (width * height) as usize);
+            assert_eq!(tiled.stored_len(), (width.div_ceil(4) * height.div_ceil(4) * 16) as usize);
+
+            let back = tiled.convert(TextureFormat::Rgba32F, TextureLayout::Linear);
+            assert_same_texels(&linear, &back, 0.0);
+        }
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        let source = gradient(6, 5);
+        let step = 1.0 / 255.0;
+
+        for layout in [TextureLayout::Linear, TextureLayout::Tiled] {
+            let rgba8 = source.convert(TextureFormat::Rgba8, layout);
+            assert_same_texels(&source, &rgba8, 1e-6);
+            assert_same_texels(&source, &rgba8.convert(TextureFormat::Rgba32F, TextureLayout::Linear), 1e-6);
+
+            // Through sRGB bytes and back, each channel within one step
+            let srgb = source.convert(TextureFormat::Rgba8Srgb, layout);
+            let back = srgb.convert(TextureFormat::Rgba32F, TextureLayout::Linear);
+            assert_eq!(back.color_space(), ColorSpace::Srgb);
+            assert_same_texels(&source, &back, step);
+            assert_same_texels(&source, &srgb.convert(TextureFormat::Rgba8, layout), step);
+        }
+
+        // Linear float data keeps its values when stored as sRGB bytes, up to the byte precision
+        let linear = source.clone().with_color_space(ColorSpace::Linear);
+        let srgb = linear.convert(TextureFormat::Rgba8Srgb, TextureLayout::Tiled);
+        for y in 0..5 {
+            for x in 0..6 {
+                assert!((srgb.get_pixel_linear(x, y) - linear.get_pixel(x, y)).length() < 0.01);
+            }
+        }
+
+        // Smaller formats keep the channels they have
+        let r8 = source.convert(TextureFormat::R8, TextureLayout::Tiled);
+        let rg16 = source.convert(TextureFormat::Rg16F, TextureLayout::Linear);
+        for y in 0..5 {
+            for x in 0..6 {
+                let c = source.get_pixel(x, y);
+                assert_eq!(r8.get_pixel(x, y), Float4::new(c.x, 0.0, 0.0, 1.0));
+                let rg = rg16.get_pixel(x, y);
+                assert!((rg.x - c.x).abs() < 1e-3 && (rg.y - c.y).abs() < 1e-3 && rg.z == 0.0 && rg.w == 1.0);
+            }
+        }
+        assert_eq!(r8.size_in_bytes(), 2 * 2 * 16);
+        assert_eq!(rg16.size_in_bytes(), 6 * 5 * 4);
+    }
+
+    #[test]
+    fn srgb_bytes_decode_to_linear() {
+        let texture = Texture::from_rgba8(1, 1, vec![[128, 0, 255, 128]]).compact();
+        assert_eq!(texture.format(), TextureFormat::Rgba8Srgb);
+        assert_eq!(texture.layout(), TextureLayout::Tiled);
+        assert_eq!(texture.color_space(), ColorSpace::Linear);
+
+        let half = 128.0 / 255.0;
+        assert_eq!(texture.get_pixel(0, 0), Float4::new(mathf::srgb_to_linear(half), 0.0, 1.0, half));
+        assert_eq!(texture.get_pixel_linear(0, 0), texture.get_pixel(0, 0));
+        assert_eq!(texture.get_pixel_srgb(0, 0), Float4::new(half, 0.0, 1.0, half));
+
+        // Linear 8-bit data isn't sRGB, compacting only tiles it
+        let data = Texture::from_rgba8(1, 1, vec![[128, 0, 255, 128]]).with_color_space(ColorSpace::Linear).compact();
+        assert_eq!(data.format(), TextureFormat::Rgba8);
+        assert_eq!(data.get_pixel(0, 0), Float4::new(half, 0.0, 1.0, half));
+    }
+
+    #[test]
+    fn samples_wrap_and_filter_in_linear() {
+        let texture = Texture::from_rgba8(2, 1, vec![[0, 0, 0, 255], [255, 255, 255, 255]]).compact();
+        assert_eq!(texture.sample(0.25, 0.5, TextureFilter::Nearest), Float4::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(texture.sample(-0.25, 0.5, TextureFilter::Nearest), Float4::ONE);
+
+        // Halfway between black and white is half the light, brighter than 0.5 once encoded
+        let middle = texture.sample(0.5, 0.5, TextureFilter::Bilinear);
+        assert!((middle.x - mathf::linear_to_srgb(0.5)).abs() < 1e-3, "{middle:?}");
+        assert_eq!(middle.w, 1.0);
+    }
 }
\ No newline at end of file
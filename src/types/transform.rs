@@ -56,6 +56,15 @@ impl Transform {
         self.set_pos_rot_scale(self.position, self.rotation + delta_rotation, self.scale);
     }
 
+    // Turns the transform to look along `forward`, without roll
+    #[allow(dead_code)]
+    pub fn set_forward(&mut self, forward: Float3) {
+        let forward = forward.normalize();
+        let pitch = forward.y.clamp(-1.0, 1.0).asin();
+        let yaw = forward.x.atan2(forward.z);
+        self.set_rotation(Float3::new(pitch, yaw, 0.0));
+    }
+
     #[allow(dead_code)]
     pub fn set_scale(&mut self, scale: Float3) {
         self.set_pos_rot_scale(self.position, self.rotation, scale);
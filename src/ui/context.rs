@@ -0,0 +1,363 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use minifb::MouseButton;
+
+use crate::core::input;
+use crate::math::numerics::{float2::Float2, float4::Float4};
+use crate::rasterizer::render_target::RenderTarget;
+use crate::ui::font::BitmapFont;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    pub text: Float4,
+    pub panel: Float4,
+    pub widget: Float4,
+    pub widget_hot: Float4,    // under the mouse
+    pub widget_active: Float4, // held down
+    pub accent: Float4,        // checkmarks and slider fills
+    pub padding: u32,
+    pub spacing: u32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            text: Float4::new(0.95, 0.95, 0.95, 1.0),
+            panel: Float4::new(0.08, 0.08, 0.1, 0.75),
+            widget: Float4::new(0.25, 0.25, 0.3, 0.9),
+            widget_hot: Float4::new(0.35, 0.35, 0.42, 0.9),
+            widget_active: Float4::new(0.2, 0.45, 0.75, 0.9),
+            accent: Float4::new(0.3, 0.6, 0.95, 1.0),
+            padding: 4,
+            spacing: 3,
+        }
+    }
+}
+
+// Window pixels, top left corner and y down like the mouse position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn contains(&self, point: Float2) -> bool {
+        point.x >= self.x as f32 && point.x < (self.x + self.width as i32) as f32
+            && point.y >= self.y as f32 && point.y < (self.y + self.height as i32) as f32
+    }
+}
+
+enum DrawCommand {
+    Rect(Rect, Float4),
+    Text(i32, i32, String, Float4),
+}
+
+// Panel being filled, widgets stack downwards inside it
+struct Panel {
+    id: u64,
+    rect: Rect,
+    cursor_y: i32,
+    background: usize, // command index, resized once the panel's height is known
+}
+
+// Immediate-mode UI: widgets are declared every frame during update and return what the mouse did
+// to them, nothing is retained except which widget is held. What they draw is queued and drawn over
+// the frame by `render`.
+//
+//   ui.begin_frame();
+//   ui.begin_panel("Debug", 8, 8, 200);
+//   ui.label(&format!("Fps: {fps:.0}"));
+//   if ui.button("Reset") { ... }
+//   ui.checkbox("Wireframe", &mut wireframe);
+//   ui.slider("Speed", &mut speed, 0.0, 10.0);
+//   ui.end_panel();
+//   ui.end_frame();
+//
+// Widgets are told apart by panel title and label, `Name##2` shows `Name` but gets its own id.
+pub struct Ui {
+    pub style: Style,
+    pub font: BitmapFont,
+
+    commands: Vec<DrawCommand>,
+    panel: Option<Panel>,
+
+    mouse: Float2,
+    mouse_pressed: bool,
+    mouse_down: bool, // went down this frame
+    mouse_up: bool,
+    active: Option<u64>,
+    over_ui: bool,
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Self {
+            style: Style::default(),
+            font: BitmapFont::builtin(),
+            commands: Vec::new(),
+            panel: None,
+            mouse: Float2::ZERO,
+            mouse_pressed: false,
+            mouse_down: false,
+            mouse_up: false,
+            active: None,
+            over_ui: false,
+        }
+    }
+
+    // Reads this frame's mouse from core::input and forgets last frame's widgets
+    pub fn begin_frame(&mut self) {
+        self.begin_frame_with(input::mouse_position(), input::is_mouse_pressed(MouseButton::Left));
+    }
+
+    // Same with the mouse given directly, `pressed` is whether the left button is held
+    pub fn begin_frame_with(&mut self, mouse: Float2, pressed: bool) {
+        self.commands.clear();
+        self.panel = None;
+        self.over_ui = false;
+
+        self.mouse = mouse;
+        self.mouse_down = pressed && !self.mouse_pressed;
+        self.mouse_up = !pressed && self.mouse_pressed;
+        self.mouse_pressed = pressed;
+    }
+
+    pub fn end_frame(&mut self) {
+        assert!(self.panel.is_none(), "UI frame ended inside a panel");
+        if !self.mouse_pressed {
+            self.active = None;
+        }
+    }
+
+    // True while the mouse is over a panel or holds a widget, the scene should ignore it then
+    pub fn wants_mouse(&self) -> bool {
+        self.over_ui || self.active.is_some()
+    }
+
+    pub fn begin_panel(&mut self, title: &str, x: i32, y: i32, width: u32) {
+        assert!(self.panel.is_none(), "Panels can't be nested");
+        let background = self.commands.len();
+        self.commands.push(DrawCommand::Rect(Rect::new(x, y, width, 0), self.style.panel));
+
+        let padding = self.style.padding as i32;
+        self.commands.push(DrawCommand::Text(x + padding, y + padding, display_text(title).to_string(), self.style.accent));
+        let cursor_y = y + padding + self.font.line_height() as i32 + self.style.spacing as i32;
+
+        self.panel = Some(Panel { id: hash(title), rect: Rect::new(x, y, width, 0), cursor_y, background });
+    }
+
+    pub fn end_panel(&mut self) {
+        let mut panel = self.panel.take().expect("end_panel without begin_panel");
+        panel.rect.height = (panel.cursor_y - panel.rect.y) as u32 + self.style.padding - self.style.spacing;
+        if let DrawCommand::Rect(rect, _) = &mut self.commands[panel.background] {
+            *rect = panel.rect;
+        }
+        if panel.rect.contains(self.mouse) {
+            self.over_ui = true;
+        }
+    }
+
+    pub fn label(&mut self, text: &str) {
+        let (_, height) = self.font.measure(text);
+        let rect = self.next_rect(height);
+        self.commands.push(DrawCommand::Text(rect.x, rect.y, text.to_string(), self.style.text));
+    }
+
+    // True on the frame it's clicked: pressed and released over it
+    pub fn button(&mut self, label: &str) -> bool {
+        let id = self.id(label);
+        let rect = self.next_rect(self.widget_height());
+        let clicked = self.interact(id, rect);
+
+        self.commands.push(DrawCommand::Rect(rect, self.widget_color(id, rect)));
+        self.push_text_in(rect, display_text(label));
+        clicked
+    }
+
+    // True when the value changed
+    pub fn checkbox(&mut self, label: &str, value: &mut bool) -> bool {
+        let id = self.id(label);
+        let rect = self.next_rect(self.widget_height());
+        let clicked = self.interact(id, rect);
+        if clicked {
+            *value = !*value;
+        }
+
+        let box_rect = Rect::new(rect.x, rect.y, rect.height, rect.height);
+        self.commands.push(DrawCommand::Rect(box_rect, self.widget_color(id, rect)));
+        if *value {
+            let inset = self.style.padding as i32;
+            let size = rect.height - self.style.padding * 2;
+            self.commands.push(DrawCommand::Rect(Rect::new(rect.x + inset, rect.y + inset, size, size), self.style.accent));
+        }
+
+        let text_rect = Rect::new(rect.x + rect.height as i32, rect.y, rect.width.saturating_sub(rect.height), rect.height);
+        self.push_text_in(text_rect, display_text(label));
+        clicked
+    }
+
+    // Drag to set `value` between `min` and `max`, true when it changed
+    pub fn slider(&mut self, label: &str, value: &mut f32, min: f32, max: f32) -> bool {
+        let id = self.id(label);
+        let rect = self.next_rect(self.widget_height());
+        self.interact(id, rect);
+
+        let old = *value;
+        if self.active == Some(id) && rect.width > 0 {
+            let t = ((self.mouse.x - rect.x as f32) / rect.width as f32).clamp(0.0, 1.0);
+            *value = min + t * (max - min);
+        }
+
+        let t = if max > min { ((*value - min) / (max - min)).clamp(0.0, 1.0) } else { 0.0 };
+        self.commands.push(DrawCommand::Rect(rect, self.widget_color(id, rect)));
+        let fill = Rect::new(rect.x, rect.y, (rect.width as f32 * t).round() as u32, rect.height);
+        self.commands.push(DrawCommand::Rect(fill, Float4 { w: 0.6, ..self.style.accent }));
+        self.push_text_in(rect, &format!("{}: {:.2}", display_text(label), *value));
+        *value != old
+    }
+
+    // Draws everything declared since `begin_frame` over the render target
+    pub fn render(&self, render_target: &mut RenderTarget) {
+        for command in &self.commands {
+            match command {
+                DrawCommand::Rect(rect, color) => render_target.fill_rect(rect.x, rect.y, rect.width, rect.height, *color),
+                DrawCommand::Text(x, y, text, color) => self.font.draw(render_target, text, *x, *y, *color),
+            }
+        }
+    }
+
+    fn widget_height(&self) -> u32 {
+        self.font.char_width() + self.style.padding * 2
+    }
+
+    // Full panel width at the layout cursor, moves the cursor below it
+    fn next_rect(&mut self, height: u32) -> Rect {
+        let padding = self.style.padding;
+        let spacing = self.style.spacing as i32;
+        let panel = self.panel.as_mut().expect("Widgets need a panel");
+        let rect = Rect::new(panel.rect.x + padding as i32, panel.cursor_y, panel.rect.width.saturating_sub(padding * 2), height);
+        panel.cursor_y += height as i32 + spacing;
+        rect
+    }
+
+    fn id(&self, label: &str) -> u64 {
+        let panel = self.panel.as_ref().map_or(0, |p| p.id);
+        hash(&(panel, label))
+    }
+
+    // Grabs the widget when pressed over it, true when released over it again
+    fn interact(&mut self, id: u64, rect: Rect) -> bool {
+        let hovered = rect.contains(self.mouse);
+        if hovered && self.mouse_down {
+            self.active = Some(id);
+        }
+        self.active == Some(id) && hovered && self.mouse_up
+    }
+
+    fn widget_color(&self, id: u64, rect: Rect) -> Float4 {
+        if self.active == Some(id) {
+            self.style.widget_active
+        } else if self.active.is_none() && rect.contains(self.mouse) {
+            self.style.widget_hot
+        } else {
+            self.style.widget
+        }
+    }
+
+    // Vertically centered, left aligned after the padding
+    fn push_text_in(&mut self, rect: Rect, text: &str) {
+        let (_, height) = self.font.measure(text);
+        let y = rect.y + (rect.height as i32 - height as i32) / 2;
+        self.commands.push(DrawCommand::Text(rect.x + self.style.padding as i32, y, text.to_string(), self.style.text));
+    }
+}
+
+// Part of a label that's shown, `##` starts the part that only goes into the id
+fn display_text(label: &str) -> &str {
+    label.split("##").next().unwrap()
+}
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One frame with a panel at the origin holding a button, a checkbox and a slider
+    fn frame(ui: &mut Ui, mouse: Float2, pressed: bool, checked: &mut bool, value: &mut f32) -> bool {
+        ui.begin_frame_with(mouse, pressed);
+        ui.begin_panel("Test", 0, 0, 108);
+        let clicked = ui.button("Button");
+        ui.checkbox("Check", checked);
+        ui.slider("Value", value, 0.0, 10.0);
+        ui.end_panel();
+        ui.end_frame();
+        clicked
+    }
+
+    #[test]
+    fn widgets_follow_the_mouse() {
+        let mut ui = Ui::new();
+        let (mut checked, mut value) = (false, 0.0);
+
+        // Title row is 4 + 10 + 3 = 17 pixels, then rows of 16 + 3
+        let button = Float2::new(20.0, 20.0);
+        let checkbox = Float2::new(8.0, 40.0);
+        let slider = Float2::new(54.0, 60.0);
+
+        assert!(!frame(&mut ui, button, false, &mut checked, &mut value));
+        assert!(ui.wants_mouse());
+        assert!(!frame(&mut ui, button, true, &mut checked, &mut value));
+        assert!(frame(&mut ui, button, false, &mut checked, &mut value));
+
+        // Released somewhere else isn't a click
+        frame(&mut ui, checkbox, true, &mut checked, &mut value);
+        frame(&mut ui, Float2::new(500.0, 500.0), false, &mut checked, &mut value);
+        assert!(!checked);
+        frame(&mut ui, checkbox, true, &mut checked, &mut value);
+        frame(&mut ui, checkbox, false, &mut checked, &mut value);
+        assert!(checked);
+
+        // The slider keeps following while held, even outside of it
+        frame(&mut ui, slider, true, &mut checked, &mut value);
+        assert!((value - 5.0).abs() < 1e-5);
+        frame(&mut ui, Float2::new(500.0, 500.0), true, &mut checked, &mut value);
+        assert_eq!(value, 10.0);
+        assert!(ui.wants_mouse());
+        frame(&mut ui, Float2::new(500.0, 500.0), false, &mut checked, &mut value);
+        assert!(!ui.wants_mouse());
+    }
+
+    #[test]
+    fn renders_the_panel_behind_its_widgets() {
+        let mut ui = Ui::new();
+        let (mut checked, mut value) = (true, 5.0);
+        frame(&mut ui, Float2::new(500.0, 500.0), false, &mut checked, &mut value);
+
+        let mut render_target = RenderTarget::new(128, 128);
+        ui.render(&mut render_target);
+
+        // Panel is 4 + 10 + 3 + 3 * 19 - 3 + 4 = 75 pixels tall, the buffer's y is up
+        assert!(render_target.get_pixel_color(106, 127).w > 0.0);
+        assert!(render_target.get_pixel_color(106, 128 - 75).w > 0.0);
+        assert_eq!(render_target.get_pixel_color(106, 127 - 75).w, 0.0);
+        assert_eq!(render_target.get_pixel_color(108, 127).w, 0.0);
+    }
+}
\ No newline at end of file